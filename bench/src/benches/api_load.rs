@@ -1,20 +1,18 @@
 use log::{info, warn};
-use std::{
-    path::Path,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
 };
 
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::signature::{Keypair, Signer};
 
 use crate::create_memo_tx_small;
+use crate::payer::PayerSource;
 
 // TC3 measure how much load the API endpoint can take
 pub async fn api_load(
-    payer_path: &Path,
+    payer: &PayerSource,
     rpc_url: String,
     test_duration_ms: u64,
     cu_price_micro_lamports: u64,
@@ -24,7 +22,7 @@ pub async fn api_load(
     let rpc = Arc::new(RpcClient::new(rpc_url));
     info!("RPC: {}", rpc.as_ref().url());
 
-    let payer: Arc<Keypair> = Arc::new(read_keypair_file(payer_path).unwrap());
+    let payer: Arc<Keypair> = Arc::new(payer.load()?);
     info!("Payer: {}", payer.pubkey().to_string());
 
     let mut txs = 0;
@@ -1,17 +1,21 @@
+use crate::metrics::{PingThing, PingThingTxType};
+use crate::payer::PayerSource;
 use crate::{create_rng, generate_txs, BenchmarkTransactionParams};
 use anyhow::Context;
 use log::{debug, info, trace, warn};
+use std::collections::BTreeMap;
 use std::ops::Add;
-use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::benches::rpc_interface::{
-    send_and_confirm_bulk_transactions, ConfirmationResponseFromRpc,
+    send_and_confirm_bulk_transactions, simulate_bulk_transactions, BulkTransactionsResult,
+    ConfirmationResponseFromRpc, SendThrottle, SimulationResponseFromRpc,
 };
 use solana_lite_rpc_util::obfuscate_rpcurl;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signer};
 use url::Url;
 
 #[derive(Clone, Copy, Debug, Default, serde::Serialize)]
@@ -24,32 +28,61 @@ pub struct Metric {
     pub average_slot_confirmation_time: f32,
     pub txs_send_errors: u64,
     pub txs_un_confirmed: u64,
+    // txs_un_confirmed the RPC never had a status for (dropped) as opposed to confirming too slow
+    pub txs_never_seen: u64,
+    // breakdown of txs_send_errors
+    pub txs_send_errors_rate_limited: u64,
+    pub txs_send_errors_node_unhealthy: u64,
+    // sends retried after being rate limited
+    pub txs_send_throttled: u64,
+    // slots passed between send slot and completion of the send_transaction call
+    pub average_slots_passed_while_sending: f32,
+    pub max_slots_passed_while_sending: u64,
 }
 
+#[allow(clippy::too_many_arguments)]
 /// TC2 send multiple runs of num_txs, measure the confirmation rate
 pub async fn confirmation_rate(
-    payer_path: &Path,
+    payer: &PayerSource,
     rpc_url: String,
     tx_status_websocket_addr: Option<String>,
     tx_params: BenchmarkTransactionParams,
     max_timeout: Duration,
     txs_per_run: usize,
     num_of_runs: usize,
+    send_throttle: SendThrottle,
+    simulate_only: bool,
+    maybe_ping_thing: Option<PingThing>,
 ) -> anyhow::Result<()> {
     warn!("THIS IS WORK IN PROGRESS");
 
     assert!(num_of_runs > 0, "num_of_runs must be greater than 0");
 
-    let rpc = Arc::new(RpcClient::new(rpc_url.clone()));
+    let rpc = Arc::new(RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
     info!("RPC: {}", obfuscate_rpcurl(&rpc.as_ref().url()));
 
+    if let Some(ping_thing) = &maybe_ping_thing {
+        ping_thing
+            .validate_rpc_cluster(&rpc)
+            .await
+            .context("validate ping-thing cluster")?;
+    }
+
     let ws_addr = tx_status_websocket_addr
         .unwrap_or_else(|| rpc_url.replace("http:", "ws:").replace("https:", "wss:"));
     info!("WS ADDR: {}", obfuscate_rpcurl(&ws_addr));
 
-    let payer: Arc<Keypair> = Arc::new(read_keypair_file(payer_path).unwrap());
+    let payer: Arc<Keypair> = Arc::new(payer.load()?);
     info!("Payer: {}", payer.pubkey().to_string());
 
+    if simulate_only {
+        info!("Simulate only - no transactions will be sent");
+        return simulate_bulk_txs(&rpc, &payer, txs_per_run, &tx_params, &send_throttle).await;
+    }
+
     let mut rpc_results = Vec::with_capacity(num_of_runs);
 
     for _ in 0..num_of_runs {
@@ -60,6 +93,8 @@ pub async fn confirmation_rate(
             txs_per_run,
             &tx_params,
             max_timeout,
+            &send_throttle,
+            maybe_ping_thing.as_ref(),
         )
         .await
         .context("send bulk tx and wait")
@@ -91,6 +126,8 @@ pub async fn send_bulk_txs_and_wait(
     num_txs: usize,
     tx_params: &BenchmarkTransactionParams,
     max_timeout: Duration,
+    send_throttle: &SendThrottle,
+    maybe_ping_thing: Option<&PingThing>,
 ) -> anyhow::Result<Metric> {
     trace!("Get latest blockhash and generate transactions");
     let hash = rpc.get_latest_blockhash().await.map_err(|err| {
@@ -101,25 +138,65 @@ pub async fn send_bulk_txs_and_wait(
     let txs = generate_txs(num_txs, payer, hash, &mut rng, tx_params);
 
     trace!("Sending {} transactions in bulk ..", txs.len());
-    let tx_and_confirmations_from_rpc: Vec<(Signature, ConfirmationResponseFromRpc)> =
-        send_and_confirm_bulk_transactions(
-            rpc,
-            tx_status_websocket_addr,
-            payer.pubkey(),
-            &txs,
-            max_timeout,
-        )
-        .await
-        .context("send and confirm bulk tx")?;
+    let BulkTransactionsResult {
+        confirmations: tx_and_confirmations_from_rpc,
+        num_sends_throttled,
+    } = send_and_confirm_bulk_transactions(
+        rpc,
+        tx_status_websocket_addr,
+        payer.pubkey(),
+        &txs,
+        max_timeout,
+        send_throttle,
+        None,
+    )
+    .await
+    .context("send and confirm bulk tx")?;
     trace!("Done sending {} transaction.", txs.len());
 
+    if let Some(ping_thing) = maybe_ping_thing {
+        let num_submitted = ping_thing
+            .submit_bulk_confirmations(&tx_and_confirmations_from_rpc, PingThingTxType::Memo)
+            .await;
+        debug!("Submitted {} confirmed txs to ping-thing", num_submitted);
+    }
+
     let mut tx_sent = 0;
     let mut tx_send_errors = 0;
+    let mut tx_send_errors_rate_limited = 0;
+    let mut tx_send_errors_node_unhealthy = 0;
     let mut tx_confirmed = 0;
     let mut tx_unconfirmed = 0;
+    let mut tx_never_seen = 0;
     let mut sum_confirmation_time = Duration::default();
     let mut sum_slot_confirmation_time = 0;
-    for (tx_sig, confirmation_response) in tx_and_confirmations_from_rpc {
+    let mut sum_slots_passed_while_sending = 0;
+    let mut max_slots_passed_while_sending = 0;
+    // slots passed while sending -> (txs sent, txs confirmed)
+    let mut landing_by_slots_passed: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+    let num_results = tx_and_confirmations_from_rpc.len();
+    for (tx_sig, slots_passed_while_sending, confirmation_response) in tx_and_confirmations_from_rpc
+    {
+        sum_slots_passed_while_sending += slots_passed_while_sending;
+        max_slots_passed_while_sending =
+            max_slots_passed_while_sending.max(slots_passed_while_sending);
+        let landing = landing_by_slots_passed
+            .entry(slots_passed_while_sending)
+            .or_default();
+        landing.0 += 1;
+        if matches!(
+            confirmation_response,
+            ConfirmationResponseFromRpc::Success(..)
+        ) {
+            landing.1 += 1;
+        }
+
+        if confirmation_response.is_rate_limited() {
+            tx_send_errors_rate_limited += 1;
+        } else if confirmation_response.is_node_unhealthy() {
+            tx_send_errors_node_unhealthy += 1;
+        }
+
         match confirmation_response {
             ConfirmationResponseFromRpc::Success(
                 slot_sent,
@@ -146,6 +223,13 @@ pub async fn send_bulk_txs_and_wait(
                 );
                 tx_send_errors += 1;
             }
+            ConfirmationResponseFromRpc::MinContextSlotNotReached(send_slot, context_slot) => {
+                debug!(
+                    "Signature {} rejected by RPC lagging behind send slot {} (context slot {:?})",
+                    tx_sig, send_slot, context_slot
+                );
+                tx_send_errors += 1;
+            }
             ConfirmationResponseFromRpc::Timeout(elapsed) => {
                 debug!(
                     "Signature {} not confirmed after {:.03}s",
@@ -155,9 +239,31 @@ pub async fn send_bulk_txs_and_wait(
                 tx_sent += 1;
                 tx_unconfirmed += 1;
             }
+            ConfirmationResponseFromRpc::NeverSeen(elapsed) => {
+                debug!(
+                    "Signature {} unknown to the RPC after {:.03}s - dropped",
+                    tx_sig,
+                    elapsed.as_secs_f32()
+                );
+                tx_sent += 1;
+                tx_unconfirmed += 1;
+                tx_never_seen += 1;
+            }
         }
     }
 
+    for (slots_passed, (sent, confirmed)) in &landing_by_slots_passed {
+        info!(
+            "{} slots passed while sending: {} of {} txs confirmed",
+            slots_passed, confirmed, sent
+        );
+    }
+    let average_slots_passed_while_sending = if num_results > 0 {
+        sum_slots_passed_while_sending as f32 / num_results as f32
+    } else {
+        0.0
+    };
+
     let average_confirmation_time_ms = if tx_confirmed > 0 {
         sum_confirmation_time.as_secs_f32() * 1000.0 / tx_confirmed as f32
     } else {
@@ -174,11 +280,79 @@ pub async fn send_bulk_txs_and_wait(
         txs_send_errors: tx_send_errors,
         txs_confirmed: tx_confirmed,
         txs_un_confirmed: tx_unconfirmed,
+        txs_never_seen: tx_never_seen,
         average_confirmation_time: average_confirmation_time_ms,
         average_slot_confirmation_time,
+        txs_send_errors_rate_limited: tx_send_errors_rate_limited,
+        txs_send_errors_node_unhealthy: tx_send_errors_node_unhealthy,
+        txs_send_throttled: num_sends_throttled,
+        average_slots_passed_while_sending,
+        max_slots_passed_while_sending,
     })
 }
 
+/// dry-run: simulate one batch of transactions and report compute units and simulation errors
+pub async fn simulate_bulk_txs(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    num_txs: usize,
+    tx_params: &BenchmarkTransactionParams,
+    send_throttle: &SendThrottle,
+) -> anyhow::Result<()> {
+    trace!("Get latest blockhash and generate transactions");
+    let hash = rpc
+        .get_latest_blockhash()
+        .await
+        .context("get latest blockhash")?;
+    let mut rng = create_rng(None);
+    let txs = generate_txs(num_txs, payer, hash, &mut rng, tx_params);
+
+    trace!("Simulating {} transactions in bulk ..", txs.len());
+    let simulation_results = simulate_bulk_transactions(rpc, &txs, send_throttle).await;
+
+    let mut txs_simulated_ok = 0;
+    let mut txs_simulation_failed = 0;
+    let mut txs_rpc_errors = 0;
+    let mut sum_units_consumed = 0;
+    for (tx_sig, simulation_response) in simulation_results {
+        match simulation_response {
+            SimulationResponseFromRpc::Success(units_consumed) => {
+                debug!(
+                    "Signature {} simulated successfully, consumed {:?} CU",
+                    tx_sig, units_consumed
+                );
+                txs_simulated_ok += 1;
+                sum_units_consumed += units_consumed.unwrap_or_default();
+            }
+            SimulationResponseFromRpc::SimulationError(err, units_consumed, logs) => {
+                info!(
+                    "Signature {} failed simulation after {:?} CU: {} - logs: {:?}",
+                    tx_sig, units_consumed, err, logs
+                );
+                txs_simulation_failed += 1;
+            }
+            SimulationResponseFromRpc::SendError(error_kind) => {
+                info!(
+                    "Signature {} failed to get simulated via RPC: {:?}",
+                    tx_sig, error_kind
+                );
+                txs_rpc_errors += 1;
+            }
+        }
+    }
+
+    let average_units_consumed = if txs_simulated_ok > 0 {
+        sum_units_consumed / txs_simulated_ok
+    } else {
+        0
+    };
+    info!(
+        "simulated: {} ok (avg {} CU), {} failed simulation, {} rpc errors",
+        txs_simulated_ok, average_units_consumed, txs_simulation_failed, txs_rpc_errors
+    );
+    Ok(())
+}
+
 fn calc_stats_avg(stats: &[Metric]) -> Metric {
     let len = stats.len();
 
@@ -187,8 +361,14 @@ fn calc_stats_avg(stats: &[Metric]) -> Metric {
         txs_send_errors: 0,
         txs_confirmed: 0,
         txs_un_confirmed: 0,
+        txs_never_seen: 0,
         average_confirmation_time: 0.0,
         average_slot_confirmation_time: 0.0,
+        txs_send_errors_rate_limited: 0,
+        txs_send_errors_node_unhealthy: 0,
+        txs_send_throttled: 0,
+        average_slots_passed_while_sending: 0.0,
+        max_slots_passed_while_sending: 0,
     };
 
     for stat in stats {
@@ -196,16 +376,29 @@ fn calc_stats_avg(stats: &[Metric]) -> Metric {
         avg.txs_send_errors += stat.txs_send_errors;
         avg.txs_confirmed += stat.txs_confirmed;
         avg.txs_un_confirmed += stat.txs_un_confirmed;
+        avg.txs_never_seen += stat.txs_never_seen;
         avg.average_confirmation_time += stat.average_confirmation_time;
         avg.average_slot_confirmation_time += stat.average_slot_confirmation_time;
+        avg.txs_send_errors_rate_limited += stat.txs_send_errors_rate_limited;
+        avg.txs_send_errors_node_unhealthy += stat.txs_send_errors_node_unhealthy;
+        avg.txs_send_throttled += stat.txs_send_throttled;
+        avg.average_slots_passed_while_sending += stat.average_slots_passed_while_sending;
+        avg.max_slots_passed_while_sending = avg
+            .max_slots_passed_while_sending
+            .max(stat.max_slots_passed_while_sending);
     }
 
     avg.txs_sent /= len as u64;
     avg.txs_send_errors /= len as u64;
     avg.txs_confirmed /= len as u64;
     avg.txs_un_confirmed /= len as u64;
+    avg.txs_never_seen /= len as u64;
     avg.average_confirmation_time /= len as f32;
     avg.average_slot_confirmation_time /= len as f32;
+    avg.txs_send_errors_rate_limited /= len as u64;
+    avg.txs_send_errors_node_unhealthy /= len as u64;
+    avg.txs_send_throttled /= len as u64;
+    avg.average_slots_passed_while_sending /= len as f32;
 
     avg
 }
@@ -1,19 +1,23 @@
-use std::path::Path;
+use std::future::Future;
 use std::time::Duration;
 
+use crate::benches::result_sink::BenchResultSink;
 use crate::benches::rpc_interface::{
-    create_rpc_client, send_and_confirm_bulk_transactions, ConfirmationResponseFromRpc,
+    check_client_commitment, create_rpc_client, is_blockhash_not_found,
+    send_and_confirm_bulk_transactions, ConfirmationResponseFromRpc, SendThrottle,
 };
-use crate::metrics::PingThing;
+use crate::payer::PayerSource;
 use crate::{create_memo_tx, create_rng, BenchmarkTransactionParams, Rng8};
 use anyhow::anyhow;
 use log::{debug, info, warn};
+use rand::Rng;
 use solana_lite_rpc_util::obfuscate_rpcurl;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{read_keypair_file, Signature, Signer};
+use solana_sdk::signature::{Signature, Signer};
 use solana_sdk::transaction::VersionedTransaction;
 use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
+use std::sync::Arc;
 use tokio::time::{sleep, Instant};
 use url::Url;
 
@@ -26,25 +30,125 @@ pub struct Metric {
     pub average_time_to_send_txs: f64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub enum ConfirmationSlotResult {
     Success(ConfirmationSlotSuccess),
+    // not confirmed within max_timeout
+    Timeout(Duration),
+    // the RPC had no status for the tx, i.e. it was dropped
+    NeverSeen(Duration),
+    // send_transaction was rejected by the RPC
+    SendError(String),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct ConfirmationSlotSuccess {
+    #[serde(serialize_with = "serialize_signature")]
+    pub signature: Signature,
     pub slot_sent: u64,
     pub slot_confirmed: u64,
     pub confirmation_time: Duration,
 }
 
+fn serialize_signature<S: serde::Serializer>(
+    signature: &Signature,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(signature)
+}
+
+impl ConfirmationSlotSuccess {
+    pub fn slots_to_land(&self) -> u64 {
+        self.slot_confirmed.saturating_sub(self.slot_sent)
+    }
+}
+
+// signature of the tx which was finally sent (might differ from the original after a rebuild)
+pub type TxConfirmation = (Signature, ConfirmationResponseFromRpc);
+
+impl ConfirmationSlotResult {
+    pub fn from_confirmation((signature, response): TxConfirmation) -> Self {
+        match response {
+            ConfirmationResponseFromRpc::Success(
+                slot_sent,
+                slot_confirmed,
+                _,
+                confirmation_time,
+            ) => ConfirmationSlotResult::Success(ConfirmationSlotSuccess {
+                signature,
+                slot_sent,
+                slot_confirmed,
+                confirmation_time,
+            }),
+            ConfirmationResponseFromRpc::Timeout(elapsed) => {
+                ConfirmationSlotResult::Timeout(elapsed)
+            }
+            ConfirmationResponseFromRpc::NeverSeen(elapsed) => {
+                ConfirmationSlotResult::NeverSeen(elapsed)
+            }
+            ConfirmationResponseFromRpc::SendError(error_kind) => {
+                ConfirmationSlotResult::SendError(error_kind.to_string())
+            }
+            ConfirmationResponseFromRpc::MinContextSlotNotReached(send_slot, context_slot) => {
+                ConfirmationSlotResult::SendError(format!(
+                    "min context slot {send_slot} not reached (node at {context_slot:?})"
+                ))
+            }
+        }
+    }
+
+    /// None for timeouts, dropped txs and send errors - these must not be counted as landed
+    pub fn success(&self) -> Option<&ConfirmationSlotSuccess> {
+        match self {
+            ConfirmationSlotResult::Success(success) => Some(success),
+            ConfirmationSlotResult::Timeout(_)
+            | ConfirmationSlotResult::NeverSeen(_)
+            | ConfirmationSlotResult::SendError(_) => None,
+        }
+    }
+}
+
+/// outcome of one round: the same kind of tx sent to both RPCs
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ConfirmationSlotRoundResult {
+    pub round: usize,
+    pub rpc_a: ConfirmationSlotResult,
+    pub rpc_b: ConfirmationSlotResult,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundWinner {
+    RpcA,
+    RpcB,
+    // both landed in the same slot
+    Tie,
+    // neither tx landed
+    NoneLanded,
+}
+
+impl ConfirmationSlotRoundResult {
+    /// the tx which landed in the earlier slot wins; a tx that did not land never wins
+    pub fn winner(&self) -> RoundWinner {
+        match (self.rpc_a.success(), self.rpc_b.success()) {
+            (Some(a), Some(b)) => match a.slot_confirmed.cmp(&b.slot_confirmed) {
+                std::cmp::Ordering::Less => RoundWinner::RpcA,
+                std::cmp::Ordering::Greater => RoundWinner::RpcB,
+                std::cmp::Ordering::Equal => RoundWinner::Tie,
+            },
+            (Some(_), None) => RoundWinner::RpcA,
+            (None, Some(_)) => RoundWinner::RpcB,
+            (None, None) => RoundWinner::NoneLanded,
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 /// TC1 -- Send 2 txs to separate RPCs and compare confirmation slot.
 /// The benchmark attempts to minimize the effect of real-world distance and synchronize the time that each transaction reaches the RPC.
 /// This is achieved by delaying submission of the transaction to the "nearer" RPC.
 /// Delay time is calculated as half of the difference in duration of [getHealth](https://solana.com/docs/rpc/http/gethealth) calls to both RPCs.
 pub async fn confirmation_slot(
-    payer_path: &Path,
+    payer: &PayerSource,
     rpc_a_url: String,
     rpc_b_url: String,
     tx_status_websocket_addr_a: Option<String>,
@@ -52,8 +156,11 @@ pub async fn confirmation_slot(
     tx_params: BenchmarkTransactionParams,
     max_timeout: Duration,
     num_of_runs: usize,
-    _maybe_ping_thing: Option<PingThing>,
-) -> anyhow::Result<()> {
+    sinks: Vec<Box<dyn BenchResultSink>>,
+    preflight_commitment: Option<CommitmentConfig>,
+    commitment: Option<CommitmentConfig>,
+    seed: Option<u64>,
+) -> anyhow::Result<Vec<ConfirmationSlotRoundResult>> {
     info!(
         "START BENCHMARK: confirmation_slot (prio_fees={})",
         tx_params.cu_price_micro_lamports
@@ -61,6 +168,7 @@ pub async fn confirmation_slot(
     warn!("THIS IS WORK IN PROGRESS");
     info!("RPC A: {}", obfuscate_rpcurl(&rpc_a_url));
     info!("RPC B: {}", obfuscate_rpcurl(&rpc_b_url));
+    check_client_commitment(commitment)?;
 
     let ws_addr_a = tx_status_websocket_addr_a
         .unwrap_or_else(|| rpc_a_url.replace("http:", "ws:").replace("https:", "wss:"));
@@ -74,91 +182,158 @@ pub async fn confirmation_slot(
     let rpc_b_url =
         Url::parse(&rpc_b_url).map_err(|e| anyhow!("Failed to parse RPC B URL: {}", e))?;
 
-    let mut rng = create_rng(None);
-    let payer = read_keypair_file(payer_path).expect("payer file");
+    let seed = seed.unwrap_or_else(rand::random);
+    info!(
+        "Seed: {} (use --seed {} to reproduce the memo transactions)",
+        seed, seed
+    );
+    let mut round_seeds = round_seeds(seed);
+    let payer = Arc::new(payer.load()?);
     let payer_pubkey = payer.pubkey();
     info!("Payer: {}", payer_pubkey.to_string());
-    // let mut ping_thing_tasks = vec![];
 
     // FIXME
     // let (tx_status_map, jh_collector) = start_tx_status_collector(Url::parse(&tx_status_websocket_addr).unwrap(), payer.pubkey(), CommitmentConfig::confirmed()).await;
 
-    for _ in 0..num_of_runs {
-        let rpc_a = create_rpc_client(&rpc_a_url);
-        let rpc_b = create_rpc_client(&rpc_b_url);
-
-        let ws_addr_a = ws_addr_a.clone();
-        let ws_addr_b = ws_addr_b.clone();
-
-        // measure network time to reach the respective RPC endpoints,
-        // used to mitigate the difference in distance by delaying the txn sending
-        let time_a = rpc_roundtrip_duration(&rpc_a).await?.as_secs_f64();
-        let time_b = rpc_roundtrip_duration(&rpc_b).await?.as_secs_f64();
+    let rpc_a_url = &rpc_a_url;
+    let rpc_b_url = &rpc_b_url;
+    let tx_params = &tx_params;
+    let results = run_confirmation_slot_rounds(num_of_runs, &sinks, move |_round| {
+        run_confirmation_slot_round(
+            rpc_a_url,
+            rpc_b_url,
+            ws_addr_a.clone(),
+            ws_addr_b.clone(),
+            payer.clone(),
+            tx_params,
+            max_timeout,
+            preflight_commitment,
+            commitment,
+            round_seeds.next().expect("endless round seeds"),
+        )
+    })
+    .await?;
 
-        debug!("(A) rpc network latency: {}", time_a);
-        debug!("(B) rpc network latency: {}", time_b);
+    Ok(results)
+}
 
-        let rpc_a_tx = create_tx(&rpc_a, &payer, &mut rng, &tx_params).await?;
-        let rpc_b_tx = create_tx(&rpc_b, &payer, &mut rng, &tx_params).await?;
+/// seeds of the rounds; the memo transactions of a round are generated from its seed
+fn round_seeds(seed: u64) -> impl Iterator<Item = u64> {
+    let mut rng = create_rng(Some(seed));
+    std::iter::repeat_with(move || rng.gen())
+}
 
-        let one_way_delay = (time_a - time_b).abs() / 2.0;
-        let (a_delay, b_delay) = if time_a > time_b {
-            (0f64, one_way_delay)
-        } else {
-            (one_way_delay, 0f64)
+/// runs the rounds one after another; a failing round aborts the benchmark as both paths must succeed;
+/// each round result is recorded to all sinks, a failing sink does not abort the benchmark
+pub async fn run_confirmation_slot_rounds<F, Fut>(
+    num_of_runs: usize,
+    sinks: &[Box<dyn BenchResultSink>],
+    mut run_round: F,
+) -> anyhow::Result<Vec<ConfirmationSlotRoundResult>>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = anyhow::Result<(TxConfirmation, TxConfirmation)>>,
+{
+    let mut results = Vec::with_capacity(num_of_runs);
+    for round in 0..num_of_runs {
+        let (a_result, b_result) = run_round(round).await?;
+        let round_result = ConfirmationSlotRoundResult {
+            round,
+            rpc_a: ConfirmationSlotResult::from_confirmation(a_result),
+            rpc_b: ConfirmationSlotResult::from_confirmation(b_result),
         };
 
-        debug!("A delay: {}s, B delay: {}s", a_delay, b_delay);
-
-        let a_task = tokio::spawn(async move {
-            sleep(Duration::from_secs_f64(a_delay)).await;
-            debug!("(A) sending tx {}", rpc_a_tx.signatures[0]);
-            send_and_confirm_transaction(&rpc_a, ws_addr_a, payer_pubkey, rpc_a_tx, max_timeout)
-                .await
-        });
-
-        let b_task = tokio::spawn(async move {
-            sleep(Duration::from_secs_f64(b_delay)).await;
-            debug!("(B) sending tx {}", rpc_b_tx.signatures[0]);
-            send_and_confirm_transaction(&rpc_b, ws_addr_b, payer_pubkey, rpc_b_tx, max_timeout)
-                .await
-        });
-
-        let (a, b) = tokio::join!(a_task, b_task);
-        // only continue if both paths suceed
-        let a_result: ConfirmationResponseFromRpc = a??;
-        let b_result: ConfirmationResponseFromRpc = b??;
-
-        if let (
-            ConfirmationResponseFromRpc::Success(a_slot_sent, a_slot_confirmed, _, _),
-            ConfirmationResponseFromRpc::Success(b_slot_sent, b_slot_confirmed, _, _),
-        ) = (a_result, b_result)
-        {
-            info!(
-                "txn A landed after {} slots",
-                a_slot_confirmed - a_slot_sent
-            );
-            info!(
-                "txn B landed after {} slots",
-                b_slot_confirmed - b_slot_sent
-            );
+        for sink in sinks {
+            if let Err(err) = sink.record_run(&round_result).await {
+                warn!("Failed to record result of round {}: {:?}", round, err);
+            }
         }
 
-        // if let Some(ping_thing) = maybe_ping_thing.clone() {
-        //     ping_thing_tasks.push(tokio::spawn(async move {
-        //         submit_ping_thing_stats(&a_result, &ping_thing)
-        //             .await
-        //             .unwrap();
-        //         submit_ping_thing_stats(&b_result, &ping_thing)
-        //             .await
-        //             .unwrap();
-        //     }));
-        // };
+        results.push(round_result);
     }
+    Ok(results)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_confirmation_slot_round(
+    rpc_a_url: &Url,
+    rpc_b_url: &Url,
+    ws_addr_a: Url,
+    ws_addr_b: Url,
+    payer: Arc<Keypair>,
+    tx_params: &BenchmarkTransactionParams,
+    max_timeout: Duration,
+    preflight_commitment: Option<CommitmentConfig>,
+    commitment: Option<CommitmentConfig>,
+    seed: u64,
+) -> anyhow::Result<(TxConfirmation, TxConfirmation)> {
+    let mut rng = create_rng(Some(seed));
+    let rpc_a = create_rpc_client(rpc_a_url, commitment);
+    let rpc_b = create_rpc_client(rpc_b_url, commitment);
+
+    // measure network time to reach the respective RPC endpoints,
+    // used to mitigate the difference in distance by delaying the txn sending
+    let time_a = rpc_roundtrip_duration(&rpc_a).await?.as_secs_f64();
+    let time_b = rpc_roundtrip_duration(&rpc_b).await?.as_secs_f64();
+
+    debug!("(A) rpc network latency: {}", time_a);
+    debug!("(B) rpc network latency: {}", time_b);
+
+    let rpc_a_tx = create_tx(&rpc_a, &payer, &mut rng, tx_params).await?;
+    let rpc_b_tx = create_tx(&rpc_b, &payer, &mut rng, tx_params).await?;
 
-    // futures::future::join_all(ping_thing_tasks).await;
+    let one_way_delay = (time_a - time_b).abs() / 2.0;
+    let (a_delay, b_delay) = if time_a > time_b {
+        (0f64, one_way_delay)
+    } else {
+        (one_way_delay, 0f64)
+    };
 
-    Ok(())
+    debug!("A delay: {}s, B delay: {}s", a_delay, b_delay);
+
+    // used to rebuild the transaction in case the blockhash expired
+    let mut rng_a = create_rng(Some(rng.gen()));
+    let mut rng_b = create_rng(Some(rng.gen()));
+    let payer_a = payer.clone();
+    let payer_b = payer.clone();
+    let tx_params_a = tx_params.clone();
+    let tx_params_b = tx_params.clone();
+
+    let a_task = tokio::spawn(async move {
+        sleep(Duration::from_secs_f64(a_delay)).await;
+        debug!("(A) sending tx {}", rpc_a_tx.signatures[0]);
+        send_and_confirm_transaction_with_rebuild(
+            &rpc_a,
+            ws_addr_a,
+            &payer_a,
+            rpc_a_tx,
+            &mut rng_a,
+            &tx_params_a,
+            max_timeout,
+            preflight_commitment,
+        )
+        .await
+    });
+
+    let b_task = tokio::spawn(async move {
+        sleep(Duration::from_secs_f64(b_delay)).await;
+        debug!("(B) sending tx {}", rpc_b_tx.signatures[0]);
+        send_and_confirm_transaction_with_rebuild(
+            &rpc_b,
+            ws_addr_b,
+            &payer_b,
+            rpc_b_tx,
+            &mut rng_b,
+            &tx_params_b,
+            max_timeout,
+            preflight_commitment,
+        )
+        .await
+    });
+
+    let (a, b) = tokio::join!(a_task, b_task);
+    // only continue if both paths suceed
+    Ok((a??, b??))
 }
 
 async fn create_tx(
@@ -174,26 +349,77 @@ async fn create_tx(
     Ok(create_memo_tx(payer, blockhash, rng, tx_params))
 }
 
+/// the blockhash fetched in create_tx might expire before the transaction gets sent;
+/// in that case the transaction is rebuilt with a fresh blockhash and sent once more
+#[allow(clippy::too_many_arguments)]
+async fn send_and_confirm_transaction_with_rebuild(
+    rpc: &RpcClient,
+    tx_status_websocket_addr: Url,
+    payer: &Keypair,
+    tx: VersionedTransaction,
+    rng: &mut Rng8,
+    tx_params: &BenchmarkTransactionParams,
+    max_timeout: Duration,
+    preflight_commitment: Option<CommitmentConfig>,
+) -> anyhow::Result<TxConfirmation> {
+    let (tx_sig, confirmation_response) = send_and_confirm_transaction(
+        rpc,
+        tx_status_websocket_addr.clone(),
+        payer.pubkey(),
+        tx,
+        max_timeout,
+        preflight_commitment,
+    )
+    .await?;
+
+    match &confirmation_response {
+        ConfirmationResponseFromRpc::SendError(error_kind)
+            if is_blockhash_not_found(error_kind) =>
+        {
+            let rebuilt_tx = create_tx(rpc, payer, rng, tx_params).await?;
+            info!(
+                "Blockhash expired for tx {} - rebuilt as tx {} with fresh blockhash and retry once",
+                tx_sig, rebuilt_tx.signatures[0]
+            );
+            send_and_confirm_transaction(
+                rpc,
+                tx_status_websocket_addr,
+                payer.pubkey(),
+                rebuilt_tx,
+                max_timeout,
+                preflight_commitment,
+            )
+            .await
+        }
+        _ => Ok((tx_sig, confirmation_response)),
+    }
+}
+
 async fn send_and_confirm_transaction(
     rpc: &RpcClient,
     tx_status_websocket_addr: Url,
     payer_pubkey: Pubkey,
     tx: VersionedTransaction,
     max_timeout: Duration,
-) -> anyhow::Result<ConfirmationResponseFromRpc> {
-    let result_vec: Vec<(Signature, ConfirmationResponseFromRpc)> =
+    preflight_commitment: Option<CommitmentConfig>,
+) -> anyhow::Result<TxConfirmation> {
+    let result_vec: Vec<(Signature, u64, ConfirmationResponseFromRpc)> =
         send_and_confirm_bulk_transactions(
             rpc,
             tx_status_websocket_addr,
             payer_pubkey,
             &[tx],
             max_timeout,
+            &SendThrottle::default(),
+            preflight_commitment,
         )
-        .await?;
+        .await?
+        .confirmations;
     assert_eq!(result_vec.len(), 1, "expected 1 result");
-    let (_sig, confirmation_response) = result_vec.into_iter().next().unwrap();
+    let (sig, _slots_passed_while_sending, confirmation_response) =
+        result_vec.into_iter().next().unwrap();
 
-    Ok(confirmation_response)
+    Ok((sig, confirmation_response))
 }
 
 pub async fn rpc_roundtrip_duration(rpc: &RpcClient) -> anyhow::Result<Duration> {
@@ -203,23 +429,133 @@ pub async fn rpc_roundtrip_duration(rpc: &RpcClient) -> anyhow::Result<Duration>
     Ok(duration)
 }
 
-// async fn submit_ping_thing_stats(
-//     confirmation_info: &ConfirmationSlotResult,
-//     ping_thing: &PingThing,
-// ) -> anyhow::Result<()> {
-//     match confirmation_info.result {
-//         ConfirmationSlotResult::Timeout(_) => Ok(()),
-//         ConfirmationSlotResult::Success(slot_landed) => {
-//             ping_thing
-//                 .submit_confirmed_stats(
-//                     confirmation_info.confirmation_time,
-//                     confirmation_info.signature,
-//                     PingThingTxType::Memo,
-//                     true,
-//                     confirmation_info.slot_sent,
-//                     slot_landed,
-//                 )
-//                 .await
-//         }
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn landed(slot_sent: u64, slot_confirmed: u64, elapsed: Duration) -> TxConfirmation {
+        (
+            Signature::new_unique(),
+            ConfirmationResponseFromRpc::Success(
+                slot_sent,
+                slot_confirmed,
+                TransactionConfirmationStatus::Confirmed,
+                elapsed,
+            ),
+        )
+    }
+
+    fn timed_out() -> TxConfirmation {
+        (
+            Signature::new_unique(),
+            ConfirmationResponseFromRpc::Timeout(Duration::from_secs(15)),
+        )
+    }
+
+    #[test]
+    fn test_same_seed_generates_same_memo_txs() {
+        let payer = Keypair::new();
+        let tx_params = BenchmarkTransactionParams {
+            tx_size: crate::tx_size::TxSize::Small,
+            cu_price_micro_lamports: 300,
+        };
+        let memo_txs = |seed: u64| {
+            round_seeds(seed)
+                .take(3)
+                .map(|round_seed| {
+                    let mut rng = create_rng(Some(round_seed));
+                    create_memo_tx(
+                        &payer,
+                        solana_sdk::hash::Hash::default(),
+                        &mut rng,
+                        &tx_params,
+                    )
+                    .message
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let first_run = memo_txs(42);
+        assert_eq!(first_run, memo_txs(42));
+        assert_ne!(first_run[0], first_run[1]);
+        assert_ne!(first_run, memo_txs(43));
+    }
+
+    fn round_result(a: TxConfirmation, b: TxConfirmation) -> ConfirmationSlotRoundResult {
+        ConfirmationSlotRoundResult {
+            round: 0,
+            rpc_a: ConfirmationSlotResult::from_confirmation(a),
+            rpc_b: ConfirmationSlotResult::from_confirmation(b),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_confirmation_slot_rounds_returns_both_endpoints() {
+        let results = run_confirmation_slot_rounds(3, &[], |round| async move {
+            Ok((
+                landed(100, 102 + round as u64, Duration::from_millis(800)),
+                timed_out(),
+            ))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        for (round, result) in results.iter().enumerate() {
+            assert_eq!(result.round, round);
+            match &result.rpc_a {
+                ConfirmationSlotResult::Success(success) => {
+                    assert_eq!(success.slots_to_land(), 2 + round as u64)
+                }
+                other => panic!("expected success for A but got {other:?}"),
+            }
+            assert!(matches!(result.rpc_b, ConfirmationSlotResult::Timeout(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_confirmation_slot_rounds_aborts_on_failed_round() {
+        let result = run_confirmation_slot_rounds(3, &[], |_| async {
+            Err::<(TxConfirmation, TxConfirmation), _>(anyhow!("rpc down"))
+        })
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_landed_at_slot_zero_is_not_a_timeout() {
+        let (signature, response) = landed(0, 0, Duration::ZERO);
+        let result = ConfirmationSlotResult::from_confirmation((signature, response));
+        let success = result.success().expect("landed tx must not be a timeout");
+        assert_eq!(success.signature, signature);
+        assert_eq!(success.slot_confirmed, 0);
+        assert_eq!(success.confirmation_time, Duration::ZERO);
+
+        assert!(ConfirmationSlotResult::from_confirmation(timed_out())
+            .success()
+            .is_none());
+    }
+
+    #[test]
+    fn test_round_winner() {
+        let tie = round_result(
+            landed(10, 12, Duration::ZERO),
+            landed(10, 12, Duration::ZERO),
+        );
+        assert_eq!(tie.winner(), RoundWinner::Tie);
+
+        let a_wins = round_result(
+            landed(10, 11, Duration::ZERO),
+            landed(10, 12, Duration::ZERO),
+        );
+        assert_eq!(a_wins.winner(), RoundWinner::RpcA);
+
+        // a timeout never wins, even against a tx landed at slot 0
+        let b_wins = round_result(timed_out(), landed(0, 0, Duration::ZERO));
+        assert_eq!(b_wins.winner(), RoundWinner::RpcB);
+
+        let none_landed = round_result(timed_out(), timed_out());
+        assert_eq!(none_landed.winner(), RoundWinner::NoneLanded);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::payer::PayerSource;
+use crate::{create_rng, generate_txs, BenchmarkTransactionParams};
+use anyhow::Context;
+use log::{info, warn};
+use std::fmt::Write;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::benches::rpc_interface::{
+    send_and_confirm_bulk_transactions, BulkTransactionsResult, ConfirmationResponseFromRpc,
+    SendThrottle,
+};
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signer};
+use url::Url;
+
+/// batch sizes of the levels: start, start + step, ..
+#[derive(Clone, Copy, Debug)]
+pub struct LoadRampConfig {
+    pub start_txs: usize,
+    pub step_txs: usize,
+    pub num_levels: usize,
+    // fraction of confirmed txs (0.0..=1.0) below which a level is considered degraded
+    pub min_success_rate: f32,
+}
+
+impl LoadRampConfig {
+    pub fn levels(&self) -> Vec<usize> {
+        (0..self.num_levels)
+            .map(|level| self.start_txs + level * self.step_txs)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct LoadLevelResult {
+    pub txs_per_level: usize,
+    pub txs_confirmed: usize,
+    // txs_confirmed / txs_per_level
+    pub success_rate: f32,
+    // None if no tx was confirmed
+    pub p90_confirmation_ms: Option<f32>,
+}
+
+impl LoadLevelResult {
+    fn from_confirmations(
+        txs_per_level: usize,
+        confirmations: &[ConfirmationResponseFromRpc],
+    ) -> Self {
+        let mut confirmation_times_ms = confirmations
+            .iter()
+            .filter_map(|confirmation| match confirmation {
+                ConfirmationResponseFromRpc::Success(_, _, _, confirmation_time) => {
+                    Some(confirmation_time.as_secs_f32() * 1000.0)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        confirmation_times_ms.sort_by(f32::total_cmp);
+
+        let txs_confirmed = confirmation_times_ms.len();
+        Self {
+            txs_per_level,
+            txs_confirmed,
+            success_rate: if txs_per_level > 0 {
+                txs_confirmed as f32 / txs_per_level as f32
+            } else {
+                0.0
+            },
+            // nearest rank
+            p90_confirmation_ms: (txs_confirmed > 0).then(|| {
+                let rank = (txs_confirmed * 90).div_ceil(100);
+                confirmation_times_ms[rank.max(1) - 1]
+            }),
+        }
+    }
+}
+
+/// TC4 increase the number of txs sent at once level by level until the confirmation rate degrades
+#[allow(clippy::too_many_arguments)]
+pub async fn load_ramp(
+    payer: &PayerSource,
+    rpc_url: String,
+    tx_status_websocket_addr: Option<String>,
+    tx_params: BenchmarkTransactionParams,
+    max_timeout: Duration,
+    config: LoadRampConfig,
+    send_throttle: SendThrottle,
+    json_output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    assert!(config.num_levels > 0, "num_levels must be greater than 0");
+
+    let rpc = Arc::new(RpcClient::new_with_commitment(
+        rpc_url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+    info!("RPC: {}", obfuscate_rpcurl(&rpc.as_ref().url()));
+    let ws_addr = tx_status_websocket_addr
+        .unwrap_or_else(|| rpc_url.replace("http:", "ws:").replace("https:", "wss:"));
+    let ws_addr = Url::parse(&ws_addr).context("invalid websocket url")?;
+    let payer: Arc<Keypair> = Arc::new(payer.load()?);
+    info!("Payer: {}", payer.pubkey().to_string());
+
+    let results = ramp_load(&config.levels(), |num_txs| {
+        send_level(
+            &rpc,
+            ws_addr.clone(),
+            &payer,
+            num_txs,
+            &tx_params,
+            max_timeout,
+            &send_throttle,
+        )
+    })
+    .await;
+
+    info!("load ramp results:\n{}", format_results_table(&results));
+    match find_knee(&results, config.min_success_rate) {
+        Some(knee) => info!(
+            "success rate dropped below {:.0}% at {} txs per level",
+            config.min_success_rate * 100.0,
+            knee.txs_per_level
+        ),
+        None => info!(
+            "success rate stayed above {:.0}% on all levels",
+            config.min_success_rate * 100.0
+        ),
+    }
+
+    if let Some(json_output) = json_output {
+        let json = serde_json::to_string_pretty(&results)?;
+        std::fs::write(&json_output, json)
+            .with_context(|| format!("write results to {}", json_output.display()))?;
+    }
+    Ok(())
+}
+
+async fn send_level(
+    rpc: &RpcClient,
+    tx_status_websocket_addr: Url,
+    payer: &Keypair,
+    num_txs: usize,
+    tx_params: &BenchmarkTransactionParams,
+    max_timeout: Duration,
+    send_throttle: &SendThrottle,
+) -> anyhow::Result<Vec<ConfirmationResponseFromRpc>> {
+    let hash = rpc
+        .get_latest_blockhash()
+        .await
+        .context("get latest blockhash")?;
+    let mut rng = create_rng(None);
+    let txs = generate_txs(num_txs, payer, hash, &mut rng, tx_params);
+
+    let BulkTransactionsResult { confirmations, .. } = send_and_confirm_bulk_transactions(
+        rpc,
+        tx_status_websocket_addr,
+        payer.pubkey(),
+        &txs,
+        max_timeout,
+        send_throttle,
+        None,
+    )
+    .await
+    .context("send and confirm bulk tx")?;
+
+    Ok(confirmations
+        .into_iter()
+        .map(|(_signature, _slots_passed, confirmation)| confirmation)
+        .collect())
+}
+
+/// runs one level after the other; a level that failed to run is reported with 0 confirmed txs
+pub async fn ramp_load<F, Fut>(levels: &[usize], mut send_level: F) -> Vec<LoadLevelResult>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<ConfirmationResponseFromRpc>>>,
+{
+    let mut results = Vec::with_capacity(levels.len());
+    for &num_txs in levels {
+        let confirmations = match send_level(num_txs).await {
+            Ok(confirmations) => confirmations,
+            Err(err) => {
+                warn!("Failed to run level with {} txs: {:?}", num_txs, err);
+                vec![]
+            }
+        };
+        let result = LoadLevelResult::from_confirmations(num_txs, &confirmations);
+        info!("{:?}", result);
+        results.push(result);
+    }
+    results
+}
+
+/// first level with a success rate below `min_success_rate`
+pub fn find_knee(results: &[LoadLevelResult], min_success_rate: f32) -> Option<&LoadLevelResult> {
+    results
+        .iter()
+        .find(|result| result.success_rate < min_success_rate)
+}
+
+pub fn format_results_table(results: &[LoadLevelResult]) -> String {
+    let mut table = format!(
+        "{:>12} | {:>12} | {:>8} | {:>10}\n",
+        "txs/level", "confirmed", "success", "p90 ms"
+    );
+    for result in results {
+        let p90 = result
+            .p90_confirmation_ms
+            .map(|p90| format!("{p90:.0}"))
+            .unwrap_or_else(|| "n/a".to_string());
+        writeln!(
+            table,
+            "{:>12} | {:>12} | {:>7.1}% | {:>10}",
+            result.txs_per_level,
+            result.txs_confirmed,
+            result.success_rate * 100.0,
+            p90
+        )
+        .unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    // confirms everything in 500ms up to `max_healthy_txs`, above only every 2nd tx
+    fn degrading_stub(num_txs: usize, max_healthy_txs: usize) -> Vec<ConfirmationResponseFromRpc> {
+        (0..num_txs)
+            .map(|i| {
+                if num_txs <= max_healthy_txs || i % 2 == 0 {
+                    ConfirmationResponseFromRpc::Success(
+                        10,
+                        12,
+                        TransactionConfirmationStatus::Confirmed,
+                        Duration::from_millis(500 + i as u64),
+                    )
+                } else {
+                    ConfirmationResponseFromRpc::Timeout(Duration::from_secs(15))
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_ramp_finds_knee() {
+        let config = LoadRampConfig {
+            start_txs: 20,
+            step_txs: 20,
+            num_levels: 5,
+            min_success_rate: 0.9,
+        };
+        assert_eq!(config.levels(), vec![20, 40, 60, 80, 100]);
+
+        let results = ramp_load(&config.levels(), |num_txs| async move {
+            anyhow::Ok(degrading_stub(num_txs, 40))
+        })
+        .await;
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[1].success_rate, 1.0);
+        // nearest rank p90 of 500..=539ms
+        assert_eq!(results[1].p90_confirmation_ms, Some(535.0));
+        assert_eq!(results[2].txs_confirmed, 30);
+        assert_eq!(results[2].success_rate, 0.5);
+
+        let knee = find_knee(&results, config.min_success_rate).unwrap();
+        assert_eq!(knee.txs_per_level, 60);
+
+        let table = format_results_table(&results);
+        assert_eq!(table.lines().count(), 6);
+        assert!(table.contains("50.0%"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_level_has_no_confirmations() {
+        let results = ramp_load(&[10], |_| async {
+            Err::<Vec<_>, _>(anyhow::anyhow!("rpc down"))
+        })
+        .await;
+        assert_eq!(results[0].txs_confirmed, 0);
+        assert_eq!(results[0].p90_confirmation_ms, None);
+        assert!(find_knee(&results, 0.5).is_some());
+    }
+}
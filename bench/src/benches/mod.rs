@@ -1,5 +1,8 @@
 pub mod api_load;
 pub mod confirmation_rate;
 pub mod confirmation_slot;
+pub mod load_ramp;
+pub mod result_sink;
 pub mod rpc_interface;
+pub mod signature_statuses;
 mod tx_status_websocket_collector;
@@ -0,0 +1,181 @@
+use crate::benches::confirmation_slot::ConfirmationSlotRoundResult;
+use crate::benches::rpc_interface::create_rpc_client;
+use crate::metrics::{PingThing, PingThingTxType};
+use anyhow::Context;
+use async_trait::async_trait;
+use log::{debug, info};
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use url::Url;
+
+/// destination for the results of a benchmark; called once per run (round) while the benchmark is running
+#[async_trait]
+pub trait BenchResultSink: Send + Sync {
+    async fn record_run(&self, result: &ConfirmationSlotRoundResult) -> anyhow::Result<()>;
+}
+
+/// logs the outcome of each run
+pub struct LogResultSink;
+
+#[async_trait]
+impl BenchResultSink for LogResultSink {
+    async fn record_run(&self, result: &ConfirmationSlotRoundResult) -> anyhow::Result<()> {
+        for (name, rpc_result) in [("A", &result.rpc_a), ("B", &result.rpc_b)] {
+            match rpc_result.success() {
+                Some(success) => info!(
+                    "txn {} landed after {} slots",
+                    name,
+                    success.slots_to_land()
+                ),
+                None => info!("txn {} did not land: {:?}", name, rpc_result),
+            }
+        }
+        debug!("round {} winner: {:?}", result.round, result.winner());
+        Ok(())
+    }
+}
+
+/// appends each run as one line of JSON (JSON Lines)
+pub struct JsonFileResultSink {
+    path: PathBuf,
+}
+
+impl JsonFileResultSink {
+    /// truncates the file if it exists
+    pub fn create(path: PathBuf) -> anyhow::Result<Self> {
+        File::create(&path).with_context(|| format!("create {}", path.display()))?;
+        Ok(Self { path })
+    }
+}
+
+#[async_trait]
+impl BenchResultSink for JsonFileResultSink {
+    async fn record_run(&self, result: &ConfirmationSlotRoundResult) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(result)?;
+        line.push('\n');
+        OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("write result to {}", self.path.display()))
+    }
+}
+
+/// submits the landed txs to ping-thing; timeouts and send errors are skipped
+pub struct PingThingResultSink {
+    ping_thing: PingThing,
+}
+
+impl PingThingResultSink {
+    /// fails if one of the RPCs is not on the cluster of the ping-thing
+    pub async fn validated(ping_thing: PingThing, rpc_urls: &[&Url]) -> anyhow::Result<Self> {
+        for rpc_url in rpc_urls {
+            ping_thing
+                .validate_rpc_cluster(&create_rpc_client(rpc_url, None))
+                .await
+                .with_context(|| {
+                    format!(
+                        "validate ping-thing cluster of {}",
+                        obfuscate_rpcurl(rpc_url.as_str())
+                    )
+                })?;
+        }
+        Ok(Self { ping_thing })
+    }
+}
+
+#[async_trait]
+impl BenchResultSink for PingThingResultSink {
+    async fn record_run(&self, result: &ConfirmationSlotRoundResult) -> anyhow::Result<()> {
+        for rpc_result in [&result.rpc_a, &result.rpc_b] {
+            let Some(success) = rpc_result.success() else {
+                continue;
+            };
+            self.ping_thing
+                .submit_confirmed_stats(
+                    success.confirmation_time,
+                    success.signature,
+                    PingThingTxType::Memo,
+                    true,
+                    success.slot_sent,
+                    success.slot_confirmed,
+                )
+                .await
+                .context("submit stats to ping-thing")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benches::confirmation_slot::run_confirmation_slot_rounds;
+    use crate::benches::rpc_interface::ConfirmationResponseFromRpc;
+    use solana_sdk::signature::Signature;
+    use solana_transaction_status::TransactionConfirmationStatus;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    struct CapturingResultSink {
+        results: Arc<Mutex<Vec<ConfirmationSlotRoundResult>>>,
+    }
+
+    #[async_trait]
+    impl BenchResultSink for CapturingResultSink {
+        async fn record_run(&self, result: &ConfirmationSlotRoundResult) -> anyhow::Result<()> {
+            self.results.lock().unwrap().push(result.clone());
+            Ok(())
+        }
+    }
+
+    struct FailingResultSink;
+
+    #[async_trait]
+    impl BenchResultSink for FailingResultSink {
+        async fn record_run(&self, _result: &ConfirmationSlotRoundResult) -> anyhow::Result<()> {
+            anyhow::bail!("sink unavailable")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sink_receives_each_run() {
+        let captured = Arc::new(Mutex::new(vec![]));
+        let sinks: Vec<Box<dyn BenchResultSink>> = vec![
+            Box::new(FailingResultSink),
+            Box::new(LogResultSink),
+            Box::new(CapturingResultSink {
+                results: captured.clone(),
+            }),
+        ];
+
+        let results = run_confirmation_slot_rounds(3, &sinks, |round| async move {
+            let landed = ConfirmationResponseFromRpc::Success(
+                100,
+                102 + round as u64,
+                TransactionConfirmationStatus::Confirmed,
+                Duration::from_millis(800),
+            );
+            let timed_out = ConfirmationResponseFromRpc::Timeout(Duration::from_secs(15));
+            Ok((
+                (Signature::new_unique(), landed),
+                (Signature::new_unique(), timed_out),
+            ))
+        })
+        .await
+        .unwrap();
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.len(), 3);
+        for (round, (captured, result)) in captured.iter().zip(&results).enumerate() {
+            assert_eq!(captured.round, round);
+            assert_eq!(
+                captured.rpc_a.success().unwrap().signature,
+                result.rpc_a.success().unwrap().signature
+            );
+            assert!(captured.rpc_b.success().is_none());
+        }
+    }
+}
@@ -10,34 +10,162 @@ use solana_lite_rpc_util::obfuscate_rpcurl;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_rpc_client::rpc_client::SerializableTransaction;
 use solana_rpc_client_api::client_error::ErrorKind;
-use solana_rpc_client_api::config::RpcSendTransactionConfig;
+use solana_rpc_client_api::config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_rpc_client_api::custom_error::{
+    JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+};
+use solana_rpc_client_api::request::{
+    RpcError, RpcResponseErrorData, MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
+};
+use solana_rpc_client_api::response::RpcSimulateTransactionResult;
 
 use solana_sdk::clock::Slot;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
-use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
 use solana_transaction_status::TransactionConfirmationStatus;
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
 use tokio::time::Instant;
 use url::Url;
 
-pub fn create_rpc_client(rpc_url: &Url) -> RpcClient {
-    RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed())
+/// client for the benchmarks; the confirmation logic follows the commitment of the client (None: confirmed)
+pub fn create_rpc_client(rpc_url: &Url, commitment: Option<CommitmentConfig>) -> RpcClient {
+    RpcClient::new_with_commitment(
+        rpc_url.to_string(),
+        commitment.unwrap_or(CommitmentConfig::confirmed()),
+    )
+}
+
+/// transaction statuses come from blockSubscribe which does not support processed
+pub fn check_client_commitment(commitment: Option<CommitmentConfig>) -> anyhow::Result<()> {
+    match commitment {
+        Some(commitment) if !commitment.is_at_least_confirmed() => {
+            bail!(
+                "Unsupported commitment {:?} - use confirmed or finalized",
+                commitment.commitment
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+/// status a transaction observed at `commitment` is reported with
+pub fn confirmation_status_for(commitment: CommitmentConfig) -> TransactionConfirmationStatus {
+    match commitment.commitment {
+        CommitmentLevel::Processed => TransactionConfirmationStatus::Processed,
+        CommitmentLevel::Finalized => TransactionConfirmationStatus::Finalized,
+        _ => TransactionConfirmationStatus::Confirmed,
+    }
 }
 
 #[derive(Clone)]
 pub enum ConfirmationResponseFromRpc {
     // RPC error on send_transaction
     SendError(Arc<ErrorKind>),
+    // RPC node rejected the send because it lags behind the send slot
+    // (send slot used as min_context_slot, context slot of the node if reported)
+    MinContextSlotNotReached(Slot, Option<Slot>),
     // (sent slot at confirmed commitment, confirmed slot, ..., ...)
     // transaction_confirmation_status is "confirmed" (finalized is not reported by blockSubscribe websocket
     Success(Slot, Slot, TransactionConfirmationStatus, Duration),
     // timout waiting for confirmation status
     Timeout(Duration),
+    // RPC had no status for the transaction (not even processed) after the never-seen check: dropped
+    NeverSeen(Duration),
+}
+
+impl ConfirmationResponseFromRpc {
+    /// RPC rejected the send with HTTP 429
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            ConfirmationResponseFromRpc::SendError(error_kind) => is_rate_limited(error_kind),
+            _ => false,
+        }
+    }
+
+    /// RPC node reported itself unhealthy or lagged behind the send slot
+    pub fn is_node_unhealthy(&self) -> bool {
+        match self {
+            ConfirmationResponseFromRpc::SendError(error_kind) => is_node_unhealthy(error_kind),
+            ConfirmationResponseFromRpc::MinContextSlotNotReached(..) => true,
+            _ => false,
+        }
+    }
+}
+
+/// pacing of send_transaction calls to avoid tripping the rate limits of the RPC
+#[derive(Clone, Debug)]
+pub struct SendThrottle {
+    /// maximum number of send_transaction requests in flight; None is unlimited (local validators)
+    pub max_concurrency: Option<usize>,
+    pub max_sends_per_second: Option<u32>,
+    /// retries of a single transaction after being rate limited (HTTP 429)
+    pub max_rate_limit_retries: u32,
+    /// doubled on every retry
+    pub rate_limit_backoff: Duration,
+}
+
+impl Default for SendThrottle {
+    fn default() -> Self {
+        Self {
+            max_concurrency: Some(32),
+            max_sends_per_second: None,
+            max_rate_limit_retries: 3,
+            rate_limit_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl SendThrottle {
+    /// send all transactions at once; only use with local validators
+    pub fn unlimited() -> Self {
+        Self {
+            max_concurrency: None,
+            max_sends_per_second: None,
+            max_rate_limit_retries: 0,
+            rate_limit_backoff: Duration::ZERO,
+        }
+    }
+}
+
+/// cadence of polling the confirmation status of the sent transactions
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// cadence of polling the confirmed slot while the sends are in flight
+const SLOT_POLL_INTERVAL_WHILE_SENDING: Duration = Duration::from_millis(100);
+
+pub struct BulkTransactionsResult {
+    /// (signature, slots passed between send slot and completion of the send, confirmation)
+    pub confirmations: Vec<(Signature, u64, ConfirmationResponseFromRpc)>,
+    /// number of sends which got rate limited and were retried
+    pub num_sends_throttled: u64,
+}
+
+/// send config of the bulk sends; preflight is skipped, but the preflight commitment still selects the
+/// bank min_context_slot is checked against (None: confirmed, the RPC default would be finalized)
+pub fn bulk_send_config(
+    send_slot: Slot,
+    preflight_commitment: Option<CommitmentConfig>,
+) -> RpcSendTransactionConfig {
+    RpcSendTransactionConfig {
+        skip_preflight: true,
+        preflight_commitment: Some(
+            preflight_commitment
+                .map(|commitment| commitment.commitment)
+                .unwrap_or(CommitmentLevel::Confirmed),
+        ),
+        encoding: None,
+        max_retries: None,
+        // reject sends to nodes lagging behind the send slot
+        min_context_slot: Some(send_slot),
+    }
 }
 
 pub async fn send_and_confirm_bulk_transactions(
@@ -46,43 +174,66 @@ pub async fn send_and_confirm_bulk_transactions(
     payer_pubkey: Pubkey,
     txs: &[VersionedTransaction],
     max_timeout: Duration,
-) -> anyhow::Result<Vec<(Signature, ConfirmationResponseFromRpc)>> {
+    send_throttle: &SendThrottle,
+    preflight_commitment: Option<CommitmentConfig>,
+) -> anyhow::Result<BulkTransactionsResult> {
     trace!("Polling for next slot ..");
-    let send_slot = poll_next_slot_start(rpc_client)
+    let send_slot = poll_next_slot_start(rpc_client, &SlotPollConfig::default())
         .await
         .context("poll for next start slot")?;
     trace!("Send slot: {}", send_slot);
 
-    let send_config = RpcSendTransactionConfig {
-        skip_preflight: true,
-        preflight_commitment: None,
-        encoding: None,
-        max_retries: None,
-        min_context_slot: None,
-    };
+    let send_config = bulk_send_config(send_slot, preflight_commitment);
 
-    // note: we get confirmed but never finaliized
-    let (tx_status_map, _jh_collector) = start_tx_status_collector(
-        tx_status_websocket_addr.clone(),
-        payer_pubkey,
-        CommitmentConfig::confirmed(),
-    )
-    .await;
+    // slots and statuses are observed at the commitment of the client
+    let commitment = rpc_client.commitment();
+    let (tx_status_map, _jh_collector) =
+        start_tx_status_collector(tx_status_websocket_addr.clone(), payer_pubkey, commitment).await;
 
     let started_at = Instant::now();
     trace!(
-        "Sending {} transactions via RPC (retries=off) ..",
-        txs.len()
+        "Sending {} transactions via RPC (retries=off, {:?}) ..",
+        txs.len(),
+        send_throttle
     );
-    let batch_sigs_or_fails = join_all(txs.iter().map(|tx| {
-        rpc_client
-            .send_transaction_with_config(tx, send_config)
-            .map_err(|e| e.kind)
-    }))
-    .await;
+    // confirmed slot observed while sending; attached to each send when it completes
+    let current_slot = AtomicU64::new(send_slot);
+    let current_slot = &current_slot;
+    let track_current_slot = async {
+        loop {
+            tokio::time::sleep(SLOT_POLL_INTERVAL_WHILE_SENDING).await;
+            match rpc_client.get_slot_with_commitment(commitment).await {
+                Ok(slot) => {
+                    current_slot.fetch_max(slot, Ordering::Relaxed);
+                }
+                Err(err) => trace!("Failed to poll slot while sending: {}", err),
+            }
+        }
+    };
+    let send_all = send_transactions_throttled(
+        txs,
+        send_throttle,
+        |(err, _): &(ErrorKind, Slot)| is_rate_limited(err),
+        |tx| {
+            rpc_client
+                .send_transaction_with_config(tx, send_config)
+                .map_ok(move |tx_sig| (tx_sig, current_slot.load(Ordering::Relaxed)))
+                .map_err(move |e| (e.kind, current_slot.load(Ordering::Relaxed)))
+        },
+    );
+    let (batch_sigs_or_fails, num_sends_throttled) = tokio::select! {
+        result = send_all => result,
+        _ = track_current_slot => unreachable!("slot tracker runs until all sends completed"),
+    };
+    if num_sends_throttled > 0 {
+        warn!(
+            "Sending transactions got rate limited {} times",
+            num_sends_throttled
+        );
+    }
 
     let after_send_slot = rpc_client
-        .get_slot_with_commitment(CommitmentConfig::confirmed())
+        .get_slot_with_commitment(commitment)
         .await
         .context("get slot afterwards")?;
 
@@ -128,12 +279,12 @@ pub async fn send_and_confirm_bulk_transactions(
     );
 
     if num_sent_failed > 0 {
+        // note: failed sends are reported as SendError to the caller
         warn!(
             "Some transactions failed to send: {} out of {}",
             num_sent_failed,
             txs.len()
         );
-        bail!("Failed to send all transactions");
     }
 
     let mut pending_status_set: HashSet<Signature> = HashSet::new();
@@ -141,21 +292,79 @@ pub async fn send_and_confirm_bulk_transactions(
         .iter()
         .filter(|sig_or_fail| sig_or_fail.is_ok())
         .for_each(|sig_or_fail| {
-            pending_status_set.insert(sig_or_fail.as_ref().unwrap().to_owned());
+            pending_status_set.insert(sig_or_fail.as_ref().unwrap().0);
         });
-    let mut result_status_map: HashMap<Signature, ConfirmationResponseFromRpc> = HashMap::new();
-
-    // items get moved from pending_status_set to result_status_map
 
     debug!(
         "Waiting for transaction confirmations from websocket source <{}> ..",
         obfuscate_rpcurl(tx_status_websocket_addr.as_str())
     );
+    let result_status_map = wait_for_confirmations(
+        pending_status_set,
+        send_slot,
+        max_timeout,
+        confirmation_status_for(commitment),
+        |tx_sig| {
+            tx_status_map
+                .get(tx_sig)
+                .map(|confirmed_slot| *confirmed_slot)
+        },
+        |tx_sigs| signatures_seen_by_rpc(rpc_client, tx_sigs),
+    )
+    .await;
+
+    let result_as_vec = batch_sigs_or_fails
+        .into_iter()
+        .enumerate()
+        .map(|(i, sig_or_fail)| match sig_or_fail {
+            Ok((tx_sig, send_completed_slot)) => {
+                let confirmation = result_status_map
+                    .get(&tx_sig)
+                    .expect("consistent map with all tx")
+                    .clone()
+                    .to_owned();
+                (tx_sig, send_completed_slot - send_slot, confirmation)
+            }
+            Err((send_error, send_completed_slot)) => {
+                let tx_sig = txs[i].get_signature();
+                let confirmation = classify_send_error(send_slot, send_error);
+                (*tx_sig, send_completed_slot - send_slot, confirmation)
+            }
+        })
+        .collect_vec();
+
+    Ok(BulkTransactionsResult {
+        confirmations: result_as_vec,
+        num_sends_throttled,
+    })
+}
+
+/// polls `confirmed_slot` until all `pending_status_set` transactions are confirmed or `max_timeout` elapsed;
+/// on timeout the unconfirmed transactions are checked once with `seen_by_rpc` and the ones the RPC has
+/// no status for are reported as dropped, the others as timed out
+pub async fn wait_for_confirmations<C, S, Fut>(
+    mut pending_status_set: HashSet<Signature>,
+    send_slot: Slot,
+    max_timeout: Duration,
+    confirmation_status: TransactionConfirmationStatus,
+    confirmed_slot: C,
+    seen_by_rpc: S,
+) -> HashMap<Signature, ConfirmationResponseFromRpc>
+where
+    C: Fn(&Signature) -> Option<Slot>,
+    S: FnOnce(Vec<Signature>) -> Fut,
+    Fut: Future<Output = anyhow::Result<HashSet<Signature>>>,
+{
+    let num_sent_ok = pending_status_set.len();
+    let mut result_status_map: HashMap<Signature, ConfirmationResponseFromRpc> = HashMap::new();
+
+    // items get moved from pending_status_set to result_status_map
+
     let started_at = Instant::now();
     let timeout_at = started_at + max_timeout;
-    // "poll" the status dashmap
+    // "poll" the status source
     'polling_loop: for iteration in 1.. {
-        let iteration_ends_at = started_at + Duration::from_millis(iteration * 100);
+        let iteration_ends_at = started_at + CONFIRMATION_POLL_INTERVAL * iteration as u32;
         assert_eq!(
             pending_status_set.len() + result_status_map.len(),
             num_sent_ok,
@@ -163,30 +372,28 @@ pub async fn send_and_confirm_bulk_transactions(
         );
         let elapsed = started_at.elapsed();
 
-        for multi in tx_status_map.iter() {
-            // note that we will see tx_sigs we did not send
-            let (tx_sig, confirmed_slot) = multi.pair();
-
+        pending_status_set.retain(|tx_sig| {
             // status is confirmed
-            if pending_status_set.remove(tx_sig) {
-                trace!(
-                    "take status for sig {:?} and confirmed_slot: {:?} from websocket source",
-                    tx_sig,
-                    confirmed_slot
-                );
-                let prev_value = result_status_map.insert(
-                    *tx_sig,
-                    ConfirmationResponseFromRpc::Success(
-                        send_slot,
-                        *confirmed_slot,
-                        // note: this is not optimal as we do not cover finalized here
-                        TransactionConfirmationStatus::Confirmed,
-                        elapsed,
-                    ),
-                );
-                assert!(prev_value.is_none(), "Must not override existing value");
-            }
-        } // -- END for tx_status_map loop
+            let Some(confirmed_slot) = confirmed_slot(tx_sig) else {
+                return true;
+            };
+            trace!(
+                "take status for sig {:?} and confirmed_slot: {:?} from status source",
+                tx_sig,
+                confirmed_slot
+            );
+            let prev_value = result_status_map.insert(
+                *tx_sig,
+                ConfirmationResponseFromRpc::Success(
+                    send_slot,
+                    confirmed_slot,
+                    confirmation_status.clone(),
+                    elapsed,
+                ),
+            );
+            assert!(prev_value.is_none(), "Must not override existing value");
+            false
+        });
 
         if pending_status_set.is_empty() {
             debug!(
@@ -209,55 +416,297 @@ pub async fn send_and_confirm_bulk_transactions(
 
     let total_time_elapsed_polling = started_at.elapsed();
 
-    // all transactions which remain in pending list are considered timed out
-    for tx_sig in pending_status_set.clone() {
-        pending_status_set.remove(&tx_sig);
+    // transactions which remain in pending list are dropped if the RPC never saw them, otherwise timed out
+    if !pending_status_set.is_empty() {
+        match seen_by_rpc(pending_status_set.iter().copied().collect()).await {
+            Ok(seen) => {
+                let num_unconfirmed = pending_status_set.len();
+                pending_status_set.retain(|tx_sig| {
+                    if seen.contains(tx_sig) {
+                        return true;
+                    }
+                    result_status_map.insert(
+                        *tx_sig,
+                        ConfirmationResponseFromRpc::NeverSeen(total_time_elapsed_polling),
+                    );
+                    false
+                });
+                let num_never_seen = num_unconfirmed - pending_status_set.len();
+                if num_never_seen > 0 {
+                    warn!(
+                        "{} transactions unknown to the RPC after {:?} - considered dropped",
+                        num_never_seen, total_time_elapsed_polling
+                    );
+                }
+            }
+            Err(err) => {
+                warn!("Failed to check for dropped transactions: {:?}", err);
+            }
+        }
+    }
+    for tx_sig in pending_status_set {
         result_status_map.insert(
             tx_sig,
             ConfirmationResponseFromRpc::Timeout(total_time_elapsed_polling),
         );
     }
 
-    let result_as_vec = batch_sigs_or_fails
-        .into_iter()
-        .enumerate()
-        .map(|(i, sig_or_fail)| match sig_or_fail {
-            Ok(tx_sig) => {
-                let confirmation = result_status_map
-                    .get(&tx_sig)
-                    .expect("consistent map with all tx")
-                    .clone()
-                    .to_owned();
-                (tx_sig, confirmation)
-            }
-            Err(send_error) => {
-                let tx_sig = txs[i].get_signature();
-                let confirmation = ConfirmationResponseFromRpc::SendError(Arc::new(send_error));
-                (*tx_sig, confirmation)
+    result_status_map
+}
+
+/// signatures the RPC has a status for at any commitment level
+async fn signatures_seen_by_rpc(
+    rpc_client: &RpcClient,
+    tx_sigs: Vec<Signature>,
+) -> anyhow::Result<HashSet<Signature>> {
+    let mut seen = HashSet::new();
+    for chunk in tx_sigs.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+        let statuses = rpc_client.get_signature_statuses(chunk).await?.value;
+        seen.extend(
+            chunk
+                .iter()
+                .zip(statuses)
+                .filter(|(_, status)| status.is_some())
+                .map(|(tx_sig, _)| *tx_sig),
+        );
+    }
+    Ok(seen)
+}
+
+#[derive(Clone, Debug)]
+pub enum SimulationResponseFromRpc {
+    // simulation succeeded (units consumed)
+    Success(Option<u64>),
+    // transaction would fail (error, units consumed, logs)
+    SimulationError(TransactionError, Option<u64>, Vec<String>),
+    // RPC error on simulate_transaction
+    SendError(Arc<ErrorKind>),
+}
+
+impl From<Result<RpcSimulateTransactionResult, ErrorKind>> for SimulationResponseFromRpc {
+    fn from(result: Result<RpcSimulateTransactionResult, ErrorKind>) -> Self {
+        match result {
+            Ok(RpcSimulateTransactionResult {
+                err: Some(err),
+                units_consumed,
+                logs,
+                ..
+            }) => SimulationResponseFromRpc::SimulationError(
+                err,
+                units_consumed,
+                logs.unwrap_or_default(),
+            ),
+            Ok(RpcSimulateTransactionResult { units_consumed, .. }) => {
+                SimulationResponseFromRpc::Success(units_consumed)
             }
+            Err(error_kind) => SimulationResponseFromRpc::SendError(Arc::new(error_kind)),
+        }
+    }
+}
+
+/// dry-run: simulate the transactions instead of sending them to validate their construction
+/// and estimate compute units without spending lamports
+pub async fn simulate_bulk_transactions(
+    rpc_client: &RpcClient,
+    txs: &[VersionedTransaction],
+    send_throttle: &SendThrottle,
+) -> Vec<(Signature, SimulationResponseFromRpc)> {
+    let simulate_config = RpcSimulateTransactionConfig {
+        sig_verify: true,
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..RpcSimulateTransactionConfig::default()
+    };
+
+    trace!("Simulating {} transactions via RPC ..", txs.len());
+    let (simulation_results, _num_throttled) =
+        send_transactions_throttled(txs, send_throttle, is_rate_limited, |tx| {
+            rpc_client
+                .simulate_transaction_with_config(tx, simulate_config.clone())
+                .map_ok(|response| response.value)
+                .map_err(|e| e.kind)
         })
-        .collect_vec();
+        .await;
+
+    to_simulation_responses(txs, simulation_results)
+}
+
+fn to_simulation_responses(
+    txs: &[VersionedTransaction],
+    simulation_results: Vec<Result<RpcSimulateTransactionResult, ErrorKind>>,
+) -> Vec<(Signature, SimulationResponseFromRpc)> {
+    txs.iter()
+        .zip(simulation_results)
+        .map(|(tx, result)| (*tx.get_signature(), SimulationResponseFromRpc::from(result)))
+        .collect_vec()
+}
+
+/// send all transactions respecting the throttle config; returns the send results in order
+/// and the number of sends that got rate limited
+async fn send_transactions_throttled<'a, T, E, F, Fut>(
+    txs: &'a [VersionedTransaction],
+    send_throttle: &SendThrottle,
+    is_rate_limited: impl Fn(&E) -> bool,
+    send: F,
+) -> (Vec<Result<T, E>>, u64)
+where
+    F: Fn(&'a VersionedTransaction) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let semaphore = send_throttle.max_concurrency.map(Semaphore::new);
+    let num_throttled = AtomicU64::new(0);
+    let started_at = Instant::now();
+
+    let results = join_all(txs.iter().enumerate().map(|(i, tx)| {
+        let semaphore = semaphore.as_ref();
+        let num_throttled = &num_throttled;
+        let is_rate_limited = &is_rate_limited;
+        let send = &send;
+        async move {
+            if let Some(max_sends_per_second) = send_throttle.max_sends_per_second {
+                let send_at =
+                    started_at + Duration::from_secs_f64(i as f64 / max_sends_per_second as f64);
+                tokio::time::sleep_until(send_at).await;
+            }
+            let _permit = match semaphore {
+                Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore not closed")),
+                None => None,
+            };
+
+            let mut retries = 0;
+            loop {
+                match send(tx).await {
+                    Err(err)
+                        if is_rate_limited(&err)
+                            && retries < send_throttle.max_rate_limit_retries =>
+                    {
+                        num_throttled.fetch_add(1, Ordering::Relaxed);
+                        tokio::time::sleep(send_throttle.rate_limit_backoff * 2u32.pow(retries))
+                            .await;
+                        retries += 1;
+                    }
+                    result => break result,
+                }
+            }
+        }
+    }))
+    .await;
+
+    (results, num_throttled.into_inner())
+}
+
+fn classify_send_error(send_slot: Slot, send_error: ErrorKind) -> ConfirmationResponseFromRpc {
+    match min_context_slot_not_reached(&send_error) {
+        Some(context_slot) => {
+            ConfirmationResponseFromRpc::MinContextSlotNotReached(send_slot, context_slot)
+        }
+        None => ConfirmationResponseFromRpc::SendError(Arc::new(send_error)),
+    }
+}
+
+/// RPC node has not reached min_context_slot; returns the context slot of the node if reported
+pub fn min_context_slot_not_reached(error_kind: &ErrorKind) -> Option<Option<Slot>> {
+    match error_kind {
+        ErrorKind::RpcError(RpcError::RpcResponseError { code, data, .. })
+            if *code == JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED =>
+        {
+            match data {
+                RpcResponseErrorData::MinContextSlotNotReached { context_slot } => {
+                    Some(Some(*context_slot))
+                }
+                _ => Some(None),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// HTTP 429 after the retries of the rpc client were exhausted
+pub fn is_rate_limited(error_kind: &ErrorKind) -> bool {
+    match error_kind {
+        ErrorKind::Reqwest(err) => err.status().map(|status| status.as_u16()) == Some(429),
+        _ => false,
+    }
+}
+
+pub fn is_node_unhealthy(error_kind: &ErrorKind) -> bool {
+    match error_kind {
+        ErrorKind::RpcError(RpcError::RpcResponseError { code, data, .. }) => {
+            *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                || matches!(data, RpcResponseErrorData::NodeUnhealthy { .. })
+        }
+        _ => false,
+    }
+}
+
+/// blockhash was not found (most likely expired) by the RPC node when sending the transaction
+pub fn is_blockhash_not_found(error_kind: &ErrorKind) -> bool {
+    matches!(
+        error_kind.get_transaction_error(),
+        Some(TransactionError::BlockhashNotFound)
+    )
+}
+
+/// cadence and timeout of polling for the next slot
+#[derive(Clone, Copy, Debug)]
+pub struct SlotPollConfig {
+    pub poll_interval: Duration,
+    pub max_wait: Duration,
+}
 
-    Ok(result_as_vec)
+impl Default for SlotPollConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(30),
+            max_wait: Duration::from_secs(15),
+        }
+    }
+}
+
+pub async fn poll_next_slot_start(
+    rpc_client: &RpcClient,
+    poll_config: &SlotPollConfig,
+) -> Result<Slot, Error> {
+    poll_slot_change(
+        move || async move {
+            rpc_client
+                .get_slot_with_commitment(rpc_client.commitment())
+                .await
+                .map_err(Error::from)
+        },
+        poll_config,
+    )
+    .await
 }
 
-pub async fn poll_next_slot_start(rpc_client: &RpcClient) -> Result<Slot, Error> {
+/// poll until the slot advances; returns the first slot seen after the change
+async fn poll_slot_change<F, Fut>(
+    mut get_slot: F,
+    poll_config: &SlotPollConfig,
+) -> Result<Slot, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Slot, Error>>,
+{
     let started_at = Instant::now();
     let mut last_slot: Option<Slot> = None;
     let mut i = 1;
     // try to catch slot start
     let send_slot = loop {
-        if i > 500 {
-            bail!("Timeout waiting for slot change");
+        if started_at.elapsed() > poll_config.max_wait {
+            bail!(
+                "Timeout waiting for slot change after {:.03}s (last observed slot {:?}, poll interval {:?})",
+                started_at.elapsed().as_secs_f64(),
+                last_slot,
+                poll_config.poll_interval
+            );
         }
 
-        let iteration_ends_at = started_at + Duration::from_millis(i * 30);
-        let slot = rpc_client
-            .get_slot_with_commitment(CommitmentConfig::confirmed())
-            .await?;
+        let iteration_ends_at = started_at + poll_config.poll_interval * i;
+        let slot = get_slot().await?;
         trace!("polling slot {}", slot);
         if let Some(last_slot) = last_slot {
-            if last_slot + 1 == slot {
+            // slot might advance by more than one between two polls
+            if slot > last_slot {
                 break slot;
             }
         }
@@ -267,3 +716,319 @@ pub async fn poll_next_slot_start(rpc_client: &RpcClient) -> Result<Slot, Error>
     };
     Ok(send_slot)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stale_node_error(context_slot: Slot) -> ErrorKind {
+        ErrorKind::RpcError(RpcError::RpcResponseError {
+            code: JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+            message: "Minimum context slot has not been reached".to_string(),
+            data: RpcResponseErrorData::MinContextSlotNotReached { context_slot },
+        })
+    }
+
+    #[test]
+    fn test_classify_stale_node() {
+        let confirmation = classify_send_error(1000, stale_node_error(997));
+        assert!(matches!(
+            confirmation,
+            ConfirmationResponseFromRpc::MinContextSlotNotReached(1000, Some(997))
+        ));
+    }
+
+    #[test]
+    fn test_send_error_categories() {
+        let unhealthy = ConfirmationResponseFromRpc::SendError(Arc::new(ErrorKind::RpcError(
+            RpcError::RpcResponseError {
+                code: JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+                message: "Node is unhealthy".to_string(),
+                data: RpcResponseErrorData::NodeUnhealthy {
+                    num_slots_behind: Some(42),
+                },
+            },
+        )));
+        assert!(unhealthy.is_node_unhealthy());
+        assert!(!unhealthy.is_rate_limited());
+
+        assert!(classify_send_error(1000, stale_node_error(997)).is_node_unhealthy());
+
+        assert!(is_blockhash_not_found(&ErrorKind::TransactionError(
+            TransactionError::BlockhashNotFound
+        )));
+        assert!(!is_blockhash_not_found(&ErrorKind::TransactionError(
+            TransactionError::AccountInUse
+        )));
+
+        let timeout = ConfirmationResponseFromRpc::Timeout(Duration::from_secs(1));
+        assert!(!timeout.is_rate_limited());
+        assert!(!timeout.is_node_unhealthy());
+    }
+
+    #[test]
+    fn test_preflight_commitment_is_threaded_into_send_config() {
+        let send_config = bulk_send_config(1000, Some(CommitmentConfig::processed()));
+        assert_eq!(
+            send_config.preflight_commitment,
+            Some(CommitmentLevel::Processed)
+        );
+        assert!(send_config.skip_preflight);
+        assert_eq!(send_config.min_context_slot, Some(1000));
+
+        let default_config = bulk_send_config(1000, None);
+        assert_eq!(
+            default_config.preflight_commitment,
+            Some(CommitmentLevel::Confirmed)
+        );
+    }
+
+    #[test]
+    fn test_client_commitment_is_configurable() {
+        let rpc_url = Url::parse("http://localhost:8899").unwrap();
+        assert_eq!(
+            create_rpc_client(&rpc_url, None).commitment(),
+            CommitmentConfig::confirmed()
+        );
+        let rpc_client = create_rpc_client(&rpc_url, Some(CommitmentConfig::finalized()));
+        assert_eq!(rpc_client.commitment(), CommitmentConfig::finalized());
+        assert_eq!(
+            confirmation_status_for(rpc_client.commitment()),
+            TransactionConfirmationStatus::Finalized
+        );
+
+        assert!(check_client_commitment(None).is_ok());
+        assert!(check_client_commitment(Some(CommitmentConfig::finalized())).is_ok());
+        assert!(check_client_commitment(Some(CommitmentConfig::processed())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_never_seen_is_distinguished_from_timeout() {
+        let confirmed_sig = Signature::new_unique();
+        let late_sig = Signature::new_unique();
+        let slow_sig = Signature::new_unique();
+        let dropped_sig = Signature::new_unique();
+        let started_at = Instant::now();
+
+        let result_status_map = wait_for_confirmations(
+            HashSet::from([confirmed_sig, late_sig, slow_sig, dropped_sig]),
+            1000,
+            Duration::from_millis(500),
+            TransactionConfirmationStatus::Confirmed,
+            |tx_sig| {
+                if *tx_sig == confirmed_sig {
+                    Some(1002)
+                } else if *tx_sig == late_sig && started_at.elapsed() > Duration::from_millis(300) {
+                    Some(1005)
+                } else {
+                    None
+                }
+            },
+            // dropped_sig is never returned by the status source; only checked at the timeout
+            |tx_sigs| async move {
+                assert_eq!(tx_sigs.len(), 2);
+                Ok(HashSet::from([slow_sig]))
+            },
+        )
+        .await;
+
+        assert_eq!(result_status_map.len(), 4);
+        assert!(matches!(
+            result_status_map[&confirmed_sig],
+            ConfirmationResponseFromRpc::Success(1000, 1002, _, _)
+        ));
+        // still polled after it was not seen for a while
+        assert!(matches!(
+            result_status_map[&late_sig],
+            ConfirmationResponseFromRpc::Success(1000, 1005, _, _)
+        ));
+        assert!(matches!(
+            result_status_map[&slow_sig],
+            ConfirmationResponseFromRpc::Timeout(elapsed) if elapsed >= Duration::from_millis(500)
+        ));
+        assert!(matches!(
+            result_status_map[&dropped_sig],
+            ConfirmationResponseFromRpc::NeverSeen(elapsed) if elapsed >= Duration::from_millis(500)
+        ));
+    }
+
+    fn create_test_txs(count: usize) -> Vec<VersionedTransaction> {
+        (0..count)
+            .map(|_| VersionedTransaction {
+                signatures: vec![Signature::new_unique()],
+                message: Default::default(),
+            })
+            .collect()
+    }
+
+    // rejects requests if more than max_in_flight requests are in flight at the same time
+    async fn stub_send(
+        tx: &VersionedTransaction,
+        in_flight: &std::sync::atomic::AtomicUsize,
+        max_in_flight: usize,
+    ) -> Result<Signature, &'static str> {
+        let now_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        if now_in_flight > max_in_flight {
+            Err("429 Too Many Requests")
+        } else {
+            Ok(tx.signatures[0])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_throttled_send_stays_below_rate_limit() {
+        let txs = create_test_txs(20);
+        let in_flight = std::sync::atomic::AtomicUsize::new(0);
+        let throttle = SendThrottle {
+            max_concurrency: Some(3),
+            ..SendThrottle::default()
+        };
+
+        let (results, num_throttled) = send_transactions_throttled(
+            &txs,
+            &throttle,
+            |err: &&str| err.starts_with("429"),
+            |tx| stub_send(tx, &in_flight, 3),
+        )
+        .await;
+
+        assert_eq!(num_throttled, 0);
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(results[7].as_ref().unwrap(), &txs[7].signatures[0]);
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_send_gets_rate_limited() {
+        let txs = create_test_txs(20);
+        let in_flight = std::sync::atomic::AtomicUsize::new(0);
+
+        let (results, num_throttled) = send_transactions_throttled(
+            &txs,
+            &SendThrottle::unlimited(),
+            |err: &&str| err.starts_with("429"),
+            |tx| stub_send(tx, &in_flight, 3),
+        )
+        .await;
+
+        // no retries configured
+        assert_eq!(num_throttled, 0);
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 17);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_send_is_retried() {
+        let txs = create_test_txs(6);
+        let in_flight = std::sync::atomic::AtomicUsize::new(0);
+        let throttle = SendThrottle {
+            max_concurrency: None,
+            max_sends_per_second: None,
+            max_rate_limit_retries: 10,
+            rate_limit_backoff: Duration::from_millis(5),
+        };
+
+        let (results, num_throttled) = send_transactions_throttled(
+            &txs,
+            &throttle,
+            |err: &&str| err.starts_with("429"),
+            |tx| stub_send(tx, &in_flight, 3),
+        )
+        .await;
+
+        assert!(num_throttled >= 3);
+        assert!(results.iter().all(|result| result.is_ok()));
+    }
+
+    #[test]
+    fn test_simulation_errors_per_signature() {
+        let txs = create_test_txs(3);
+        let simulation_results = vec![
+            Ok(RpcSimulateTransactionResult {
+                err: None,
+                logs: None,
+                accounts: None,
+                units_consumed: Some(1200),
+                return_data: None,
+                inner_instructions: None,
+            }),
+            Ok(RpcSimulateTransactionResult {
+                err: Some(TransactionError::InsufficientFundsForFee),
+                logs: Some(vec!["Program log: failed".to_string()]),
+                accounts: None,
+                units_consumed: Some(0),
+                return_data: None,
+                inner_instructions: None,
+            }),
+            Err(ErrorKind::RpcError(RpcError::RpcRequestError(
+                "node down".to_string(),
+            ))),
+        ];
+
+        let responses = to_simulation_responses(&txs, simulation_results);
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[1].0, txs[1].signatures[0]);
+        assert!(matches!(
+            responses[0].1,
+            SimulationResponseFromRpc::Success(Some(1200))
+        ));
+        assert!(matches!(
+            &responses[1].1,
+            SimulationResponseFromRpc::SimulationError(
+                TransactionError::InsufficientFundsForFee,
+                Some(0),
+                logs
+            ) if logs.len() == 1
+        ));
+        assert!(matches!(
+            responses[2].1,
+            SimulationResponseFromRpc::SendError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_poll_slot_change() {
+        let slots = &std::sync::Mutex::new(vec![100, 100, 100, 102, 103].into_iter());
+        let poll_config = SlotPollConfig {
+            poll_interval: Duration::from_millis(1),
+            max_wait: Duration::from_secs(1),
+        };
+
+        let slot = poll_slot_change(
+            move || async move { Ok(slots.lock().unwrap().next().unwrap()) },
+            &poll_config,
+        )
+        .await
+        .unwrap();
+        // skipped slot 101
+        assert_eq!(slot, 102);
+    }
+
+    #[tokio::test]
+    async fn test_poll_slot_change_timeout() {
+        let poll_config = SlotPollConfig {
+            poll_interval: Duration::from_millis(5),
+            max_wait: Duration::from_millis(50),
+        };
+
+        let err = poll_slot_change(|| async { Ok(4242) }, &poll_config)
+            .await
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Timeout waiting for slot change"));
+        assert!(message.contains("last observed slot Some(4242)"));
+    }
+
+    #[test]
+    fn test_classify_other_send_error() {
+        let confirmation = classify_send_error(
+            1000,
+            ErrorKind::RpcError(RpcError::RpcRequestError("node down".to_string())),
+        );
+        assert!(matches!(
+            confirmation,
+            ConfirmationResponseFromRpc::SendError(_)
+        ));
+    }
+}
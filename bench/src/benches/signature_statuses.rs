@@ -0,0 +1,257 @@
+use anyhow::Context;
+use log::{info, warn};
+use std::fmt::Write;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+
+/// getSignatureStatuses calls taking longer are logged as warning
+pub const DEFAULT_SLOW_STATUS_CALL_THRESHOLD: Duration = Duration::from_millis(1000);
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct BatchSizeResult {
+    pub batch_size: usize,
+    pub calls: usize,
+    pub failed_calls: usize,
+    // calls above the slow call threshold
+    pub slow_calls: usize,
+    // latencies of the successful calls; None if all calls failed
+    pub p50_ms: Option<f32>,
+    pub p90_ms: Option<f32>,
+    pub max_ms: Option<f32>,
+}
+
+impl BatchSizeResult {
+    fn from_latencies(
+        batch_size: usize,
+        calls: usize,
+        mut latencies_ms: Vec<f32>,
+        slow_calls: usize,
+    ) -> Self {
+        latencies_ms.sort_by(f32::total_cmp);
+        // nearest rank
+        let percentile = |percent: usize| {
+            (!latencies_ms.is_empty()).then(|| {
+                let rank = (latencies_ms.len() * percent).div_ceil(100);
+                latencies_ms[rank.max(1) - 1]
+            })
+        };
+        Self {
+            batch_size,
+            calls,
+            failed_calls: calls - latencies_ms.len(),
+            slow_calls,
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            max_ms: latencies_ms.last().copied(),
+        }
+    }
+}
+
+/// warn if a getSignatureStatuses call took longer than `threshold`; returns true if it did
+pub fn warn_if_slow_status_call(elapsed: Duration, threshold: Duration, batch_size: usize) -> bool {
+    if elapsed <= threshold {
+        return false;
+    }
+    warn!(
+        "getSignatureStatuses for {} signatures took {:.0}ms (threshold {}ms)",
+        batch_size,
+        elapsed.as_secs_f32() * 1000.0,
+        threshold.as_millis()
+    );
+    true
+}
+
+/// TC5 measure the latency of getSignatureStatuses for growing batch sizes to size status-polling batches
+///
+/// the signatures are random, so the RPC looks up signatures it does not know (worst case for the lookup)
+pub async fn signature_statuses(
+    rpc_url: String,
+    batch_sizes: Vec<usize>,
+    calls_per_batch_size: usize,
+    slow_call_threshold: Duration,
+    json_output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    if batch_sizes.is_empty() {
+        anyhow::bail!("batch_sizes must not be empty");
+    }
+    if calls_per_batch_size == 0 {
+        anyhow::bail!("calls_per_batch_size must be greater than 0");
+    }
+
+    let rpc = RpcClient::new(rpc_url);
+    info!("RPC: {}", obfuscate_rpcurl(&rpc.url()));
+
+    let results = measure_signature_statuses(
+        &batch_sizes,
+        calls_per_batch_size,
+        slow_call_threshold,
+        |signatures| {
+            let rpc = &rpc;
+            async move {
+                rpc.get_signature_statuses(&signatures).await?;
+                Ok(())
+            }
+        },
+    )
+    .await;
+
+    info!(
+        "getSignatureStatuses results:\n{}",
+        format_results_table(&results)
+    );
+    match max_batch_size_without_errors(&results) {
+        Some(batch_size) => info!("largest batch size without errors: {batch_size}"),
+        None => warn!("getSignatureStatuses failed on the smallest batch size"),
+    }
+
+    if let Some(json_output) = json_output {
+        let json = serde_json::to_string_pretty(&results)?;
+        std::fs::write(&json_output, json)
+            .with_context(|| format!("write results to {}", json_output.display()))?;
+    }
+    Ok(())
+}
+
+/// calls `get_statuses` `calls_per_batch_size` times per batch size in the given order;
+/// stops after the first batch size with failed calls as larger batches are expected to fail too
+pub async fn measure_signature_statuses<F, Fut>(
+    batch_sizes: &[usize],
+    calls_per_batch_size: usize,
+    slow_call_threshold: Duration,
+    mut get_statuses: F,
+) -> Vec<BatchSizeResult>
+where
+    F: FnMut(Vec<Signature>) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut results = Vec::with_capacity(batch_sizes.len());
+    for &batch_size in batch_sizes {
+        let mut latencies_ms = Vec::with_capacity(calls_per_batch_size);
+        let mut slow_calls = 0;
+        for _ in 0..calls_per_batch_size {
+            let signatures = (0..batch_size).map(|_| Signature::new_unique()).collect();
+            let started_at = Instant::now();
+            match get_statuses(signatures).await {
+                Ok(()) => {
+                    let elapsed = started_at.elapsed();
+                    if warn_if_slow_status_call(elapsed, slow_call_threshold, batch_size) {
+                        slow_calls += 1;
+                    }
+                    latencies_ms.push(elapsed.as_secs_f32() * 1000.0);
+                }
+                Err(err) => {
+                    warn!("getSignatureStatuses for {batch_size} signatures failed: {err:?}");
+                }
+            }
+        }
+
+        let result = BatchSizeResult::from_latencies(
+            batch_size,
+            calls_per_batch_size,
+            latencies_ms,
+            slow_calls,
+        );
+        info!("{:?}", result);
+        let has_failures = result.failed_calls > 0;
+        results.push(result);
+        if has_failures {
+            break;
+        }
+    }
+    results
+}
+
+/// largest batch size of the results before the first batch size with failed calls
+pub fn max_batch_size_without_errors(results: &[BatchSizeResult]) -> Option<usize> {
+    results
+        .iter()
+        .take_while(|result| result.failed_calls == 0)
+        .map(|result| result.batch_size)
+        .max()
+}
+
+pub fn format_results_table(results: &[BatchSizeResult]) -> String {
+    let format_ms = |ms: Option<f32>| {
+        ms.map(|ms| format!("{ms:.0}"))
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+    let mut table = format!(
+        "{:>10} | {:>6} | {:>6} | {:>6} | {:>8} | {:>8} | {:>8}\n",
+        "batch size", "calls", "failed", "slow", "p50 ms", "p90 ms", "max ms"
+    );
+    for result in results {
+        writeln!(
+            table,
+            "{:>10} | {:>6} | {:>6} | {:>6} | {:>8} | {:>8} | {:>8}",
+            result.batch_size,
+            result.calls,
+            result.failed_calls,
+            result.slow_calls,
+            format_ms(result.p50_ms),
+            format_ms(result.p90_ms),
+            format_ms(result.max_ms)
+        )
+        .unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_slow_calls_are_warned() {
+        // calls with more than 10 signatures take 30ms
+        let results = measure_signature_statuses(
+            &[1, 10, 100],
+            3,
+            Duration::from_millis(20),
+            |signatures| async move {
+                if signatures.len() > 10 {
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                }
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.slow_calls)
+                .collect::<Vec<_>>(),
+            vec![0, 0, 3]
+        );
+        assert!(results[2].p50_ms.unwrap() >= 30.0);
+        assert_eq!(max_batch_size_without_errors(&results), Some(100));
+        assert_eq!(format_results_table(&results).lines().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_first_failing_batch_size() {
+        // RPC limit of 256 signatures per call
+        let results = measure_signature_statuses(
+            &[128, 256, 512, 1024],
+            2,
+            DEFAULT_SLOW_STATUS_CALL_THRESHOLD,
+            |signatures| async move {
+                if signatures.len() > 256 {
+                    anyhow::bail!("Too many inputs provided; max 256");
+                }
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].failed_calls, 2);
+        assert_eq!(results[2].p90_ms, None);
+        assert_eq!(max_batch_size_without_errors(&results), Some(256));
+    }
+}
@@ -3,14 +3,22 @@ use std::time::Duration;
 
 use bench::{
     benches::{
-        api_load::api_load, confirmation_rate::confirmation_rate,
+        api_load::api_load,
+        confirmation_rate::confirmation_rate,
         confirmation_slot::confirmation_slot,
+        load_ramp::{load_ramp, LoadRampConfig},
+        result_sink::{BenchResultSink, JsonFileResultSink, LogResultSink, PingThingResultSink},
+        rpc_interface::SendThrottle,
+        signature_statuses::{signature_statuses, DEFAULT_SLOW_STATUS_CALL_THRESHOLD},
     },
     metrics::{PingThing, PingThingCluster},
+    payer::PayerArgs,
     tx_size::TxSize,
     BenchmarkTransactionParams,
 };
 use clap::{Parser, Subcommand};
+use solana_sdk::commitment_config::CommitmentConfig;
+use url::Url;
 
 #[derive(Parser, Debug)]
 #[clap(version, about)]
@@ -23,8 +31,8 @@ struct Arguments {
 #[derive(Subcommand, Debug)]
 enum SubCommand {
     ApiLoad {
-        #[clap(short, long)]
-        payer_path: PathBuf,
+        #[clap(flatten)]
+        payer: PayerArgs,
         #[clap(short, long)]
         rpc_url: String,
         #[clap(short, long)]
@@ -35,8 +43,8 @@ enum SubCommand {
         cu_price: u64,
     },
     ConfirmationRate {
-        #[clap(short, long)]
-        payer_path: PathBuf,
+        #[clap(flatten)]
+        payer: PayerArgs,
         #[clap(short, long)]
         rpc_url: String,
         /// Set websocket source (blockSubscribe method) for transaction status updates.
@@ -58,11 +66,28 @@ enum SubCommand {
         #[clap(short, long, default_value_t = 300)]
         #[arg(short = 'f')]
         cu_price: u64,
+        /// Maximum number of concurrent send_transaction requests
+        #[clap(long, default_value_t = 32)]
+        max_concurrent_sends: usize,
+        /// Pace the send_transaction requests
+        #[clap(long)]
+        max_sends_per_second: Option<u32>,
+        /// Send all transactions at once without pacing or retries; use for local validators only
+        #[clap(long)]
+        unlimited_concurrency: bool,
+        /// Simulate the transactions instead of sending them (dry-run, no fees are paid)
+        #[clap(long)]
+        simulate_only: bool,
+        #[clap(long)]
+        ping_thing_token: Option<String>,
+        /// Cluster the ping-thing results are reported for (mainnet, testnet or devnet)
+        #[clap(long, default_value = "mainnet")]
+        ping_thing_cluster: PingThingCluster,
     },
     /// Compares the confirmation slot of txs sent to 2 different RPCs
     ConfirmationSlot {
-        #[clap(short, long)]
-        payer_path: PathBuf,
+        #[clap(flatten)]
+        payer: PayerArgs,
         /// URL of the 1st RPC
         #[clap(short, long)]
         #[arg(short = 'a')]
@@ -88,6 +113,76 @@ enum SubCommand {
         cu_price: u64,
         #[clap(long)]
         ping_thing_token: Option<String>,
+        /// Cluster the ping-thing results are reported for (mainnet, testnet or devnet)
+        #[clap(long, default_value = "mainnet")]
+        ping_thing_cluster: PingThingCluster,
+        /// Commitment the RPC checks the send slot against (processed, confirmed or finalized); defaults to confirmed
+        #[clap(long)]
+        preflight_commitment: Option<CommitmentConfig>,
+        /// Commitment of the RPC clients; slots and transaction statuses are observed at this commitment
+        /// (confirmed or finalized); defaults to confirmed
+        #[clap(long)]
+        commitment: Option<CommitmentConfig>,
+        /// Seed for the memo transactions to reproduce a run; random if not set (the seed is logged)
+        #[clap(long)]
+        seed: Option<u64>,
+        /// Append the result of each run as a line of JSON
+        #[clap(long)]
+        json_output: Option<PathBuf>,
+    },
+    /// Increases the number of txs sent at once level by level to find where confirmation degrades
+    LoadRamp {
+        #[clap(flatten)]
+        payer: PayerArgs,
+        #[clap(short, long)]
+        rpc_url: String,
+        #[clap(short = 'w', long)]
+        tx_status_websocket_addr: Option<String>,
+        #[clap(short, long)]
+        size_tx: TxSize,
+        /// Maximum confirmation time in milliseconds. After this, the txn is considered unconfirmed
+        #[clap(short, long, default_value_t = 15_000)]
+        max_timeout_ms: u64,
+        /// Number of txs sent on the first level
+        #[clap(long, default_value_t = 10)]
+        start_txs: usize,
+        /// Number of txs added on each level
+        #[clap(long, default_value_t = 10)]
+        step_txs: usize,
+        #[clap(long, default_value_t = 10)]
+        num_levels: usize,
+        /// Confirmation rate (0.0 - 1.0) below which a level is considered degraded
+        #[clap(long, default_value_t = 0.9)]
+        min_success_rate: f32,
+        /// The CU price in micro lamports
+        #[clap(short, long, default_value_t = 300)]
+        #[arg(short = 'f')]
+        cu_price: u64,
+        /// Maximum number of concurrent send_transaction requests
+        #[clap(long, default_value_t = 32)]
+        max_concurrent_sends: usize,
+        /// Pace the send_transaction requests
+        #[clap(long)]
+        max_sends_per_second: Option<u32>,
+        /// Write the per-level results as JSON
+        #[clap(long)]
+        json_output: Option<PathBuf>,
+    },
+    /// Measures the getSignatureStatuses latency for growing batch sizes
+    SignatureStatuses {
+        #[clap(short, long)]
+        rpc_url: String,
+        /// Number of signatures per call, comma separated; stops at the first batch size with errors
+        #[clap(long, value_delimiter = ',', default_value = "1,16,64,128,256,512")]
+        batch_sizes: Vec<usize>,
+        #[clap(long, default_value_t = 20)]
+        calls_per_batch_size: usize,
+        /// Calls taking longer are logged as warning
+        #[clap(long, default_value_t = DEFAULT_SLOW_STATUS_CALL_THRESHOLD.as_millis() as u64)]
+        slow_call_threshold_ms: u64,
+        /// Write the per-batch-size results as JSON
+        #[clap(long)]
+        json_output: Option<PathBuf>,
     },
 }
 
@@ -106,17 +201,17 @@ async fn main() {
 
     match args.subcommand {
         SubCommand::ApiLoad {
-            payer_path,
+            payer,
             rpc_url,
             test_duration_ms,
             cu_price,
         } => {
-            api_load(&payer_path, rpc_url, test_duration_ms, cu_price)
+            api_load(&payer.into(), rpc_url, test_duration_ms, cu_price)
                 .await
                 .unwrap();
         }
         SubCommand::ConfirmationRate {
-            payer_path,
+            payer,
             rpc_url,
             tx_status_websocket_addr,
             size_tx,
@@ -124,8 +219,14 @@ async fn main() {
             txs_per_run,
             num_of_runs,
             cu_price,
+            max_concurrent_sends,
+            max_sends_per_second,
+            unlimited_concurrency,
+            simulate_only,
+            ping_thing_token,
+            ping_thing_cluster,
         } => confirmation_rate(
-            &payer_path,
+            &payer.into(),
             rpc_url,
             tx_status_websocket_addr,
             BenchmarkTransactionParams {
@@ -135,11 +236,22 @@ async fn main() {
             Duration::from_millis(max_timeout_ms),
             txs_per_run,
             num_of_runs,
+            if unlimited_concurrency {
+                SendThrottle::unlimited()
+            } else {
+                SendThrottle {
+                    max_concurrency: Some(max_concurrent_sends),
+                    max_sends_per_second,
+                    ..SendThrottle::default()
+                }
+            },
+            simulate_only,
+            ping_thing_token.map(|t| PingThing::new(ping_thing_cluster, t)),
         )
         .await
         .unwrap(),
         SubCommand::ConfirmationSlot {
-            payer_path,
+            payer,
             rpc_a,
             rpc_b,
             tx_status_websocket_addr_a,
@@ -149,22 +261,96 @@ async fn main() {
             num_of_runs,
             cu_price,
             ping_thing_token,
-        } => confirmation_slot(
-            &payer_path,
-            rpc_a,
-            rpc_b,
-            tx_status_websocket_addr_a,
-            tx_status_websocket_addr_b,
+            ping_thing_cluster,
+            preflight_commitment,
+            commitment,
+            seed,
+            json_output,
+        } => {
+            let mut sinks: Vec<Box<dyn BenchResultSink>> = vec![Box::new(LogResultSink)];
+            if let Some(json_output) = json_output {
+                sinks.push(Box::new(JsonFileResultSink::create(json_output).unwrap()));
+            }
+            if let Some(ping_thing_token) = ping_thing_token {
+                let rpc_a_url = Url::parse(&rpc_a).unwrap();
+                let rpc_b_url = Url::parse(&rpc_b).unwrap();
+                let ping_thing = PingThing::new(ping_thing_cluster, ping_thing_token);
+                let ping_thing_sink =
+                    PingThingResultSink::validated(ping_thing, &[&rpc_a_url, &rpc_b_url])
+                        .await
+                        .unwrap();
+                sinks.push(Box::new(ping_thing_sink));
+            }
+            confirmation_slot(
+                &payer.into(),
+                rpc_a,
+                rpc_b,
+                tx_status_websocket_addr_a,
+                tx_status_websocket_addr_b,
+                BenchmarkTransactionParams {
+                    tx_size: size_tx,
+                    cu_price_micro_lamports: cu_price,
+                },
+                Duration::from_millis(max_timeout_ms),
+                num_of_runs,
+                sinks,
+                preflight_commitment,
+                commitment,
+                seed,
+            )
+            .await
+            .unwrap();
+        }
+        SubCommand::LoadRamp {
+            payer,
+            rpc_url,
+            tx_status_websocket_addr,
+            size_tx,
+            max_timeout_ms,
+            start_txs,
+            step_txs,
+            num_levels,
+            min_success_rate,
+            cu_price,
+            max_concurrent_sends,
+            max_sends_per_second,
+            json_output,
+        } => load_ramp(
+            &payer.into(),
+            rpc_url,
+            tx_status_websocket_addr,
             BenchmarkTransactionParams {
                 tx_size: size_tx,
                 cu_price_micro_lamports: cu_price,
             },
             Duration::from_millis(max_timeout_ms),
-            num_of_runs,
-            ping_thing_token.map(|t| PingThing {
-                cluster: PingThingCluster::Mainnet,
-                va_api_key: t,
-            }),
+            LoadRampConfig {
+                start_txs,
+                step_txs,
+                num_levels,
+                min_success_rate,
+            },
+            SendThrottle {
+                max_concurrency: Some(max_concurrent_sends),
+                max_sends_per_second,
+                ..SendThrottle::default()
+            },
+            json_output,
+        )
+        .await
+        .unwrap(),
+        SubCommand::SignatureStatuses {
+            rpc_url,
+            batch_sizes,
+            calls_per_batch_size,
+            slow_call_threshold_ms,
+            json_output,
+        } => signature_statuses(
+            rpc_url,
+            batch_sizes,
+            calls_per_batch_size,
+            Duration::from_millis(slow_call_threshold_ms),
+            json_output,
         )
         .await
         .unwrap(),
@@ -28,6 +28,7 @@ pub mod bench1;
 pub mod benches;
 pub mod helpers;
 pub mod metrics;
+pub mod payer;
 pub mod service_adapter1;
 pub mod service_adapter_new;
 pub mod tx_size;
@@ -57,6 +58,7 @@ pub struct Args {
     pub large_transactions: bool,
 }
 
+#[derive(Clone)]
 pub struct BenchmarkTransactionParams {
     pub tx_size: TxSize,
     pub cu_price_micro_lamports: u64,
@@ -145,11 +147,17 @@ pub async fn send_and_confirm_transactions(
             })
             .collect_vec();
 
+        let started_at = Instant::now();
         let mut statuses = rpc_client
             .get_signature_statuses(&sigs)
             .await?
             .value
             .into_iter();
+        benches::signature_statuses::warn_if_slow_status_call(
+            started_at.elapsed(),
+            benches::signature_statuses::DEFAULT_SLOW_STATUS_CALL_THRESHOLD,
+            sigs.len(),
+        );
 
         results.iter_mut().for_each(|result| {
             if let Ok(None) = result {
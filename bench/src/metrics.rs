@@ -1,13 +1,22 @@
 use std::{
     fmt::{self, Display},
     ops::{AddAssign, DivAssign},
+    str::FromStr,
     time::Duration,
 };
 
+use futures::StreamExt;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use solana_sdk::{signature::Signature, slot_history::Slot};
-use tracing::debug;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, signature::Signature, slot_history::Slot};
+use tracing::{debug, warn};
+
+use crate::benches::rpc_interface::ConfirmationResponseFromRpc;
+
+const PING_THING_API_URL: &str = "https://www.validators.app/api/v1/ping-thing";
+/// bulk benches produce many results at once; do not flood the PingThing API
+const PING_THING_MAX_CONCURRENT_SUBMISSIONS: usize = 4;
 
 #[derive(Clone, Copy, Debug, Default, serde::Serialize)]
 pub struct Metric {
@@ -148,8 +157,9 @@ pub struct TxMetricData {
     pub time_to_confirm_in_millis: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub enum PingThingCluster {
+    #[default]
     Mainnet,
     Testnet,
     Devnet,
@@ -157,16 +167,64 @@ pub enum PingThingCluster {
 
 impl PingThingCluster {
     pub fn from_arg(cluster: String) -> Self {
+        cluster.parse().expect("incorrect cluster name")
+    }
+}
+
+impl FromStr for PingThingCluster {
+    type Err = String;
+
+    fn from_str(cluster: &str) -> Result<Self, Self::Err> {
         match cluster.to_lowercase().as_str() {
-            "mainnet" => PingThingCluster::Mainnet,
-            "testnet" => PingThingCluster::Testnet,
-            "devnet" => PingThingCluster::Devnet,
-            _ => panic!("incorrect cluster name"),
+            "mainnet" => Ok(PingThingCluster::Mainnet),
+            "testnet" => Ok(PingThingCluster::Testnet),
+            "devnet" => Ok(PingThingCluster::Devnet),
+            _ => Err(format!(
+                "incorrect cluster name {cluster} (mainnet, testnet or devnet)"
+            )),
         }
     }
 }
 
 impl PingThingCluster {
+    const ALL: [PingThingCluster; 3] = [
+        PingThingCluster::Mainnet,
+        PingThingCluster::Testnet,
+        PingThingCluster::Devnet,
+    ];
+
+    pub fn genesis_hash(&self) -> Hash {
+        match self {
+            PingThingCluster::Mainnet => "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d",
+            PingThingCluster::Testnet => "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY",
+            PingThingCluster::Devnet => "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG",
+        }
+        .parse()
+        .expect("valid genesis hash")
+    }
+
+    /// make sure results from e.g. a devnet RPC do not get reported as mainnet
+    pub fn check_genesis_hash(&self, genesis_hash: &Hash) -> anyhow::Result<()> {
+        if self.genesis_hash() == *genesis_hash {
+            return Ok(());
+        }
+        match Self::ALL
+            .iter()
+            .find(|cluster| cluster.genesis_hash() == *genesis_hash)
+        {
+            Some(actual) => anyhow::bail!(
+                "RPC belongs to {} but ping-thing results would be reported as {}",
+                actual.to_url_part(),
+                self.to_url_part()
+            ),
+            None => anyhow::bail!(
+                "RPC genesis hash {} does not belong to {}",
+                genesis_hash,
+                self.to_url_part()
+            ),
+        }
+    }
+
     pub fn to_url_part(&self) -> String {
         match self {
             PingThingCluster::Mainnet => "mainnet",
@@ -196,6 +254,7 @@ impl Display for PingThingTxType {
 pub struct PingThing {
     pub cluster: PingThingCluster,
     pub va_api_key: String,
+    pub api_url: String,
 }
 
 /// request format see https://github.com/Block-Logic/ping-thing-client/blob/4c008c741164702a639c282f1503a237f7d95e64/ping-thing-client.mjs#L160
@@ -212,6 +271,61 @@ struct PingThingData {
 }
 
 impl PingThing {
+    pub fn new(cluster: PingThingCluster, va_api_key: String) -> Self {
+        Self {
+            cluster,
+            va_api_key,
+            api_url: PING_THING_API_URL.to_string(),
+        }
+    }
+
+    /// fail if the RPC does not belong to the cluster the results are reported for
+    pub async fn validate_rpc_cluster(&self, rpc: &RpcClient) -> anyhow::Result<()> {
+        let genesis_hash = rpc.get_genesis_hash().await?;
+        self.cluster.check_genesis_hash(&genesis_hash)
+    }
+
+    /// submit the confirmed transactions of a bulk run; timeouts and send errors are skipped
+    /// returns the number of successful submissions
+    pub async fn submit_bulk_confirmations(
+        &self,
+        confirmations: &[(Signature, u64, ConfirmationResponseFromRpc)],
+        tx_type: PingThingTxType,
+    ) -> usize {
+        futures::stream::iter(
+            confirmations
+                .iter()
+                .filter_map(|(tx_sig, _, confirmation)| match confirmation {
+                    ConfirmationResponseFromRpc::Success(
+                        slot_sent,
+                        slot_landed,
+                        _,
+                        confirmation_time,
+                    ) => Some(self.submit_confirmed_stats(
+                        *confirmation_time,
+                        *tx_sig,
+                        tx_type.clone(),
+                        true,
+                        *slot_sent,
+                        *slot_landed,
+                    )),
+                    _ => None,
+                }),
+        )
+        .buffer_unordered(PING_THING_MAX_CONCURRENT_SUBMISSIONS)
+        .filter_map(|result| async move {
+            match result {
+                Ok(()) => Some(()),
+                Err(err) => {
+                    warn!("Failed to submit stats to ping-thing: {:?}", err);
+                    None
+                }
+            }
+        })
+        .count()
+        .await
+    }
+
     pub async fn submit_confirmed_stats(
         &self,
         tx_elapsed: Duration,
@@ -222,6 +336,7 @@ impl PingThing {
         slot_landed: Slot,
     ) -> anyhow::Result<()> {
         submit_stats_to_ping_thing(
+            &self.api_url,
             self.cluster.clone(),
             self.va_api_key.clone(),
             tx_elapsed,
@@ -239,6 +354,7 @@ impl PingThing {
 /// Assumes that the txn was sent on Mainnet and had the "confirmed" commitment level
 #[allow(clippy::too_many_arguments)]
 async fn submit_stats_to_ping_thing(
+    api_url: &str,
     cluster: PingThingCluster,
     va_api_key: String,
     tx_elapsed: Duration,
@@ -262,10 +378,7 @@ async fn submit_stats_to_ping_thing(
     let client = reqwest::Client::new();
     // cluster: 'mainnet'
     let response = client
-        .post(format!(
-            "https://www.validators.app/api/v1/ping-thing/{}",
-            cluster.to_url_part()
-        ))
+        .post(format!("{}/{}", api_url, cluster.to_url_part()))
         .header("Content-Type", "application/json")
         .header("Token", va_api_key)
         .json(&submit_data_request)
@@ -285,10 +398,7 @@ async fn test_ping_thing() {
     let token = "".to_string();
     assert!(token.is_empty(), "Empty token for ping thing test");
 
-    let ping_thing = PingThing {
-        cluster: PingThingCluster::Mainnet,
-        va_api_key: token,
-    };
+    let ping_thing = PingThing::new(PingThingCluster::Mainnet, token);
 
     ping_thing
         .submit_confirmed_stats(
@@ -302,3 +412,158 @@ async fn test_ping_thing() {
         .await
         .unwrap();
 }
+
+/// minimal http endpoint recording the (path, json body) of every request posted to it
+#[cfg(test)]
+async fn start_mock_ping_thing() -> (
+    String,
+    std::sync::Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let api_url = format!("http://{}", listener.local_addr().unwrap());
+    let received = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+    let received_by_server = received.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let (header_len, content_length) = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                    continue;
+                };
+                let headers = String::from_utf8_lossy(&request[..pos]).to_lowercase();
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|len| len.trim().parse::<usize>().unwrap())
+                    .unwrap_or_default();
+                break (pos + 4, content_length);
+            };
+            while request.len() < header_len + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let path = String::from_utf8_lossy(&request)
+                .split_whitespace()
+                .nth(1)
+                .unwrap()
+                .to_string();
+            let body = serde_json::from_slice(&request[header_len..]).unwrap();
+            received_by_server.lock().unwrap().push((path, body));
+
+            stream
+                .write_all(
+                    b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    (api_url, received)
+}
+
+#[tokio::test]
+async fn test_ping_thing_bulk_submits_confirmed_only() {
+    use solana_transaction_status::TransactionConfirmationStatus;
+    use std::sync::Arc;
+
+    let (api_url, received) = start_mock_ping_thing().await;
+    let ping_thing = PingThing {
+        api_url,
+        ..PingThing::new(PingThingCluster::Mainnet, "token".to_string())
+    };
+
+    let confirmed_sig = Signature::new_unique();
+    let confirmations = vec![
+        (
+            confirmed_sig,
+            0,
+            ConfirmationResponseFromRpc::Success(
+                100,
+                102,
+                TransactionConfirmationStatus::Confirmed,
+                Duration::from_millis(850),
+            ),
+        ),
+        (
+            Signature::new_unique(),
+            1,
+            ConfirmationResponseFromRpc::Timeout(Duration::from_secs(15)),
+        ),
+        (
+            Signature::new_unique(),
+            0,
+            ConfirmationResponseFromRpc::SendError(Arc::new(
+                solana_rpc_client_api::client_error::ErrorKind::Custom("boom".to_string()),
+            )),
+        ),
+    ];
+
+    let num_submitted = ping_thing
+        .submit_bulk_confirmations(&confirmations, PingThingTxType::Memo)
+        .await;
+
+    assert_eq!(num_submitted, 1);
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let (path, body) = &received[0];
+    assert_eq!(path, "/mainnet");
+    assert_eq!(body["signature"], confirmed_sig.to_string());
+    assert_eq!(body["transaction_type"], "memo");
+    assert_eq!(body["time"], 850);
+    assert_eq!(body["slot_sent"], 100);
+    assert_eq!(body["slot_landed"], 102);
+}
+
+#[tokio::test]
+async fn test_ping_thing_submits_selected_cluster() {
+    let (api_url, received) = start_mock_ping_thing().await;
+    let ping_thing = PingThing {
+        api_url,
+        ..PingThing::new(
+            "devnet".parse::<PingThingCluster>().unwrap(),
+            "token".to_string(),
+        )
+    };
+
+    ping_thing
+        .submit_confirmed_stats(
+            Duration::from_millis(500),
+            Signature::new_unique(),
+            PingThingTxType::Memo,
+            true,
+            10,
+            11,
+        )
+        .await
+        .unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, "/devnet");
+}
+
+#[test]
+fn test_ping_thing_cluster_genesis_hash() {
+    let devnet_genesis = PingThingCluster::Devnet.genesis_hash();
+
+    assert!(PingThingCluster::Devnet
+        .check_genesis_hash(&devnet_genesis)
+        .is_ok());
+    let err = PingThingCluster::Mainnet
+        .check_genesis_hash(&devnet_genesis)
+        .unwrap_err();
+    assert!(err.to_string().contains("RPC belongs to devnet"));
+    assert!(PingThingCluster::Mainnet
+        .check_genesis_hash(&Hash::new_unique())
+        .is_err());
+    assert!("localnet".parse::<PingThingCluster>().is_err());
+}
@@ -0,0 +1,111 @@
+use anyhow::{bail, Context};
+use clap::Args;
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use std::env;
+use std::path::PathBuf;
+
+/// default env variable holding the payer keypair
+pub const PAYER_KEYPAIR_ENV: &str = "PAYER_KEYPAIR";
+
+#[derive(Args, Debug, Clone)]
+pub struct PayerArgs {
+    /// Path to the payer keypair file; takes precedence over --payer-from-env
+    #[clap(short, long)]
+    pub payer_path: Option<PathBuf>,
+    /// Env variable with the payer keypair as base58 secret key or JSON byte array; used if no payer path is given
+    #[clap(long, default_value = PAYER_KEYPAIR_ENV)]
+    pub payer_from_env: String,
+}
+
+/// where the benchmark payer keypair is loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayerSource {
+    File(PathBuf),
+    Env(String),
+}
+
+impl From<PayerArgs> for PayerSource {
+    fn from(args: PayerArgs) -> Self {
+        match args.payer_path {
+            Some(payer_path) => PayerSource::File(payer_path),
+            None => PayerSource::Env(args.payer_from_env),
+        }
+    }
+}
+
+impl PayerSource {
+    pub fn load(&self) -> anyhow::Result<Keypair> {
+        match self {
+            PayerSource::File(payer_path) => read_keypair_file(payer_path).map_err(|err| {
+                anyhow::anyhow!("Cannot read payer keypair file {payer_path:?}: {err}")
+            }),
+            PayerSource::Env(env_var) => {
+                let payer = env::var(env_var).with_context(|| {
+                    format!(
+                        "No payer keypair: neither --payer-path nor env variable {env_var} is set"
+                    )
+                })?;
+                parse_payer_keypair(&payer)
+                    .with_context(|| format!("Invalid payer keypair in env variable {env_var}"))
+            }
+        }
+    }
+}
+
+/// parse keypair from a base58 encoded secret key or a JSON byte array (as in Solana CLI keypair files)
+pub fn parse_payer_keypair(input: &str) -> anyhow::Result<Keypair> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Payer keypair is empty");
+    }
+
+    let payer_bytes: Vec<u8> = if input.starts_with('[') {
+        serde_json::from_str(input).context("Invalid payer format expected Vec<u8>")?
+    } else {
+        bs58::decode(input)
+            .into_vec()
+            .context("Payer is neither a JSON byte array nor a base58 secret key")?
+    };
+    Keypair::from_bytes(&payer_bytes).context("Invalid payer secret key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn test_parse_payer_keypair_env_string() {
+        let keypair = Keypair::new();
+
+        let json_array = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        let parsed = parse_payer_keypair(&format!("{json_array}\n")).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+
+        let parsed = parse_payer_keypair(&keypair.to_base58_string()).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+
+        assert!(parse_payer_keypair("").is_err());
+        assert!(parse_payer_keypair("[1, 2, 3]").is_err());
+        assert!(parse_payer_keypair("not-base58-0OIl").is_err());
+    }
+
+    #[test]
+    fn test_payer_path_takes_precedence() {
+        let source = PayerSource::from(PayerArgs {
+            payer_path: Some(PathBuf::from("payer.json")),
+            payer_from_env: PAYER_KEYPAIR_ENV.to_string(),
+        });
+        assert_eq!(source, PayerSource::File(PathBuf::from("payer.json")));
+
+        let source = PayerSource::from(PayerArgs {
+            payer_path: None,
+            payer_from_env: "BENCH_TEST_PAYER_NOT_SET".to_string(),
+        });
+        assert_eq!(
+            source,
+            PayerSource::Env("BENCH_TEST_PAYER_NOT_SET".to_string())
+        );
+        assert!(source.load().is_err());
+    }
+}
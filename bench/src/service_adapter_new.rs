@@ -1,9 +1,11 @@
 use crate::benches::confirmation_rate;
 use crate::benches::confirmation_rate::send_bulk_txs_and_wait;
+use crate::benches::rpc_interface::SendThrottle;
 use crate::service_adapter1::BenchConfig;
 use crate::BenchmarkTransactionParams;
 use log::error;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::signature::Keypair;
 use std::sync::Arc;
 use std::time::Duration;
@@ -15,7 +17,10 @@ pub async fn benchnew_confirmation_rate_servicerunner(
     tx_status_websocket_addr: Option<String>,
     funded_payer: Keypair,
 ) -> confirmation_rate::Metric {
-    let rpc = Arc::new(RpcClient::new(rpc_addr.clone()));
+    let rpc = Arc::new(RpcClient::new_with_commitment(
+        rpc_addr.clone(),
+        CommitmentConfig::confirmed(),
+    ));
     let tx_params = BenchmarkTransactionParams {
         tx_size: bench_config.tx_size,
         cu_price_micro_lamports: bench_config.cu_price_micro_lamports,
@@ -32,6 +37,8 @@ pub async fn benchnew_confirmation_rate_servicerunner(
         bench_config.tx_count,
         &tx_params,
         max_timeout,
+        &SendThrottle::default(),
+        None,
     )
     .await;
     result.unwrap_or_else(|err| {
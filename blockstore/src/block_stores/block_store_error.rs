@@ -0,0 +1,60 @@
+use solana_sdk::slot_history::Slot;
+
+use crate::block_stores::postgres::PostgresQueryError;
+
+/// lets callers tell a missing block from a failing backend without matching on error strings
+#[derive(Debug, thiserror::Error)]
+pub enum BlockStoreError {
+    /// slot is in the range served by the store but the block is not there (e.g. skipped slot)
+    #[error("Block {slot} not found")]
+    NotFound { slot: Slot },
+    /// slot is not served by any of the configured stores
+    #[error("Block {slot} is out of range of the block store")]
+    OutOfRange { slot: Slot },
+    /// slot is older than the oldest archived block - its epoch was pruned from the store
+    #[error(
+        "Block {slot} was pruned from the block store (oldest available slot {oldest_available})"
+    )]
+    Pruned { slot: Slot, oldest_available: Slot },
+    /// there is no schema for the epoch of the slot in the block store
+    #[error("Block {slot} is not in the block store: no schema for epoch {epoch}")]
+    EpochNotArchived { slot: Slot, epoch: u64 },
+    /// the epoch schema of the slot was created but no blocks were written to it yet
+    #[error("Block {slot} is not in the block store yet: schema for epoch {epoch} has no blocks")]
+    EpochEmpty { slot: Slot, epoch: u64 },
+    /// stored data of the block is inconsistent
+    #[error("Block {slot} is corrupt: {reason}")]
+    Corrupt { slot: Slot, reason: String },
+    /// block has more transactions than the configured limit
+    #[error("Block {slot} has {count} transactions which is more than the configured limit")]
+    TooLarge { slot: Slot, count: u64 },
+    #[error("Slot range {start}..={end} is too large; max {max_range}")]
+    SlotRangeTooLarge {
+        start: Slot,
+        end: Slot,
+        max_range: u64,
+    },
+    #[error(transparent)]
+    Backend(#[from] anyhow::Error),
+}
+
+impl From<PostgresQueryError> for BlockStoreError {
+    fn from(err: PostgresQueryError) -> Self {
+        BlockStoreError::Backend(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converts_into_anyhow() {
+        let err: anyhow::Error = BlockStoreError::NotFound { slot: 42 }.into();
+        assert_eq!(err.to_string(), "Block 42 not found");
+        assert!(matches!(
+            err.downcast_ref::<BlockStoreError>(),
+            Some(BlockStoreError::NotFound { slot: 42 })
+        ));
+    }
+}
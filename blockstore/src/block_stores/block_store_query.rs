@@ -0,0 +1,22 @@
+use crate::block_stores::block_store_error::BlockStoreError;
+use async_trait::async_trait;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::slot_history::Slot;
+use std::ops::RangeInclusive;
+
+/// read access to archived blocks; lets consumers of the query store be tested without a database
+#[async_trait]
+pub trait BlockStoreQuery: Send + Sync {
+    async fn query_block(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError>;
+
+    /// archived blocks in the slot range in slot order; missing slots are skipped
+    async fn query_blocks(
+        &self,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<Vec<ProducedBlock>, BlockStoreError>;
+
+    /// lowest to highest archived slot; None if no blocks are archived
+    async fn get_slot_range(&self) -> Result<Option<RangeInclusive<Slot>>, BlockStoreError>;
+
+    async fn is_block_in_range(&self, slot: Slot) -> bool;
+}
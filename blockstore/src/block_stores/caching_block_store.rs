@@ -0,0 +1,203 @@
+use crate::block_stores::block_store_error::BlockStoreError;
+use crate::block_stores::block_store_query::BlockStoreQuery;
+use prometheus::{opts, register_int_counter, IntCounter};
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::slot_history::Slot;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+lazy_static::lazy_static! {
+    static ref BLOCK_CACHE_HIT: IntCounter =
+    register_int_counter!(opts!("literpc_blockstore_block_cache_hit", "Blocks served from the in-memory block cache")).unwrap();
+    static ref BLOCK_CACHE_MISS: IntCounter =
+    register_int_counter!(opts!("literpc_blockstore_block_cache_miss", "Blocks not in the in-memory block cache and queried from postgres")).unwrap();
+}
+
+pub const DEFAULT_BLOCK_CACHE_CAPACITY: usize = 128;
+
+/// LRU of the most recently requested blocks; blocks share their data through the Arc inside [ProducedBlock]
+pub struct BlockCache {
+    capacity: usize,
+    // blocks by slot and slots from least to most recently used
+    lru: Mutex<(HashMap<Slot, ProducedBlock>, VecDeque<Slot>)>,
+}
+
+impl BlockCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "block cache capacity must be greater than 0");
+        Self {
+            capacity,
+            lru: Mutex::new((
+                HashMap::with_capacity(capacity),
+                VecDeque::with_capacity(capacity),
+            )),
+        }
+    }
+
+    pub fn get(&self, slot: Slot) -> Option<ProducedBlock> {
+        let mut lru = self.lru.lock().unwrap();
+        let (blocks, order) = &mut *lru;
+        let block = blocks.get(&slot)?.clone();
+        Self::touch(order, slot);
+        Some(block)
+    }
+
+    pub fn insert(&self, slot: Slot, block: ProducedBlock) {
+        let mut lru = self.lru.lock().unwrap();
+        let (blocks, order) = &mut *lru;
+        if blocks.insert(slot, block).is_some() {
+            Self::touch(order, slot);
+            return;
+        }
+        order.push_back(slot);
+        if order.len() > self.capacity {
+            if let Some(evicted) = order.pop_front() {
+                blocks.remove(&evicted);
+            }
+        }
+    }
+
+    /// returns the cached block or calls `fetch` and caches the result; errors are not cached
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        slot: Slot,
+        fetch: F,
+    ) -> Result<ProducedBlock, BlockStoreError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ProducedBlock, BlockStoreError>>,
+    {
+        if let Some(block) = self.get(slot) {
+            BLOCK_CACHE_HIT.inc();
+            return Ok(block);
+        }
+
+        BLOCK_CACHE_MISS.inc();
+        let block = fetch().await?;
+        self.insert(slot, block.clone());
+        Ok(block)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn touch(order: &mut VecDeque<Slot>, slot: Slot) {
+        if let Some(position) = order.iter().position(|cached| *cached == slot) {
+            order.remove(position);
+        }
+        order.push_back(slot);
+    }
+}
+
+/// serves repeated [BlockStoreQuery::query_block] calls for the same slot from memory
+pub struct CachingBlockStore {
+    block_storage_query: Arc<dyn BlockStoreQuery>,
+    cache: BlockCache,
+}
+
+impl CachingBlockStore {
+    pub fn new(block_storage_query: Arc<dyn BlockStoreQuery>, capacity: usize) -> Self {
+        Self {
+            block_storage_query,
+            cache: BlockCache::new(capacity),
+        }
+    }
+
+    pub async fn query_block(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError> {
+        self.cache
+            .get_or_fetch(slot, || self.block_storage_query.query_block(slot))
+            .await
+    }
+
+    pub fn block_storage_query(&self) -> &dyn BlockStoreQuery {
+        self.block_storage_query.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_stores::test_utils::create_test_block;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn counting_fetch(
+        cache: &BlockCache,
+        slot: Slot,
+        num_fetches: &AtomicUsize,
+    ) -> Result<ProducedBlock, BlockStoreError> {
+        cache
+            .get_or_fetch(slot, || async move {
+                num_fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(create_test_block(slot, CommitmentConfig::finalized()))
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_second_fetch_is_served_from_cache() {
+        let cache = BlockCache::new(2);
+        let num_fetches = AtomicUsize::new(0);
+
+        let first = counting_fetch(&cache, 42, &num_fetches).await.unwrap();
+        let second = counting_fetch(&cache, 42, &num_fetches).await.unwrap();
+
+        assert_eq!(num_fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(first.blockhash, second.blockhash);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_block_is_evicted() {
+        let cache = BlockCache::new(2);
+        let num_fetches = AtomicUsize::new(0);
+
+        counting_fetch(&cache, 1, &num_fetches).await.unwrap();
+        counting_fetch(&cache, 2, &num_fetches).await.unwrap();
+        // 1 becomes the most recently used
+        counting_fetch(&cache, 1, &num_fetches).await.unwrap();
+        counting_fetch(&cache, 3, &num_fetches).await.unwrap();
+        assert_eq!(num_fetches.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.get(1).is_some());
+        assert!(cache.get(2).is_none());
+        assert!(cache.get(3).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_caching_block_store_with_mock() {
+        use crate::block_stores::mock_block_store_query::MockBlockStoreQuery;
+
+        let mock = Arc::new(MockBlockStoreQuery::new([create_test_block(
+            42,
+            CommitmentConfig::finalized(),
+        )]));
+        let caching_block_store = CachingBlockStore::new(mock.clone(), 2);
+
+        let first = caching_block_store.query_block(42).await.unwrap();
+        let second = caching_block_store.query_block(42).await.unwrap();
+        assert_eq!(first.blockhash, second.blockhash);
+        assert!(matches!(
+            caching_block_store.query_block(43).await,
+            Err(BlockStoreError::NotFound { slot: 43 })
+        ));
+        assert_eq!(mock.num_block_queries(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = BlockCache::new(2);
+
+        let result = cache
+            .get_or_fetch(7, || async { Err(BlockStoreError::NotFound { slot: 7 }) })
+            .await;
+        assert!(result.is_err());
+        assert!(cache.is_empty());
+    }
+}
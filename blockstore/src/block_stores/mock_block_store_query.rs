@@ -0,0 +1,78 @@
+use crate::block_stores::block_store_error::BlockStoreError;
+use crate::block_stores::block_store_query::BlockStoreQuery;
+use async_trait::async_trait;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::slot_history::Slot;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+/// in-memory [BlockStoreQuery] for unit tests
+#[derive(Default)]
+pub struct MockBlockStoreQuery {
+    blocks: RwLock<BTreeMap<Slot, ProducedBlock>>,
+    num_block_queries: AtomicUsize,
+}
+
+impl MockBlockStoreQuery {
+    pub fn new(blocks: impl IntoIterator<Item = ProducedBlock>) -> Self {
+        let mock = Self::default();
+        for block in blocks {
+            mock.insert(block);
+        }
+        mock
+    }
+
+    pub fn insert(&self, block: ProducedBlock) {
+        self.blocks.write().unwrap().insert(block.slot, block);
+    }
+
+    /// number of [BlockStoreQuery::query_block] calls so far
+    pub fn num_block_queries(&self) -> usize {
+        self.num_block_queries.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl BlockStoreQuery for MockBlockStoreQuery {
+    async fn query_block(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError> {
+        self.num_block_queries.fetch_add(1, Ordering::Relaxed);
+        self.blocks
+            .read()
+            .unwrap()
+            .get(&slot)
+            .cloned()
+            .ok_or(BlockStoreError::NotFound { slot })
+    }
+
+    async fn query_blocks(
+        &self,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<Vec<ProducedBlock>, BlockStoreError> {
+        // BTreeMap::range panics on reversed ranges
+        if slots.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .blocks
+            .read()
+            .unwrap()
+            .range(slots)
+            .map(|(_slot, block)| block.clone())
+            .collect())
+    }
+
+    async fn get_slot_range(&self) -> Result<Option<RangeInclusive<Slot>>, BlockStoreError> {
+        let blocks = self.blocks.read().unwrap();
+        Ok(blocks
+            .keys()
+            .next()
+            .zip(blocks.keys().next_back())
+            .map(|(first, last)| *first..=*last))
+    }
+
+    async fn is_block_in_range(&self, slot: Slot) -> bool {
+        matches!(self.get_slot_range().await, Ok(Some(range)) if range.contains(&slot))
+    }
+}
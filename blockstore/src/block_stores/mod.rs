@@ -1,3 +1,10 @@
+pub mod block_store_error;
+pub mod block_store_query;
+pub mod caching_block_store;
 pub mod faithful_history;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock_block_store_query;
 pub mod multiple_strategy_block_store;
 pub mod postgres;
+#[cfg(test)]
+mod test_utils;
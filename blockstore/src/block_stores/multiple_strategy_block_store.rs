@@ -1,6 +1,7 @@
+use crate::block_stores::block_store_error::BlockStoreError;
+use crate::block_stores::block_store_query::BlockStoreQuery;
 use crate::block_stores::faithful_history::faithful_block_store::FaithfulBlockStore;
-use crate::block_stores::postgres::postgres_block_store_query::PostgresQueryBlockStore;
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use log::{debug, trace};
 use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
@@ -33,7 +34,7 @@ impl Deref for BlockStorageData {
 
 // you might need to add a read-cache instead
 pub struct MultipleStrategyBlockStorage {
-    block_storage_query: PostgresQueryBlockStore,
+    block_storage_query: Arc<dyn BlockStoreQuery>,
     // note supported ATM
     faithful_block_storage: Option<FaithfulBlockStore>, // to fetch legacy blocks from faithful_history
                                                         // last_confirmed_slot: Arc<AtomicU64>,
@@ -41,7 +42,7 @@ pub struct MultipleStrategyBlockStorage {
 
 impl MultipleStrategyBlockStorage {
     pub fn new(
-        block_storage_query: PostgresQueryBlockStore,
+        block_storage_query: Arc<dyn BlockStoreQuery>,
         _faithful_rpc_client: Option<Arc<RpcClient>>,
     ) -> Self {
         Self {
@@ -53,9 +54,13 @@ impl MultipleStrategyBlockStorage {
     }
 
     // we need to build the slots from right to left
-    pub async fn get_slot_range(&self) -> RangeInclusive<Slot> {
+    pub async fn get_slot_range(&self) -> Result<RangeInclusive<Slot>> {
         // merge them
-        let persistent_storage_range = self.block_storage_query.get_slot_range().await;
+        let persistent_storage_range = self
+            .block_storage_query
+            .get_slot_range()
+            .await?
+            .context("no blocks in persistent storage")?;
         trace!("Persistent storage range: {:?}", persistent_storage_range);
 
         let mut lower = *persistent_storage_range.start();
@@ -75,7 +80,7 @@ impl MultipleStrategyBlockStorage {
             merged
         );
 
-        merged
+        Ok(merged)
     }
 
     // lookup confirmed or finalized block from either our blockstore or faithful_history
@@ -83,7 +88,7 @@ impl MultipleStrategyBlockStorage {
     pub async fn query_block(
         &self,
         slot: solana_sdk::slot_history::Slot,
-    ) -> Result<BlockStorageData> {
+    ) -> Result<BlockStorageData, BlockStoreError> {
         // TODO this check is optional and might be moved to the caller
         // if slot > last_confirmed_slot {
         //     bail!(format!(
@@ -104,11 +109,7 @@ impl MultipleStrategyBlockStorage {
                     "Assume block {} to be available in persistent block-storage",
                     slot,
                 );
-                let lookup = self
-                    .block_storage_query
-                    .query_block(slot)
-                    .await
-                    .context(format!("block {} not found although it was in range", slot));
+                let lookup = self.block_storage_query.query_block(slot).await;
 
                 return lookup.map(|b| BlockStorageData {
                     block: b,
@@ -141,14 +142,48 @@ impl MultipleStrategyBlockStorage {
                         "Block {} not found in faithful_history storage - giving up",
                         slot
                     );
-                    bail!(format!("Block {} not found in faithful_history", slot));
+                    Err(BlockStoreError::NotFound { slot })
                 }
             }
         } else {
-            bail!(format!(
-                "Block {} not found - faithful_history not available",
-                slot
-            ));
+            debug!("Block {} not found - faithful_history not available", slot);
+            Err(BlockStoreError::OutOfRange { slot })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_stores::mock_block_store_query::MockBlockStoreQuery;
+    use crate::block_stores::test_utils::create_test_block;
+    use solana_sdk::commitment_config::CommitmentConfig;
+
+    #[tokio::test]
+    async fn test_query_block_with_mock_query_store() {
+        let mock = MockBlockStoreQuery::new([
+            create_test_block(1200, CommitmentConfig::finalized()),
+            create_test_block(1289, CommitmentConfig::finalized()),
+        ]);
+        let multi_store = MultipleStrategyBlockStorage::new(Arc::new(mock), None);
+
+        assert_eq!(multi_store.get_slot_range().await.unwrap(), 1200..=1289);
+
+        let block_1200 = multi_store.query_block(1200).await.unwrap();
+        assert_eq!(block_1200.slot, 1200);
+        assert!(matches!(
+            block_1200.result_source,
+            BlockSource::RecentEpochDatabase
+        ));
+        // in range but skipped
+        assert!(matches!(
+            multi_store.query_block(1250).await,
+            Err(BlockStoreError::NotFound { slot: 1250 })
+        ));
+        // no faithful_history store for older blocks
+        assert!(matches!(
+            multi_store.query_block(1000).await,
+            Err(BlockStoreError::OutOfRange { slot: 1000 })
+        ));
+    }
+}
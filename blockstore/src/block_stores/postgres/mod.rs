@@ -1,12 +1,21 @@
 pub mod postgres_block_store_query;
 pub mod postgres_block_store_writer;
+pub use postgres_config::redact_pg_config;
+pub use postgres_config::PostgresMessageCompression;
+pub use postgres_config::PostgresQueryTimeouts;
 pub use postgres_config::PostgresSessionConfig;
+pub use postgres_config::PostgresSslMode;
+pub use postgres_epoch::{
+    epoch_for_slot, epoch_from_schema_name, schema_name_for_epoch, EPOCH_SCHEMA_PREFIX,
+};
+pub use postgres_session::PostgresQueryError;
 pub use postgres_session::PostgresSession;
 pub use postgres_session::PostgresWriteSession;
 
 mod postgres_block;
 mod postgres_config;
 mod postgres_epoch;
+mod postgres_self_check;
 mod postgres_session;
 mod postgres_transaction;
 
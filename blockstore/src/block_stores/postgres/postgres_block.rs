@@ -95,20 +95,79 @@ impl PostgresBlock {
         )
     }
 
-    pub fn build_query_statement(epoch: EpochRef, slot: Slot) -> String {
+    // slot is passed as $1 so the statement can be reused for all slots of an epoch
+    pub fn build_query_statement(epoch: EpochRef) -> String {
         format!(
             r#"
                 SELECT
                     slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id,
                     {epoch}::bigint as _epoch, '{schema}'::text as _epoch_schema FROM {schema}.blocks
-                WHERE slot = {slot}
+                WHERE slot = $1
             "#,
             schema = PostgresEpoch::build_schema_name(epoch),
             epoch = epoch,
-            slot = slot
         )
     }
 
+    pub fn build_query_block_time_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT block_time FROM {schema}.blocks
+                WHERE slot = $1
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    /// slots of the stored blocks in the range $1..=$2, ascending
+    pub fn build_query_slots_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT slot FROM {schema}.blocks
+                WHERE slot >= $1 AND slot <= $2
+                ORDER BY slot
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    /// (slot, leader_id) of all blocks of the epoch with a known leader
+    pub fn build_query_leaders_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT slot, leader_id FROM {schema}.blocks
+                WHERE leader_id IS NOT NULL
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    pub fn build_query_rewards_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT slot, rewards FROM {schema}.blocks
+                WHERE rewards IS NOT NULL
+                ORDER BY slot
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    // all slots of the epochs with the block height (param $1), lowest slot first
+    pub fn build_query_slots_by_height_statement(epochs: &[EpochRef]) -> String {
+        let union = epochs
+            .iter()
+            .map(|epoch| {
+                format!(
+                    "SELECT slot FROM {schema}.blocks WHERE block_height = $1",
+                    schema = PostgresEpoch::build_schema_name(*epoch),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ");
+        format!("{union} ORDER BY slot")
+    }
+
     // true is actually inserted; false if operation was noop
     pub async fn save(
         &self,
@@ -1,106 +1,407 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::ops::RangeInclusive;
-use std::time::Instant;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::block_stores::block_store_error::BlockStoreError;
+use crate::block_stores::block_store_query::BlockStoreQuery;
 use crate::block_stores::postgres::LITERPC_QUERY_ROLE;
-use anyhow::{bail, Result};
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::{Stream, TryStreamExt};
+use futures_util::pin_mut;
 use itertools::Itertools;
 use log::{debug, info, warn};
+use solana_lite_rpc_core::encoding::BASE64;
 use solana_lite_rpc_core::structures::epoch::EpochRef;
 use solana_lite_rpc_core::structures::{epoch::EpochCache, produced_block::ProducedBlock};
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use solana_sdk::slot_history::Slot;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{
+    Reward, RewardType, TransactionConfirmationStatus, TransactionDetails, TransactionStatus,
+};
 
 use super::postgres_block::*;
 use super::postgres_config::*;
 use super::postgres_epoch::*;
+use super::postgres_self_check::check_role;
 use super::postgres_session::*;
 use super::postgres_transaction::*;
 
+/// what the archive holds for one epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochArchiveInfo {
+    pub epoch: EpochRef,
+    pub schema_name: String,
+    pub slot_min: Slot,
+    pub slot_max: Slot,
+    pub block_count: u64,
+}
+
+impl EpochArchiveInfo {
+    pub fn slot_range(&self) -> RangeInclusive<Slot> {
+        RangeInclusive::new(self.slot_min, self.slot_max)
+    }
+}
+
+/// reward of one address paid in an archived block (fields of getInflationReward)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardEntry {
+    /// slot of the block which paid the reward
+    pub slot: Slot,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<RewardType>,
+    pub commission: Option<u8>,
+}
+
 #[derive(Clone)]
 pub struct PostgresQueryBlockStore {
-    session_cache: PostgresSessionCache,
+    // primary - used for self-checks only
+    primary_session_cache: PostgresSessionCache,
+    // one per read replica or the primary if no replica is configured
+    query_sessions: Arc<Vec<QuerySessions>>,
+    next_query_sessions: Arc<AtomicUsize>,
     epoch_schedule: EpochCache,
+    slot_range_cache: Arc<SlotRangeCache>,
+    max_available_slots_range: u64,
+    strict_transaction_order: bool,
+    max_transactions_per_block: usize,
+    strict_max_transactions_per_block: bool,
+}
+
+/// page size used to collect the blocks of [BlockStoreQuery::query_blocks]
+const QUERY_BLOCKS_PAGE_SIZE: usize = 100;
+
+/// a lookup for a slot newer than the cached ranges refreshes the cache at most this often
+const SLOT_RANGE_CACHE_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// memoized epoch -> slot range map; refreshed lazily once the TTL expired so that
+/// a burst of range checks runs the expensive scan over all epoch schemas only once
+struct SlotRangeCache {
+    ttl: Duration,
+    // (fetched at, ranges); lock is held during refresh so concurrent callers wait for the same result
+    cached: tokio::sync::Mutex<Option<(Instant, HashMap<EpochRef, RangeInclusive<Slot>>)>>,
+}
+
+impl SlotRangeCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// `required_slot` above the cached range of its epoch or in an epoch newer than all cached epochs
+    /// indicates that blocks were written since the last refresh (the current epoch keeps growing)
+    async fn get_or_refresh<F, Fut>(
+        &self,
+        required_slot: Option<(EpochRef, Slot)>,
+        refresh: F,
+    ) -> Result<HashMap<EpochRef, RangeInclusive<Slot>>, PostgresQueryError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<HashMap<EpochRef, RangeInclusive<Slot>>, PostgresQueryError>>,
+    {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, ranges)) = cached.as_ref() {
+            let age = fetched_at.elapsed();
+            let is_newer_slot = required_slot.is_some_and(|(required_epoch, required_slot)| {
+                match ranges.get(&required_epoch) {
+                    Some(range) => required_slot > *range.end(),
+                    None => ranges.keys().all(|epoch| *epoch < required_epoch),
+                }
+            });
+            if age < self.ttl && !(is_newer_slot && age >= SLOT_RANGE_CACHE_MIN_REFRESH_INTERVAL) {
+                return Ok(ranges.clone());
+            }
+        }
+
+        let ranges = refresh().await?;
+        *cached = Some((Instant::now(), ranges.clone()));
+        Ok(ranges)
+    }
+}
+
+struct QuerySessions {
+    // point lookups by slot
+    point_lookup: PostgresSessionCache,
+    // scans across all epoch schemas; longer statement_timeout
+    range_scan: PostgresSessionCache,
+}
+
+impl QuerySessions {
+    async fn new(pg_session_config: PostgresSessionConfig) -> anyhow::Result<Self> {
+        let query_timeouts = pg_session_config.query_timeouts.clone();
+        Ok(Self {
+            point_lookup: PostgresSessionCache::new_with_statement_timeout(
+                pg_session_config.clone(),
+                query_timeouts.point_lookup(),
+            )
+            .await?,
+            range_scan: PostgresSessionCache::new_with_statement_timeout(
+                pg_session_config,
+                query_timeouts.range_scan(),
+            )
+            .await?,
+        })
+    }
 }
 
 impl PostgresQueryBlockStore {
     pub async fn new(epoch_schedule: EpochCache, pg_session_config: PostgresSessionConfig) -> Self {
-        let session_cache = PostgresSessionCache::new(pg_session_config.clone())
+        let primary_session_cache = PostgresSessionCache::new(pg_session_config.clone())
             .await
             .unwrap();
 
-        Self::check_query_role(&session_cache).await;
+        let mut query_sessions = vec![];
+        for query_session_config in pg_session_config.query_session_configs() {
+            query_sessions.push(QuerySessions::new(query_session_config).await.unwrap());
+        }
+        info!(
+            "Postgres query traffic is served by {} read replica(s)",
+            pg_session_config.read_replicas.len()
+        );
+
+        check_role(
+            &primary_session_cache,
+            LITERPC_QUERY_ROLE,
+            "query role",
+            pg_session_config.skip_self_checks,
+        )
+        .await;
 
         Self {
-            session_cache,
+            primary_session_cache,
+            query_sessions: Arc::new(query_sessions),
+            next_query_sessions: Arc::new(AtomicUsize::new(0)),
             epoch_schedule,
+            slot_range_cache: Arc::new(SlotRangeCache::new(
+                pg_session_config.slot_range_cache_ttl(),
+            )),
+            max_available_slots_range: pg_session_config.max_available_slots_range,
+            strict_transaction_order: pg_session_config.strict_transaction_order,
+            max_transactions_per_block: pg_session_config.max_transactions_per_block,
+            strict_max_transactions_per_block: pg_session_config.strict_max_transactions_per_block,
         }
     }
 
+    /// false while the primary or any query session is disconnected; sessions reconnect on their own
+    pub fn is_connected(&self) -> bool {
+        self.primary_session_cache.is_connected()
+            && self.query_sessions.iter().all(|query_sessions| {
+                query_sessions.point_lookup.is_connected()
+                    && query_sessions.range_scan.is_connected()
+            })
+    }
+
+    // round-robin across the replicas
+    fn next_query_sessions(&self) -> &QuerySessions {
+        let index = self.next_query_sessions.fetch_add(1, Ordering::Relaxed);
+        &self.query_sessions[index % self.query_sessions.len()]
+    }
+
     async fn get_session(&self) -> PostgresSession {
-        self.session_cache
+        self.next_query_sessions()
+            .point_lookup
+            .get_session()
+            .await
+            .expect("should get new postgres session")
+    }
+
+    async fn get_range_scan_session(&self) -> PostgresSession {
+        self.next_query_sessions()
+            .range_scan
             .get_session()
             .await
             .expect("should get new postgres session")
     }
 
     pub async fn is_block_in_range(&self, slot: Slot) -> bool {
-        let epoch = self.epoch_schedule.get_epoch_at_slot(slot);
-        let ranges = self.get_slot_range_by_epoch().await;
-        let matching_range: Option<&RangeInclusive<Slot>> = ranges.get(&epoch.into());
+        let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(slot).into();
+        let ranges = match self
+            .slot_range_cache
+            .get_or_refresh(Some((epoch, slot)), || self.query_slot_range_by_epoch())
+            .await
+        {
+            Ok(ranges) => ranges,
+            Err(err) => {
+                warn!(
+                    "Cannot check if block {} is in postgres range: {}",
+                    slot, err
+                );
+                return false;
+            }
+        };
+        let matching_range: Option<&RangeInclusive<Slot>> = ranges.get(&epoch);
 
         matching_range
             .map(|slot_range| slot_range.contains(&slot))
-            .is_some()
+            .unwrap_or(false)
     }
 
-    pub async fn query_block(&self, slot: Slot) -> Result<ProducedBlock> {
-        let started_at = Instant::now();
-        let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(slot).into();
+    pub async fn query_block(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError> {
+        self.query_block_with_details(slot, TransactionDetails::Full)
+            .await
+    }
 
-        let statement = PostgresBlock::build_query_statement(epoch, slot);
-        let block_row = self
-            .get_session()
+    /// block meta data (blockhash, parent, height, time, rewards) without querying its transactions;
+    /// the returned block has an empty transaction list
+    pub async fn query_block_meta(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError> {
+        self.query_block_with_details(slot, TransactionDetails::None)
             .await
-            .query_opt(&statement, &[])
+    }
+
+    /// getBlock with transactionDetails `none` is served by [Self::query_block_meta], all other options need the full block
+    pub async fn query_block_with_transaction_details(
+        &self,
+        slot: Slot,
+        transaction_details: TransactionDetails,
+    ) -> Result<ProducedBlock, BlockStoreError> {
+        self.query_block_with_details(slot, transaction_details)
             .await
-            .unwrap();
+    }
+
+    async fn query_block_with_details(
+        &self,
+        slot: Slot,
+        transaction_details: TransactionDetails,
+    ) -> Result<ProducedBlock, BlockStoreError> {
+        let started_at = Instant::now();
+        let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(slot).into();
+
+        // the epoch schema of a pruned slot does not exist anymore
+        let slot_range_by_epoch = self.get_slot_range_by_epoch().await?;
+        check_not_pruned(slot, &slot_range_by_epoch)?;
+
+        // same replica for block and transactions
+        let session = self.get_session().await;
+
+        // the slot ranges only contain epochs with blocks
+        if !slot_range_by_epoch.contains_key(&epoch) {
+            match query_epoch_schema_state(&session, epoch).await? {
+                EpochSchemaState::Missing => {
+                    debug!("No epoch schema for epoch {} of block {}", epoch, slot);
+                    return Err(BlockStoreError::EpochNotArchived {
+                        slot,
+                        epoch: epoch.get_epoch(),
+                    });
+                }
+                EpochSchemaState::Empty => {
+                    warn!(
+                        "Epoch schema for epoch {} exists but has no blocks yet - cannot serve block {}",
+                        epoch, slot
+                    );
+                    return Err(BlockStoreError::EpochEmpty {
+                        slot,
+                        epoch: epoch.get_epoch(),
+                    });
+                }
+                // slot ranges were cached before the first block of the epoch was written
+                EpochSchemaState::Populated => {}
+            }
+        }
+
+        let slot_param = slot as i64;
+
+        let statement = PostgresBlock::build_query_statement(epoch);
+        let block_row = session.try_query_opt(&statement, &[&slot_param]).await?;
 
         if block_row.is_none() {
-            bail!("Block {} in epoch {} not found in postgres", slot, epoch);
+            debug!("Block {} in epoch {} not found in postgres", slot, epoch);
+            return Err(BlockStoreError::NotFound { slot });
         }
 
-        let statement = PostgresTransaction::build_query_statement(epoch, slot);
-        let transaction_rows = self
-            .get_session()
-            .await
-            .query_list(&statement, &[])
-            .await
-            .unwrap();
+        let session_ref = &session;
+        // one more than the limit to detect blocks above the limit without loading all their transactions
+        let limit_param = i64::try_from(self.max_transactions_per_block)
+            .unwrap_or(i64::MAX)
+            .saturating_add(1);
+        let mut transaction_rows =
+            query_transactions_for_details(transaction_details, || async move {
+                let statement = PostgresTransaction::build_query_statement(epoch);
+                match session_ref
+                    .try_query_list(&statement, &[&slot_param, &limit_param])
+                    .await
+                {
+                    // schema was not migrated yet - all rows have idx_in_block = NULL
+                    Err(err) if err.is_undefined_column() => {
+                        let statement =
+                            PostgresTransaction::build_query_statement_without_idx_in_block(epoch);
+                        session_ref
+                            .try_query_list(&statement, &[&slot_param, &limit_param])
+                            .await
+                    }
+                    result => result,
+                }
+            })
+            .await?;
+
+        if transaction_rows.len() > self.max_transactions_per_block {
+            let statement = PostgresTransaction::build_count_statement(epoch);
+            let count = session
+                .try_query_opt(&statement, &[&slot_param])
+                .await?
+                .map_or(transaction_rows.len() as i64, |row| {
+                    row.get::<&str, i64>("count")
+                });
+            if self.strict_max_transactions_per_block {
+                return Err(BlockStoreError::TooLarge {
+                    slot,
+                    count: count as u64,
+                });
+            }
+            warn!(
+                "Block {} has {} transactions - truncating to the first {}",
+                slot, count, self.max_transactions_per_block
+            );
+            transaction_rows.truncate(self.max_transactions_per_block);
+        }
 
         warn!(
             "transaction_rows: {} - print first 10",
             transaction_rows.len()
         );
 
-        let tx_infos = transaction_rows
+        let mut postgres_transactions = transaction_rows
             .iter()
-            .map(|tx_row| {
-                let postgres_transaction = PostgresTransaction {
-                    slot: slot as i64,
-                    signature: tx_row.get("signature"),
-                    err: tx_row.get("err"),
-                    cu_requested: tx_row.get("cu_requested"),
-                    prioritization_fees: tx_row.get("prioritization_fees"),
-                    cu_consumed: tx_row.get("cu_consumed"),
-                    recent_blockhash: tx_row.get("recent_blockhash"),
-                    message: tx_row.get("message"),
-                };
-
-                postgres_transaction.to_transaction_info()
+            .map(|tx_row| PostgresTransaction {
+                slot: slot as i64,
+                signature: tx_row.get("signature"),
+                err: tx_row.get("err"),
+                cu_requested: tx_row.get("cu_requested"),
+                prioritization_fees: tx_row.get("prioritization_fees"),
+                cu_consumed: tx_row.get("cu_consumed"),
+                recent_blockhash: tx_row.get("recent_blockhash"),
+                message: tx_row.get("message"),
+                idx_in_block: tx_row.get("idx_in_block"),
             })
             .collect_vec();
 
+        if let Err(duplicate_idx) = sort_transactions_by_idx_in_block(&mut postgres_transactions) {
+            if self.strict_transaction_order {
+                return Err(BlockStoreError::Corrupt {
+                    slot,
+                    reason: format!("multiple transactions with idx_in_block {duplicate_idx}"),
+                });
+            }
+            warn!(
+                "Block {} has multiple transactions with idx_in_block {} - ordering them by signature",
+                slot, duplicate_idx
+            );
+        }
+
+        let tx_infos = postgres_transactions
+            .iter()
+            .map(|postgres_transaction| postgres_transaction.to_transaction_info())
+            .collect_vec();
+
         let row = block_row.unwrap();
         // meta data
         let _epoch: i64 = row.get("_epoch");
@@ -144,53 +445,546 @@ impl PostgresQueryBlockStore {
         Ok(produced_block)
     }
 
-    async fn check_query_role(session_cache: &PostgresSessionCache) {
-        let role = LITERPC_QUERY_ROLE;
-        let statement = format!("SELECT 1 FROM pg_roles WHERE rolname='{role}'");
-        let count = session_cache
-            .get_session()
+    /// archived block with the given block height; None if no archived block has that height
+    ///
+    /// note: block_height is not indexed, so the blocks tables of all archived epochs are scanned;
+    /// if several blocks share the height (inconsistent archive) the one with the lowest slot is returned
+    pub async fn query_block_by_height(
+        &self,
+        block_height: u64,
+    ) -> Result<Option<ProducedBlock>, BlockStoreError> {
+        let epochs = self
+            .get_slot_range_by_epoch()
+            .await?
+            .into_keys()
+            .sorted()
+            .collect_vec();
+        if epochs.is_empty() {
+            return Ok(None);
+        }
+
+        let statement = PostgresBlock::build_query_slots_by_height_statement(&epochs);
+        let rows = self
+            .get_range_scan_session()
             .await
-            .expect("must get session")
-            .execute(&statement, &[])
+            .try_query_list(&statement, &[&(block_height as i64)])
+            .await?;
+        let slots = rows
+            .iter()
+            .map(|row| row.get::<&str, i64>("slot") as Slot)
+            .collect_vec();
+        let Some(&slot) = slots.first() else {
+            return Ok(None);
+        };
+        if slots.len() > 1 {
+            warn!(
+                "Block height {} is ambiguous in the archive (slots {:?}) - using slot {}",
+                block_height, slots, slot
+            );
+        }
+
+        match self.query_block(slot).await {
+            Ok(block) => Ok(Some(block)),
+            // deleted in the meantime
+            Err(BlockStoreError::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// block_time of an archived block (single column lookup);
+    /// None if the block is not archived, including slots of pruned or missing epochs
+    pub async fn query_block_time(&self, slot: Slot) -> Result<Option<i64>, BlockStoreError> {
+        let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(slot).into();
+
+        let slot_range_by_epoch = self.get_slot_range_by_epoch().await?;
+        if check_not_pruned(slot, &slot_range_by_epoch).is_err() {
+            return Ok(None);
+        }
+
+        let session = self.get_session().await;
+
+        // the slot ranges only contain epochs with blocks
+        if !slot_range_by_epoch.contains_key(&epoch)
+            && query_epoch_schema_state(&session, epoch).await? != EpochSchemaState::Populated
+        {
+            return Ok(None);
+        }
+
+        let statement = PostgresBlock::build_query_block_time_statement(epoch);
+        let row = session.try_query_opt(&statement, &[&(slot as i64)]).await?;
+        Ok(row.map(|row| row.get::<&str, i64>("block_time")))
+    }
+
+    /// blocks in start..=end in slot order; the range is queried in pages of `page_size` slots
+    /// which never cross an epoch boundary, so only one page of slots is held in memory;
+    /// missing slots are skipped and the stream ends after the first error
+    pub fn query_block_range_paged(
+        &self,
+        start: Slot,
+        end: Slot,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<ProducedBlock, BlockStoreError>> + '_ {
+        assert!(page_size > 0, "page_size must be greater than 0");
+        // (start of next page, slots of the current page not yet yielded)
+        let init: (Option<Slot>, VecDeque<Slot>) =
+            ((start <= end).then_some(start), VecDeque::new());
+
+        futures::stream::try_unfold(
+            init,
+            move |(mut next_page_from, mut pending_slots)| async move {
+                loop {
+                    if let Some(slot) = pending_slots.pop_front() {
+                        match self.query_block(slot).await {
+                            Ok(block) => return Ok(Some((block, (next_page_from, pending_slots)))),
+                            // deleted in the meantime
+                            Err(BlockStoreError::NotFound { .. }) => continue,
+                            Err(err) => return Err(err),
+                        }
+                    }
+
+                    let Some(page_from) = next_page_from else {
+                        return Ok(None);
+                    };
+                    let page = page_slot_range(&self.epoch_schedule, page_from, end, page_size);
+                    next_page_from = (*page.end() < end).then(|| page.end() + 1);
+                    pending_slots = self.query_slots_in_range(page).await?.into();
+                }
+            },
+        )
+    }
+
+    /// blocks in start..=end grouped by epoch in epoch order; each epoch is queried on its own so that
+    /// a failing epoch (e.g. schema dropped concurrently) does not lose the blocks of the other epochs;
+    /// every epoch touched by the range has an entry, the caller decides how to handle failed epochs
+    pub async fn query_blocks_by_epoch(
+        &self,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<Vec<(EpochRef, Result<Vec<ProducedBlock>, BlockStoreError>)>, BlockStoreError> {
+        let (start, end) = slots.into_inner();
+        check_slot_range_size(start, end, self.max_available_slots_range)?;
+
+        let mut blocks_by_epoch = vec![];
+        let mut next_from = (start <= end).then_some(start);
+        while let Some(from) = next_from {
+            let epoch_range =
+                page_slot_range(&self.epoch_schedule, from, end, (end - from + 1) as usize);
+            next_from = (*epoch_range.end() < end).then(|| epoch_range.end() + 1);
+
+            let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(from).into();
+            let blocks: Result<Vec<ProducedBlock>, BlockStoreError> = self
+                .query_block_range_paged(
+                    *epoch_range.start(),
+                    *epoch_range.end(),
+                    QUERY_BLOCKS_PAGE_SIZE,
+                )
+                .try_collect()
+                .await;
+            if let Err(err) = &blocks {
+                warn!(
+                    "Failed to query blocks of epoch {epoch} in slots {}..={} - continue with next epoch: {err}",
+                    epoch_range.start(),
+                    epoch_range.end()
+                );
+            }
+            blocks_by_epoch.push((epoch, blocks));
+        }
+        Ok(blocks_by_epoch)
+    }
+
+    /// slot -> leader of the archived blocks of the epoch; slots without a known leader are skipped
+    /// and the map is empty if the epoch is not archived
+    pub async fn query_leader_schedule(
+        &self,
+        epoch: EpochRef,
+    ) -> Result<HashMap<Slot, Pubkey>, BlockStoreError> {
+        if !self.get_slot_range_by_epoch().await?.contains_key(&epoch) {
+            return Ok(HashMap::new());
+        }
+
+        let statement = PostgresBlock::build_query_leaders_statement(epoch);
+        let rows = self
+            .get_range_scan_session()
             .await
-            .expect("must execute query to check for role");
+            .try_query_list(&statement, &[])
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let slot = row.get::<&str, i64>("slot") as Slot;
+                let leader_id: &str = row.get("leader_id");
+                let leader = Pubkey::from_str(leader_id)
+                    .with_context(|| format!("invalid leader_id {leader_id} of slot {slot}"))?;
+                Ok((slot, leader))
+            })
+            .collect()
+    }
+
+    /// rewards paid to `address` by the archived blocks of `epoch` in slot order, e.g. for an archive based
+    /// getInflationReward; empty if the epoch is not archived
+    ///
+    /// note: scans and decodes the rewards of all blocks of the epoch; configure read replicas to keep this
+    /// off the primary
+    pub async fn query_rewards_for_address(
+        &self,
+        address: &Pubkey,
+        epoch: EpochRef,
+    ) -> Result<Vec<RewardEntry>, BlockStoreError> {
+        if !self.get_slot_range_by_epoch().await?.contains_key(&epoch) {
+            return Ok(vec![]);
+        }
 
-        if count == 0 {
-            panic!(
-                "Missing mandatory postgres query role '{}' for Lite RPC - see permissions.sql",
-                role
+        let statement = PostgresBlock::build_query_rewards_statement(epoch);
+        let session = self.get_range_scan_session().await;
+        // the rewards of a whole epoch are too large to load at once
+        let rows = session.try_query_stream(&statement, &[]).await?;
+        pin_mut!(rows);
+
+        let address = address.to_string();
+        let mut entries = vec![];
+        while let Some(row) = rows.try_next().await? {
+            let slot = row.get::<&str, i64>("slot") as Slot;
+            let rewards: String = row.get("rewards");
+            let rewards = BASE64.deserialize::<Vec<Reward>>(&rewards).map_err(|err| {
+                BlockStoreError::Corrupt {
+                    slot,
+                    reason: format!("cannot decode rewards: {err}"),
+                }
+            })?;
+            entries.extend(
+                rewards
+                    .into_iter()
+                    .filter(|reward| reward.pubkey == address)
+                    .map(|reward| RewardEntry {
+                        slot,
+                        lamports: reward.lamports,
+                        post_balance: reward.post_balance,
+                        reward_type: reward.reward_type,
+                        commission: reward.commission,
+                    }),
             );
-        } else {
-            info!("Self check - found postgres role '{}'", role);
         }
+        Ok(entries)
+    }
+
+    /// slots in start..=end for which a block is archived, ascending (like getBlocks);
+    /// empty if end < start
+    pub async fn query_available_slots(
+        &self,
+        start: Slot,
+        end: Slot,
+    ) -> Result<Vec<Slot>, BlockStoreError> {
+        check_slot_range_size(start, end, self.max_available_slots_range)?;
+
+        let mut slots = vec![];
+        let mut next_from = (start <= end).then_some(start);
+        while let Some(from) = next_from {
+            // one query per epoch schema
+            let page = page_slot_range(&self.epoch_schedule, from, end, (end - from + 1) as usize);
+            next_from = (*page.end() < end).then(|| page.end() + 1);
+            slots.extend(self.query_slots_in_range(page).await?);
+        }
+        Ok(slots)
+    }
+
+    /// slots of the stored blocks; the range must not cross an epoch boundary
+    async fn query_slots_in_range(
+        &self,
+        range: RangeInclusive<Slot>,
+    ) -> Result<Vec<Slot>, BlockStoreError> {
+        let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(*range.start()).into();
+        if !self.get_slot_range_by_epoch().await?.contains_key(&epoch) {
+            // no epoch schema
+            return Ok(vec![]);
+        }
+
+        let statement = PostgresBlock::build_query_slots_statement(epoch);
+        let rows = self
+            .get_session()
+            .await
+            .try_query_list(
+                &statement,
+                &[&(*range.start() as i64), &(*range.end() as i64)],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get::<&str, i64>("slot") as Slot)
+            .collect_vec())
+    }
+
+    /// status of the archived transactions in input order (like getSignatureStatuses);
+    /// None if the signature is not archived
+    pub async fn query_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> Result<Vec<Option<TransactionStatus>>, BlockStoreError> {
+        let mut epochs = self
+            .get_slot_range_by_epoch()
+            .await?
+            .into_keys()
+            .collect_vec();
+        // recent transactions are looked up more often
+        epochs.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut found: HashMap<Signature, TransactionStatus> = HashMap::new();
+        for epoch in epochs {
+            let missing = signatures
+                .iter()
+                .filter(|signature| !found.contains_key(signature))
+                .unique()
+                .map(|signature| signature.to_string())
+                .collect_vec();
+            if missing.is_empty() {
+                break;
+            }
+
+            let statement = PostgresTransaction::build_query_signature_statuses_statement(epoch);
+            let rows = self
+                .get_session()
+                .await
+                .try_query_list(&statement, &[&missing])
+                .await?;
+
+            for row in rows {
+                let signature: &str = row.get("signature");
+                let signature = Signature::from_str(signature)
+                    .with_context(|| format!("invalid signature {signature} in epoch {epoch}"))?;
+                let slot = row.get::<&str, i64>("slot") as Slot;
+                let err = PostgresTransaction::decode_err(row.get("err"));
+                found.insert(signature, archived_transaction_status(slot, err));
+            }
+        }
+
+        Ok(signatures
+            .iter()
+            .map(|signature| found.get(signature).cloned())
+            .collect_vec())
+    }
+}
+
+/// status of an archived transaction; archived transactions are rooted so they are reported as finalized
+fn archived_transaction_status(slot: Slot, err: Option<TransactionError>) -> TransactionStatus {
+    TransactionStatus {
+        slot,
+        confirmations: None,
+        status: err.clone().map_or(Ok(()), Err),
+        err,
+        confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+    }
+}
+
+/// runs the transaction query unless only the block meta data was requested
+async fn query_transactions_for_details<T, E, F, Fut>(
+    transaction_details: TransactionDetails,
+    query_transactions: F,
+) -> Result<Vec<T>, E>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    match transaction_details {
+        TransactionDetails::None => Ok(vec![]),
+        TransactionDetails::Full
+        | TransactionDetails::Signatures
+        | TransactionDetails::Accounts => query_transactions().await,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EpochSchemaState {
+    Missing,
+    // schema was created but no block was written yet
+    Empty,
+    Populated,
+}
+
+async fn query_epoch_schema_state(
+    session: &PostgresSession,
+    epoch: EpochRef,
+) -> Result<EpochSchemaState, PostgresQueryError> {
+    let schema = PostgresEpoch::build_schema_name(epoch);
+    let schema_row = session
+        .try_query_opt(
+            "SELECT schema_name FROM information_schema.schemata WHERE schema_name = $1",
+            &[&schema],
+        )
+        .await?;
+    if schema_row.is_none() {
+        return Ok(EpochSchemaState::Missing);
+    }
+
+    let statement = format!("SELECT slot FROM {schema}.blocks LIMIT 1", schema = schema);
+    match session.try_query_opt(&statement, &[]).await? {
+        Some(_) => Ok(EpochSchemaState::Populated),
+        None => Ok(EpochSchemaState::Empty),
     }
 }
 
+/// slots below the oldest archived slot are reported as pruned rather than not found
+fn check_not_pruned(
+    slot: Slot,
+    slot_range_by_epoch: &HashMap<EpochRef, RangeInclusive<Slot>>,
+) -> Result<(), BlockStoreError> {
+    let oldest_available = slot_range_by_epoch
+        .values()
+        .map(|range| *range.start())
+        .min();
+    match oldest_available {
+        Some(oldest_available) if slot < oldest_available => Err(BlockStoreError::Pruned {
+            slot,
+            oldest_available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// canonical transaction order: by idx_in_block, ties broken by signature;
+/// Err with the first index shared by multiple transactions
+fn sort_transactions_by_idx_in_block(transactions: &mut [PostgresTransaction]) -> Result<(), i32> {
+    transactions
+        .sort_by(|a, b| (a.idx_in_block, &a.signature).cmp(&(b.idx_in_block, &b.signature)));
+    match transactions
+        .windows(2)
+        .find(|pair| pair[0].idx_in_block.is_some() && pair[0].idx_in_block == pair[1].idx_in_block)
+    {
+        Some(pair) => Err(pair[0].idx_in_block.unwrap_or_default()),
+        None => Ok(()),
+    }
+}
+
+#[async_trait]
+impl BlockStoreQuery for PostgresQueryBlockStore {
+    async fn query_block(&self, slot: Slot) -> Result<ProducedBlock, BlockStoreError> {
+        PostgresQueryBlockStore::query_block(self, slot).await
+    }
+
+    /// all blocks are held in memory - limited to `max_available_slots_range` slots;
+    /// use [PostgresQueryBlockStore::query_block_range_paged] to stream larger ranges
+    async fn query_blocks(
+        &self,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<Vec<ProducedBlock>, BlockStoreError> {
+        let (start, end) = slots.into_inner();
+        check_slot_range_size(start, end, self.max_available_slots_range)?;
+        self.query_block_range_paged(start, end, QUERY_BLOCKS_PAGE_SIZE)
+            .try_collect()
+            .await
+    }
+
+    async fn get_slot_range(&self) -> Result<Option<RangeInclusive<Slot>>, BlockStoreError> {
+        Ok(PostgresQueryBlockStore::get_slot_range(self).await?)
+    }
+
+    async fn is_block_in_range(&self, slot: Slot) -> bool {
+        PostgresQueryBlockStore::is_block_in_range(self, slot).await
+    }
+}
+
+fn intersect_slot_ranges(
+    a: &RangeInclusive<Slot>,
+    b: &RangeInclusive<Slot>,
+) -> Option<RangeInclusive<Slot>> {
+    let start = *a.start().max(b.start());
+    let end = *a.end().min(b.end());
+    (start <= end).then_some(start..=end)
+}
+
+fn check_slot_range_size(start: Slot, end: Slot, max_range: u64) -> Result<(), BlockStoreError> {
+    if end >= start && end - start >= max_range {
+        return Err(BlockStoreError::SlotRangeTooLarge {
+            start,
+            end,
+            max_range,
+        });
+    }
+    Ok(())
+}
+
+/// next page of at most `page_size` slots starting at `from`, ending at `end` or the epoch boundary
+fn page_slot_range(
+    epoch_schedule: &EpochCache,
+    from: Slot,
+    end: Slot,
+    page_size: usize,
+) -> RangeInclusive<Slot> {
+    let epoch = epoch_schedule.get_epoch_at_slot(from).epoch;
+    let page_end = (from + page_size as u64 - 1)
+        .min(end)
+        .min(epoch_schedule.get_last_slot_in_epoch(epoch));
+    from..=page_end
+}
+
 impl PostgresQueryBlockStore {
-    pub async fn get_slot_range(&self) -> RangeInclusive<Slot> {
-        let map_epoch_to_slot_range = self.get_slot_range_by_epoch().await;
+    /// lowest to highest archived slot over all epochs; None if no blocks are archived
+    pub async fn get_slot_range(&self) -> Result<Option<RangeInclusive<Slot>>, PostgresQueryError> {
+        let map_epoch_to_slot_range = self.get_slot_range_by_epoch().await?;
 
         let rows_minmax: Vec<&RangeInclusive<Slot>> =
             map_epoch_to_slot_range.values().collect_vec();
 
-        let slot_min = rows_minmax
-            .iter()
-            .map(|range| range.start())
-            .min()
-            // TODO decide what todo
-            .expect("non-empty result - TODO");
+        let Some(slot_min) = rows_minmax.iter().map(|range| range.start()).min() else {
+            return Ok(None);
+        };
         let slot_max = rows_minmax
             .iter()
             .map(|range| range.end())
             .max()
-            .expect("non-empty result - TODO");
+            .expect("ranges must not be empty");
 
-        RangeInclusive::new(*slot_min, *slot_max)
+        Ok(Some(RangeInclusive::new(*slot_min, *slot_max)))
     }
 
-    pub async fn get_slot_range_by_epoch(&self) -> HashMap<EpochRef, RangeInclusive<Slot>> {
+    /// part of `requested` within the archived slot range ([Self::get_slot_range]); None if they do not overlap
+    /// or nothing is archived
+    ///
+    /// note: the archived slot range may have gaps, e.g. skipped slots or epochs that were not archived
+    pub async fn covered_subrange(
+        &self,
+        requested: RangeInclusive<Slot>,
+    ) -> Result<Option<RangeInclusive<Slot>>, PostgresQueryError> {
+        let archived = self.get_slot_range().await?;
+        Ok(archived.and_then(|archived| intersect_slot_ranges(&archived, &requested)))
+    }
+
+    /// cached for `slot_range_cache_ttl_ms`
+    pub async fn get_slot_range_by_epoch(
+        &self,
+    ) -> Result<HashMap<EpochRef, RangeInclusive<Slot>>, PostgresQueryError> {
+        self.slot_range_cache
+            .get_or_refresh(None, || self.query_slot_range_by_epoch())
+            .await
+    }
+
+    async fn query_slot_range_by_epoch(
+        &self,
+    ) -> Result<HashMap<EpochRef, RangeInclusive<Slot>>, PostgresQueryError> {
         let started = Instant::now();
-        let session = self.get_session().await;
+        let archive_summary = self.query_archive_summary().await?;
+
+        let final_range: HashMap<EpochRef, RangeInclusive<Slot>> = archive_summary
+            .iter()
+            .map(|info| (info.epoch, info.slot_range()))
+            .collect();
+
+        debug!(
+            "Slot range check in postgres found {} ranges, took {:2}sec: {:?}",
+            final_range.len(),
+            started.elapsed().as_secs_f64(),
+            final_range
+        );
+
+        Ok(final_range)
+    }
+
+    /// every archived epoch with its slot range and number of blocks, ordered by epoch ascending;
+    /// epoch schemas without blocks are not listed
+    pub async fn archive_summary(&self) -> Result<Vec<EpochArchiveInfo>, BlockStoreError> {
+        Ok(self.query_archive_summary().await?)
+    }
+
+    async fn query_archive_summary(&self) -> Result<Vec<EpochArchiveInfo>, PostgresQueryError> {
+        let session = self.get_range_scan_session().await;
         // e.g. "rpc2a_epoch_552"
         let query = format!(
             r#"
@@ -201,7 +995,7 @@ impl PostgresQueryBlockStore {
             "#,
             schema_prefix = EPOCH_SCHEMA_PREFIX
         );
-        let result = session.query_list(&query, &[]).await.unwrap();
+        let result = session.try_query_list(&query, &[]).await?;
 
         let epoch_schemas = result
             .iter()
@@ -215,7 +1009,7 @@ impl PostgresQueryBlockStore {
             .collect_vec();
 
         if epoch_schemas.is_empty() {
-            return HashMap::new();
+            return Ok(vec![]);
         }
 
         let inner = epoch_schemas
@@ -231,55 +1025,919 @@ impl PostgresQueryBlockStore {
 
         let query = format!(
             r#"
-                SELECT epoch, min(slot) as slot_min, max(slot) as slot_max FROM (
+                SELECT epoch, min(slot) as slot_min, max(slot) as slot_max, count(*) as block_count FROM (
                     {inner}
                 ) AS all_slots
                 GROUP BY epoch
+                ORDER BY epoch
             "#,
             inner = inner
         );
 
-        let rows_minmax = session.query_list(&query, &[]).await.unwrap();
+        let rows_minmax = session.try_query_list(&query, &[]).await?;
 
-        if rows_minmax.is_empty() {
-            return HashMap::new();
-        }
+        let archive_summary = rows_minmax
+            .iter()
+            .map(|row| {
+                let epoch = EpochRef::new(row.get::<&str, i64>("epoch") as u64);
+                EpochArchiveInfo {
+                    epoch,
+                    schema_name: PostgresEpoch::build_schema_name(epoch),
+                    slot_min: row.get::<&str, i64>("slot_min") as Slot,
+                    slot_max: row.get::<&str, i64>("slot_max") as Slot,
+                    block_count: row.get::<&str, i64>("block_count") as u64,
+                }
+            })
+            .collect_vec();
+
+        Ok(archive_summary)
+    }
 
-        let mut map_epoch_to_slot_range = rows_minmax
+    /// disk space of each epoch schema in bytes (tables incl. indexes and TOAST), ordered by epoch ascending
+    pub async fn query_epoch_sizes(&self) -> Result<Vec<(EpochRef, u64)>, BlockStoreError> {
+        Ok(self.query_epoch_sizes_inner().await?)
+    }
+
+    async fn query_epoch_sizes_inner(&self) -> Result<Vec<(EpochRef, u64)>, PostgresQueryError> {
+        let session = self.get_range_scan_session().await;
+        let query = format!(
+            r#"
+                SELECT
+                 nspname AS schema_name,
+                 sum(pg_total_relation_size(pg_class.oid))::bigint AS total_bytes
+                FROM pg_class
+                INNER JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+                WHERE nspname ~ '^{schema_prefix}[0-9]+$'
+                 AND relkind = 'r'
+                 AND relname IN ('blocks', 'transaction_ids', 'transaction_blockdata')
+                GROUP BY nspname
+            "#,
+            schema_prefix = EPOCH_SCHEMA_PREFIX
+        );
+        let rows = session.try_query_list(&query, &[]).await?;
+
+        let epoch_sizes = rows
             .iter()
             .map(|row| {
+                let schema_name = row.get::<&str, &str>("schema_name");
                 (
-                    row.get::<&str, i64>("epoch"),
-                    RangeInclusive::new(
-                        row.get::<&str, i64>("slot_min") as Slot,
-                        row.get::<&str, i64>("slot_max") as Slot,
-                    ),
+                    PostgresEpoch::parse_epoch_from_schema_name(schema_name),
+                    row.get::<&str, i64>("total_bytes") as u64,
                 )
             })
-            .into_grouping_map()
-            .fold(None, |acc, _key, val| {
-                assert!(acc.is_none(), "epoch must be unique");
-                Some(val)
-            });
+            .sorted_by_key(|(epoch, _)| *epoch)
+            .collect_vec();
 
-        let final_range: HashMap<EpochRef, RangeInclusive<Slot>> = map_epoch_to_slot_range
-            .iter_mut()
-            .map(|(epoch, range)| {
-                let epoch = EpochRef::new(*epoch as u64);
-                (
-                    epoch,
-                    range.clone().expect("range must be returned from SQL"),
-                )
+        Ok(epoch_sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_stores::test_utils::{
+        create_test_block_inner, create_test_transaction, recreate_epoch_schemas, save_blocks,
+        seed_blocks,
+    };
+    use solana_lite_rpc_core::structures::produced_block::ProducedBlockInner;
+
+    fn counting_refresh(
+        num_queries: &AtomicUsize,
+        ranges: &[(u64, RangeInclusive<Slot>)],
+    ) -> Result<HashMap<EpochRef, RangeInclusive<Slot>>, PostgresQueryError> {
+        num_queries.fetch_add(1, Ordering::SeqCst);
+        Ok(ranges
+            .iter()
+            .map(|(epoch, range)| (EpochRef::new(*epoch), range.clone()))
+            .collect())
+    }
+
+    #[test]
+    fn test_intersect_slot_ranges() {
+        let archived = 1000..=1999;
+        // fully inside
+        assert_eq!(
+            intersect_slot_ranges(&archived, &(1200..=1300)),
+            Some(1200..=1300)
+        );
+        // requested covers the archive
+        assert_eq!(
+            intersect_slot_ranges(&archived, &(0..=5000)),
+            Some(1000..=1999)
+        );
+        // partial overlap on both sides
+        assert_eq!(
+            intersect_slot_ranges(&archived, &(900..=1100)),
+            Some(1000..=1100)
+        );
+        assert_eq!(
+            intersect_slot_ranges(&archived, &(1999..=2500)),
+            Some(1999..=1999)
+        );
+        // disjoint
+        assert_eq!(intersect_slot_ranges(&archived, &(0..=999)), None);
+        assert_eq!(intersect_slot_ranges(&archived, &(2000..=3000)), None);
+        // empty requested range
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 1500..=1400;
+        assert_eq!(intersect_slot_ranges(&archived, &empty), None);
+    }
+
+    #[test]
+    fn test_page_slot_range_two_epochs() {
+        // 1000 slots per epoch
+        let epoch_schedule = EpochCache::new_for_tests();
+        let (start, end) = (985, 1012);
+
+        let mut pages = vec![];
+        let mut from = start;
+        loop {
+            let page = page_slot_range(&epoch_schedule, from, end, 10);
+            pages.push(page.clone());
+            if *page.end() == end {
+                break;
+            }
+            from = page.end() + 1;
+        }
+
+        assert_eq!(pages, vec![985..=994, 995..=999, 1000..=1009, 1010..=1012]);
+    }
+
+    #[test]
+    fn test_slot_below_archive_floor_is_pruned() {
+        let slot_range_by_epoch = HashMap::from([
+            (EpochRef::new(5), 5010..=5999),
+            (EpochRef::new(6), 6000..=6500),
+        ]);
+
+        assert!(matches!(
+            check_not_pruned(4200, &slot_range_by_epoch),
+            Err(BlockStoreError::Pruned {
+                slot: 4200,
+                oldest_available: 5010
             })
-            .collect();
+        ));
+        // below the first block of the oldest epoch
+        assert!(matches!(
+            check_not_pruned(5001, &slot_range_by_epoch),
+            Err(BlockStoreError::Pruned { .. })
+        ));
+        assert!(check_not_pruned(5010, &slot_range_by_epoch).is_ok());
+        assert!(check_not_pruned(7000, &slot_range_by_epoch).is_ok());
+        // nothing archived yet
+        assert!(check_not_pruned(4200, &HashMap::new()).is_ok());
+    }
 
-        debug!(
-            "Slot range check in postgres found {} ranges, took {:2}sec: {:?}",
-            rows_minmax.len(),
-            started.elapsed().as_secs_f64(),
-            final_range
+    fn postgres_transaction(signature: &str, idx_in_block: Option<i32>) -> PostgresTransaction {
+        PostgresTransaction {
+            signature: signature.to_string(),
+            slot: 42,
+            err: None,
+            cu_requested: None,
+            prioritization_fees: None,
+            cu_consumed: None,
+            recent_blockhash: String::new(),
+            message: String::new(),
+            idx_in_block,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_idx_in_block_is_ordered_by_signature() {
+        let mut transactions = vec![
+            postgres_transaction("sig_c", Some(1)),
+            postgres_transaction("sig_b", Some(0)),
+            postgres_transaction("sig_z", Some(1)),
+            postgres_transaction("sig_a", Some(1)),
+        ];
+
+        assert_eq!(sort_transactions_by_idx_in_block(&mut transactions), Err(1));
+        let order = transactions
+            .iter()
+            .map(|tx| tx.signature.as_str())
+            .collect_vec();
+        assert_eq!(order, vec!["sig_b", "sig_a", "sig_c", "sig_z"]);
+
+        let mut transactions = vec![
+            postgres_transaction("sig_c", Some(1)),
+            postgres_transaction("sig_a", Some(0)),
+        ];
+        assert_eq!(sort_transactions_by_idx_in_block(&mut transactions), Ok(()));
+        assert_eq!(transactions[0].signature, "sig_a");
+    }
+
+    #[tokio::test]
+    async fn test_block_meta_issues_no_transaction_query() {
+        let num_queries = &AtomicUsize::new(0);
+        let query_transactions = || async move {
+            num_queries.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, BlockStoreError>(vec![postgres_transaction("sig", Some(0))])
+        };
+
+        let transactions =
+            query_transactions_for_details(TransactionDetails::None, query_transactions)
+                .await
+                .unwrap();
+        assert!(transactions.is_empty());
+        assert_eq!(num_queries.load(Ordering::SeqCst), 0);
+
+        let transactions =
+            query_transactions_for_details(TransactionDetails::Full, query_transactions)
+                .await
+                .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(num_queries.load(Ordering::SeqCst), 1);
+
+        query_transactions_for_details(TransactionDetails::Signatures, query_transactions)
+            .await
+            .unwrap();
+        assert_eq!(num_queries.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_check_slot_range_size() {
+        assert!(check_slot_range_size(100, 599, 500).is_ok());
+        assert!(matches!(
+            check_slot_range_size(100, 600, 500),
+            Err(BlockStoreError::SlotRangeTooLarge { max_range: 500, .. })
+        ));
+        // empty range
+        assert!(check_slot_range_size(600, 100, 500).is_ok());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_available_slots_sparse() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epochs 8 and 9 (1000 slots per epoch)
+        let seeded_slots = [8990, 8993, 8999, 9000, 9007];
+        recreate_epoch_schemas(&writer, &[8, 9]).await;
+        seed_blocks(&writer, &seeded_slots.map(|slot| (slot, slot, None))).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+        assert_eq!(
+            block_store.query_available_slots(8991, 9005).await.unwrap(),
+            vec![8993, 8999, 9000]
+        );
+        assert_eq!(
+            block_store.query_available_slots(8990, 9007).await.unwrap(),
+            seeded_slots.to_vec()
+        );
+        assert!(block_store
+            .query_available_slots(9001, 9006)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            block_store.query_available_slots(0, 1_000_000).await,
+            Err(BlockStoreError::SlotRangeTooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_in_empty_epoch_schema() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 11 populated, epoch 12 empty (created ahead for epoch 11), epoch 13 missing (1000 slots per epoch)
+        for epoch in [12, 13] {
+            writer
+                .drop_epoch_schema(EpochRef::new(epoch))
+                .await
+                .unwrap();
+        }
+        recreate_epoch_schemas(&writer, &[11]).await;
+        seed_blocks(&writer, &[(11042, 11042, None)]).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+        assert!(block_store.query_block(11042).await.is_ok());
+        assert!(matches!(
+            block_store.query_block(11043).await,
+            Err(BlockStoreError::NotFound { slot: 11043 })
+        ));
+        assert!(matches!(
+            block_store.query_block(12042).await,
+            Err(BlockStoreError::EpochEmpty {
+                slot: 12042,
+                epoch: 12
+            })
+        ));
+        assert!(matches!(
+            block_store.query_block(13042).await,
+            Err(BlockStoreError::EpochNotArchived {
+                slot: 13042,
+                epoch: 13
+            })
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_archive_summary() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epochs 16 and 17 (1000 slots per epoch)
+        recreate_epoch_schemas(&writer, &[16, 17]).await;
+        seed_blocks(
+            &writer,
+            &[16100, 16200, 16300, 17005].map(|slot| (slot, slot, None)),
+        )
+        .await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+        let archive_summary = block_store.archive_summary().await.unwrap();
+        assert!(archive_summary
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.epoch < b.epoch));
+        let seeded = archive_summary
+            .into_iter()
+            .filter(|info| [16, 17].contains(&info.epoch.get_epoch()))
+            .collect_vec();
+        assert_eq!(
+            seeded,
+            vec![
+                EpochArchiveInfo {
+                    epoch: EpochRef::new(16),
+                    schema_name: "rpc2a_epoch_16".to_string(),
+                    slot_min: 16100,
+                    slot_max: 16300,
+                    block_count: 3,
+                },
+                EpochArchiveInfo {
+                    epoch: EpochRef::new(17),
+                    schema_name: "rpc2a_epoch_17".to_string(),
+                    slot_min: 17005,
+                    slot_max: 17005,
+                    block_count: 1,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_epoch_sizes() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 24 (1000 slots per epoch)
+        recreate_epoch_schemas(&writer, &[24]).await;
+        seed_blocks(&writer, &[(24100, 24100, None), (24200, 24200, None)]).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+        let epoch_sizes = block_store.query_epoch_sizes().await.unwrap();
+        assert!(epoch_sizes
+            .iter()
+            .tuple_windows()
+            .all(|((a, _), (b, _))| a < b));
+        let (_, size) = epoch_sizes
+            .iter()
+            .find(|(epoch, _)| epoch.get_epoch() == 24)
+            .expect("epoch 24 must be listed");
+        assert!(*size > 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_time() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 18 (1000 slots per epoch); epoch 19 has no schema
+        recreate_epoch_schemas(&writer, &[18]).await;
+        writer.drop_epoch_schema(EpochRef::new(19)).await.unwrap();
+        seed_blocks(&writer, &[(18042, 18042, None)]).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+        assert_eq!(
+            block_store.query_block_time(18042).await.unwrap(),
+            Some(1699260872)
+        );
+        assert_eq!(block_store.query_block_time(18043).await.unwrap(), None);
+        assert_eq!(block_store.query_block_time(19042).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_archived_transaction_status() {
+        let status = archived_transaction_status(42, None);
+        assert_eq!(status.slot, 42);
+        assert_eq!(status.status, Ok(()));
+        assert_eq!(
+            status.confirmation_status,
+            Some(TransactionConfirmationStatus::Finalized)
+        );
+
+        let status = archived_transaction_status(42, Some(TransactionError::AccountInUse));
+        assert_eq!(status.status, Err(TransactionError::AccountInUse));
+        assert_eq!(status.err, Some(TransactionError::AccountInUse));
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_above_max_transactions() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig {
+            max_transactions_per_block: 2,
+            ..PostgresSessionConfig::new_for_tests()
+        };
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 21 (1000 slots per epoch)
+        let slot = 21042;
+        recreate_epoch_schemas(&writer, &[21]).await;
+        let block = ProducedBlockInner {
+            transactions: (0..3).map(|_| create_test_transaction(None)).collect_vec(),
+            ..create_test_block_inner(slot)
+        };
+        save_blocks(&writer, [block]).await;
+
+        let block_store =
+            PostgresQueryBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+        let truncated = block_store.query_block(slot).await.unwrap();
+        assert_eq!(truncated.transactions.len(), 2);
+
+        let strict_block_store = PostgresQueryBlockStore::new(
+            epoch_cache,
+            PostgresSessionConfig {
+                strict_max_transactions_per_block: true,
+                ..pg_session_config
+            },
+        )
+        .await;
+        assert!(matches!(
+            strict_block_store.query_block(slot).await,
+            Err(BlockStoreError::TooLarge {
+                slot: 21042,
+                count: 3
+            })
+        ));
+        // block meta data does not load the transactions
+        assert!(strict_block_store.query_block_meta(slot).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_in_schema_without_idx_in_block() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 28 (1000 slots per epoch)
+        let (slot, epoch) = (28042, 28);
+        recreate_epoch_schemas(&writer, &[epoch]).await;
+        let block = ProducedBlockInner {
+            transactions: vec![create_test_transaction(None)],
+            ..create_test_block_inner(slot)
+        };
+        save_blocks(&writer, [block]).await;
+
+        // simulate a schema created before the idx_in_block column was added
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        block_store
+            .get_session()
+            .await
+            .execute_multiple(&format!(
+                "ALTER TABLE {}.transaction_blockdata DROP COLUMN idx_in_block",
+                PostgresEpoch::build_schema_name(EpochRef::new(epoch))
+            ))
+            .await
+            .unwrap();
+        let block = block_store.query_block(slot).await.unwrap();
+        assert_eq!(block.transactions.len(), 1);
+
+        // preparing the existing schema adds the column back
+        assert!(!writer.prepare_epoch_schema(slot).await.unwrap());
+        let block = block_store.query_block(slot).await.unwrap();
+        assert_eq!(block.transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_signature_statuses() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 7 (1000 slots per epoch)
+        let slot = 7042;
+        recreate_epoch_schemas(&writer, &[7]).await;
+
+        let succeeded = create_test_transaction(None);
+        let failed = create_test_transaction(Some(TransactionError::AccountInUse));
+        let (succeeded_signature, failed_signature) = (succeeded.signature, failed.signature);
+        let block = ProducedBlockInner {
+            transactions: vec![succeeded, failed],
+            ..create_test_block_inner(slot)
+        };
+        save_blocks(&writer, [block]).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        let statuses = block_store
+            .query_signature_statuses(&[
+                Signature::new_unique(),
+                failed_signature,
+                succeeded_signature,
+                Signature::new_unique(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(statuses.len(), 4);
+        assert!(statuses[0].is_none());
+        assert_eq!(
+            statuses[1]
+                .as_ref()
+                .map(|status| (status.slot, status.err.clone())),
+            Some((slot, Some(TransactionError::AccountInUse)))
         );
+        assert_eq!(
+            statuses[2]
+                .as_ref()
+                .map(|status| (status.slot, status.err.clone())),
+            Some((slot, None))
+        );
+        assert!(statuses[3].is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_range_paged() {
+        use futures::TryStreamExt;
+
+        let block_store = PostgresQueryBlockStore::new(
+            EpochCache::new_for_tests(),
+            PostgresSessionConfig::new_for_tests(),
+        )
+        .await;
+
+        let blocks: Vec<ProducedBlock> = block_store
+            .query_block_range_paged(985, 1012, 10)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(blocks
+            .iter()
+            .all(|block| (985..=1012).contains(&block.slot)));
+        assert!(blocks.windows(2).all(|w| w[0].slot < w[1].slot));
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_blocks_by_epoch_with_missing_schema() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig {
+            // keep the dropped epoch in the slot range cache
+            slot_range_cache_ttl_ms: 60_000,
+            ..PostgresSessionConfig::new_for_tests()
+        };
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epochs 22 and 23 (1000 slots per epoch)
+        recreate_epoch_schemas(&writer, &[22, 23]).await;
+        seed_blocks(&writer, &[(22042, 22042, None), (23042, 23042, None)]).await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        // cache both epochs before one of the schemas disappears
+        assert!(block_store.is_block_in_range(23042).await);
+        writer.drop_epoch_schema(EpochRef::new(23)).await.unwrap();
+
+        let blocks_by_epoch = block_store
+            .query_blocks_by_epoch(22000..=23999)
+            .await
+            .unwrap();
+        assert_eq!(blocks_by_epoch.len(), 2);
+
+        let (epoch, blocks) = &blocks_by_epoch[0];
+        assert_eq!(*epoch, EpochRef::new(22));
+        let blocks = blocks.as_ref().unwrap();
+        assert_eq!(
+            blocks.iter().map(|block| block.slot).collect_vec(),
+            vec![22042]
+        );
+
+        let (epoch, blocks) = &blocks_by_epoch[1];
+        assert_eq!(*epoch, EpochRef::new(23));
+        assert!(blocks.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_leader_schedule() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        // epoch 7 (1000 slots per epoch); slot 7002 has no leader
+        let leader_a = Pubkey::new_unique();
+        let leader_b = Pubkey::new_unique();
+        let seeded = [
+            (7000, Some(leader_a)),
+            (7001, Some(leader_a)),
+            (7002, None),
+            (7003, Some(leader_b)),
+        ];
+        let epoch = EpochRef::new(7);
+        recreate_epoch_schemas(&writer, &[7]).await;
+        save_blocks(
+            &writer,
+            seeded.map(|(slot, leader)| ProducedBlockInner {
+                leader_id: leader.map(|leader| leader.to_string()),
+                ..create_test_block_inner(slot)
+            }),
+        )
+        .await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        let leader_schedule = block_store.query_leader_schedule(epoch).await.unwrap();
+
+        assert_eq!(
+            leader_schedule,
+            HashMap::from([(7000, leader_a), (7001, leader_a), (7003, leader_b)])
+        );
+        assert!(block_store
+            .query_leader_schedule(EpochRef::new(999_999))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_rewards_for_address() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+
+        let voter = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let reward = |pubkey: Pubkey, lamports: i64, reward_type: RewardType| Reward {
+            pubkey: pubkey.to_string(),
+            lamports,
+            post_balance: 1_000_000 + lamports as u64,
+            reward_type: Some(reward_type),
+            commission: Some(10),
+        };
+        // epoch 25 (1000 slots per epoch); slot 25001 has no rewards
+        let seeded = [
+            (
+                25000,
+                Some(vec![
+                    reward(voter, 500, RewardType::Voting),
+                    reward(other, 700, RewardType::Voting),
+                ]),
+            ),
+            (25001, None),
+            (25002, Some(vec![reward(other, 100, RewardType::Fee)])),
+            (25003, Some(vec![reward(voter, 300, RewardType::Staking)])),
+        ];
+        let epoch = EpochRef::new(25);
+        recreate_epoch_schemas(&writer, &[25]).await;
+        seed_blocks(
+            &writer,
+            &seeded.map(|(slot, rewards)| (slot, slot, rewards)),
+        )
+        .await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        let rewards = block_store
+            .query_rewards_for_address(&voter, epoch)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rewards,
+            vec![
+                RewardEntry {
+                    slot: 25000,
+                    lamports: 500,
+                    post_balance: 1_000_500,
+                    reward_type: Some(RewardType::Voting),
+                    commission: Some(10),
+                },
+                RewardEntry {
+                    slot: 25003,
+                    lamports: 300,
+                    post_balance: 1_000_300,
+                    reward_type: Some(RewardType::Staking),
+                    commission: Some(10),
+                },
+            ]
+        );
+        assert!(block_store
+            .query_rewards_for_address(&voter, EpochRef::new(999_999))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_by_height() {
+        use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+        let epoch_cache = EpochCache::new_for_tests();
+        let pg_session_config = PostgresSessionConfig::new_for_tests();
+        let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
 
-        final_range
+        // epochs 26 and 27 (1000 slots per epoch); heights far off the slot based heights of other tests;
+        // height 9_026_900 is ambiguous
+        let seeded = [
+            (26000, 9_026_000),
+            (26001, 9_026_001),
+            (26003, 9_026_002),
+            (26500, 9_026_900),
+            (27000, 9_026_900),
+        ];
+        recreate_epoch_schemas(&writer, &[26, 27]).await;
+        seed_blocks(
+            &writer,
+            &seeded.map(|(slot, block_height)| (slot, block_height, None)),
+        )
+        .await;
+
+        let block_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+        let block = block_store
+            .query_block_by_height(9_026_002)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.slot, 26003);
+        assert_eq!(block.block_height, 9_026_002);
+
+        let ambiguous = block_store
+            .query_block_by_height(9_026_900)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ambiguous.slot, 26500);
+
+        assert!(block_store
+            .query_block_by_height(9_026_003)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slot_range_cache_within_ttl() {
+        let cache = SlotRangeCache::new(Duration::from_secs(60));
+        let num_queries = AtomicUsize::new(0);
+        let ranges = [(500, 216000000..=216431999)];
+
+        let first = cache
+            .get_or_refresh(None, || async { counting_refresh(&num_queries, &ranges) })
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_refresh(Some((EpochRef::new(500), 216000100)), || async {
+                counting_refresh(&num_queries, &ranges)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(num_queries.load(Ordering::SeqCst), 1);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_slot_range_cache_refreshes_for_newer_slot() {
+        let cache = SlotRangeCache::new(Duration::from_secs(60));
+        let num_queries = AtomicUsize::new(0);
+        let ranges = [(500, 216000000..=216000100)];
+        let newer_slot = Some((EpochRef::new(500), 216000200));
+
+        cache
+            .get_or_refresh(None, || async { counting_refresh(&num_queries, &ranges) })
+            .await
+            .unwrap();
+        // rate limited
+        cache
+            .get_or_refresh(newer_slot, || async {
+                counting_refresh(&num_queries, &ranges)
+            })
+            .await
+            .unwrap();
+        assert_eq!(num_queries.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(SLOT_RANGE_CACHE_MIN_REFRESH_INTERVAL).await;
+        // slot within the cached range of the current epoch does not refresh
+        cache
+            .get_or_refresh(Some((EpochRef::new(500), 216000050)), || async {
+                counting_refresh(&num_queries, &ranges)
+            })
+            .await
+            .unwrap();
+        assert_eq!(num_queries.load(Ordering::SeqCst), 1);
+        cache
+            .get_or_refresh(newer_slot, || async {
+                counting_refresh(&num_queries, &ranges)
+            })
+            .await
+            .unwrap();
+        assert_eq!(num_queries.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_slot_range_cache_expires() {
+        let cache = SlotRangeCache::new(Duration::from_millis(10));
+        let num_queries = AtomicUsize::new(0);
+        let ranges = [(500, 216000000..=216431999)];
+
+        for _ in 0..2 {
+            cache
+                .get_or_refresh(None, || async { counting_refresh(&num_queries, &ranges) })
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(num_queries.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_query_block_uses_read_replica() {
+        let primary_config = PostgresSessionConfig::new_for_tests();
+        // same database but distinguishable by application_name
+        let replica_pg_config = format!(
+            "{} application_name=literpc_read_replica",
+            primary_config.pg_config
+        );
+        let pg_session_config = PostgresSessionConfig {
+            read_replicas: vec![replica_pg_config],
+            ..primary_config
+        };
+
+        let block_store =
+            PostgresQueryBlockStore::new(EpochCache::new_for_tests(), pg_session_config).await;
+
+        // query_block uses the session returned by get_session
+        let row = block_store
+            .get_session()
+            .await
+            .query_one(
+                "SELECT current_setting('application_name') AS application_name",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            row.get::<&str, String>("application_name"),
+            "literpc_read_replica"
+        );
+
+        let row = block_store
+            .primary_session_cache
+            .get_session()
+            .await
+            .unwrap()
+            .query_one(
+                "SELECT current_setting('application_name') AS application_name",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_ne!(
+            row.get::<&str, String>("application_name"),
+            "literpc_read_replica"
+        );
     }
 }
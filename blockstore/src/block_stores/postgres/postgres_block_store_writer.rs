@@ -13,6 +13,7 @@ use tokio_postgres::error::SqlState;
 use super::postgres_block::*;
 use super::postgres_config::*;
 use super::postgres_epoch::*;
+use super::postgres_self_check::check_role;
 use super::postgres_session::*;
 use super::postgres_transaction::*;
 
@@ -25,6 +26,8 @@ pub struct PostgresBlockStore {
     // use this session only for the write path!
     write_sessions: Vec<PostgresWriteSession>,
     epoch_schedule: EpochCache,
+    message_compression: PostgresMessageCompression,
+    enable_epoch_pruning: bool,
 }
 
 impl PostgresBlockStore {
@@ -45,36 +48,20 @@ impl PostgresBlockStore {
             "must have at least one write session"
         );
 
-        Self::check_write_role(&session_cache).await;
+        check_role(
+            &session_cache,
+            LITERPC_ROLE,
+            "write role/ownership",
+            pg_session_config.skip_self_checks,
+        )
+        .await;
 
         Self {
             session_cache,
             write_sessions,
             epoch_schedule,
-        }
-    }
-
-    async fn check_write_role(session_cache: &PostgresSessionCache) {
-        let role = LITERPC_ROLE;
-        let statement = format!("SELECT 1 FROM pg_roles WHERE rolname='{role}'");
-        let count = session_cache
-            .get_session()
-            .await
-            .expect("must get session")
-            .execute(&statement, &[])
-            .await
-            .expect("must execute query to check for role");
-
-        if count == 0 {
-            panic!(
-                "Missing mandatory postgres write/ownership role '{}' for Lite RPC - see permissions.sql",
-                role
-            );
-        } else {
-            info!(
-                "Self check - found postgres write role/ownership '{}'",
-                role
-            );
+            message_compression: pg_session_config.message_compression,
+            enable_epoch_pruning: pg_session_config.enable_epoch_pruning,
         }
     }
 
@@ -98,6 +85,11 @@ impl PostgresBlockStore {
                     "Schema {} for epoch {} already exists - data will be appended",
                     schema_name, epoch
                 );
+                let statement = PostgresTransaction::build_add_idx_in_block_column_statement(epoch);
+                session
+                    .execute_multiple(&statement)
+                    .await
+                    .context("add idx_in_block column to existing epoch schema")?;
                 return Ok(false);
             } else {
                 return Err(err).context("create schema for new epoch");
@@ -171,7 +163,11 @@ impl PostgresBlockStore {
         let transactions = block
             .transactions
             .iter()
-            .map(|x| PostgresTransaction::new(x, slot))
+            .enumerate()
+            .map(|(idx, x)| PostgresTransaction {
+                idx_in_block: Some(idx as i32),
+                ..PostgresTransaction::new_with_compression(x, slot, &self.message_compression)
+            })
             .collect_vec();
         let postgres_block = PostgresBlock::from(block);
 
@@ -273,7 +269,7 @@ impl PostgresBlockStore {
         Ok(created_current || created_next)
     }
 
-    // used for testing only ATM
+    // used for testing and by prune_epochs_older_than
     pub async fn drop_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<()> {
         // create schema for new epoch
         let schema_name = PostgresEpoch::build_schema_name(epoch);
@@ -291,6 +287,116 @@ impl PostgresBlockStore {
             }
         }
     }
+
+    /// drop the oldest epoch schemas, keeping the last `keep_last_n_epochs` archived epochs; returns the
+    /// dropped epochs (oldest first)
+    ///
+    /// the current epoch (see [Self::plan_epoch_pruning]) and its previous epoch are never dropped;
+    /// requires `enable_epoch_pruning` and a session with the write role
+    pub async fn prune_epochs_older_than(
+        &self,
+        keep_last_n_epochs: usize,
+    ) -> Result<Vec<EpochRef>> {
+        if !self.enable_epoch_pruning {
+            bail!("Epoch pruning is disabled - set enable_epoch_pruning (PG_ENABLE_EPOCH_PRUNING)");
+        }
+        let session = self.get_session().await;
+
+        let has_write_role: bool = session
+            .query_one(
+                &format!(
+                    "SELECT pg_has_role(current_user, '{LITERPC_ROLE}', 'MEMBER') AS has_role"
+                ),
+                &[],
+            )
+            .await
+            .context("check write role for pruning")?
+            .get("has_role");
+        if !has_write_role {
+            bail!("Epoch pruning requires the postgres write role '{LITERPC_ROLE}'");
+        }
+
+        let prune = self.plan_epoch_pruning(keep_last_n_epochs).await?;
+        for epoch in &prune {
+            self.drop_epoch_schema(*epoch).await?;
+        }
+        info!(
+            "Pruned {} epoch schemas (keep last {})",
+            prune.len(),
+            keep_last_n_epochs
+        );
+        Ok(prune)
+    }
+
+    /// dry-run of [Self::prune_epochs_older_than]: the epoch schemas it would drop (oldest first)
+    ///
+    /// the current epoch is the epoch of the newest archived block; not the newest schema as the schema of the
+    /// next epoch is created ahead of time
+    pub async fn plan_epoch_pruning(&self, keep_last_n_epochs: usize) -> Result<Vec<EpochRef>> {
+        let session = self.get_session().await;
+
+        let query = format!(
+            r#"
+                SELECT
+                 schema_name
+                FROM information_schema.schemata
+                WHERE schema_name ~ '^{schema_prefix}[0-9]+$'
+            "#,
+            schema_prefix = EPOCH_SCHEMA_PREFIX
+        );
+        let epochs = session
+            .query_list(&query, &[])
+            .await
+            .context("list epoch schemas")?
+            .iter()
+            .map(|row| PostgresEpoch::parse_epoch_from_schema_name(row.get("schema_name")))
+            .sorted()
+            .collect_vec();
+
+        let mut latest_archived_slot = None;
+        for epoch in epochs.iter().rev() {
+            let statement = format!(
+                "SELECT max(slot) AS max_slot FROM {schema}.blocks",
+                schema = PostgresEpoch::build_schema_name(*epoch)
+            );
+            let max_slot: Option<i64> = session
+                .query_one(&statement, &[])
+                .await
+                .context("query latest archived slot")?
+                .get("max_slot");
+            if max_slot.is_some() {
+                latest_archived_slot = max_slot;
+                break;
+            }
+        }
+        let Some(latest_archived_slot) = latest_archived_slot else {
+            bail!("No archived blocks - cannot determine the current epoch for pruning");
+        };
+
+        let current_epoch: EpochRef = self
+            .epoch_schedule
+            .get_epoch_at_slot(latest_archived_slot as Slot)
+            .into();
+        Ok(epochs_to_prune(&epochs, current_epoch, keep_last_n_epochs))
+    }
+}
+
+/// epochs (sorted ascending) beyond the last `keep_last_n_epochs` up to `current_epoch`, oldest first;
+/// never the current or previous epoch nor epochs after the current one
+fn epochs_to_prune(
+    epochs: &[EpochRef],
+    current_epoch: EpochRef,
+    keep_last_n_epochs: usize,
+) -> Vec<EpochRef> {
+    let archived = epochs
+        .iter()
+        .filter(|epoch| **epoch <= current_epoch)
+        .collect_vec();
+    archived[..archived.len().saturating_sub(keep_last_n_epochs)]
+        .iter()
+        .map(|epoch| **epoch)
+        .filter(|epoch| epoch.get_epoch() + 1 < current_epoch.get_epoch())
+        .collect_vec()
 }
 
 fn build_assign_permissions_statements(epoch: EpochRef) -> String {
@@ -316,6 +422,7 @@ fn div_ceil(a: usize, b: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block_stores::test_utils::{recreate_epoch_schemas, seed_blocks};
     use solana_lite_rpc_core::structures::produced_block::{ProducedBlockInner, TransactionInfo};
     use solana_sdk::commitment_config::CommitmentConfig;
     use solana_sdk::message::{v0, MessageHeader, VersionedMessage};
@@ -337,6 +444,75 @@ mod tests {
         info!("row: {:?}", row_role);
     }
 
+    #[test]
+    fn test_epochs_to_prune() {
+        let epochs = [10, 11, 12, 13, 14, 15].map(EpochRef::new);
+        let current_epoch = EpochRef::new(14);
+
+        // 15 is the schema created ahead for the next epoch
+        assert_eq!(
+            epochs_to_prune(&epochs, current_epoch, 3),
+            [10, 11].map(EpochRef::new)
+        );
+        // current and previous epoch are kept regardless of the retention
+        assert_eq!(
+            epochs_to_prune(&epochs, current_epoch, 0),
+            [10, 11, 12].map(EpochRef::new)
+        );
+        assert!(epochs_to_prune(&epochs, current_epoch, 10).is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_plan_epoch_pruning() {
+        let epoch_cache = EpochCache::new_for_tests();
+        let writer =
+            PostgresBlockStore::new(epoch_cache, PostgresSessionConfig::new_for_tests()).await;
+
+        // epochs far above the schemas of the other tests and real epochs (1000 slots per epoch) so that
+        // 100_004 is the current epoch; the plan includes other schemas, so only this range is asserted
+        let epochs = (100_000..=100_004).collect_vec();
+        recreate_epoch_schemas(&writer, &epochs).await;
+        seed_blocks(
+            &writer,
+            &epochs
+                .iter()
+                .map(|epoch| (epoch * 1000 + 42, epoch * 1000 + 42, None))
+                .collect_vec(),
+        )
+        .await;
+
+        let plan = writer.plan_epoch_pruning(3).await.unwrap();
+        assert!(plan.windows(2).all(|w| w[0] < w[1]), "oldest first");
+        let test_epochs = plan
+            .into_iter()
+            .filter(|epoch| (100_000..=100_005).contains(&epoch.get_epoch()))
+            .collect_vec();
+        assert_eq!(
+            test_epochs,
+            vec![EpochRef::new(100_000), EpochRef::new(100_001)]
+        );
+
+        // do not leave the newest archived epoch behind for other tests
+        for epoch in 100_000..=100_005 {
+            writer
+                .drop_epoch_schema(EpochRef::new(epoch))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "need postgres database"]
+    async fn test_prune_epochs_disabled() {
+        let writer = PostgresBlockStore::new(
+            EpochCache::new_for_tests(),
+            PostgresSessionConfig::new_for_tests(),
+        )
+        .await;
+        assert!(writer.prune_epochs_older_than(3).await.is_err());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_save_block() {
@@ -346,6 +522,21 @@ mod tests {
             pg_config: "host=localhost dbname=literpc3 user=literpc_app password=litelitesecret"
                 .to_string(),
             ssl: None,
+            query_timeouts: Default::default(),
+            ssl_mode: None,
+            ssl_root_cert: None,
+            ssl_cert: None,
+            ssl_key: None,
+            read_replicas: vec![],
+            message_compression: Default::default(),
+            slot_range_cache_ttl_ms: PostgresSessionConfig::default_slot_range_cache_ttl_ms(),
+            max_available_slots_range: PostgresSessionConfig::default_max_available_slots_range(),
+            strict_transaction_order: false,
+            max_transactions_per_block: PostgresSessionConfig::default_max_transactions_per_block(),
+            strict_max_transactions_per_block: false,
+            health_check_interval_ms: PostgresSessionConfig::default_health_check_interval_ms(),
+            skip_self_checks: false,
+            enable_epoch_pruning: false,
         };
 
         let _postgres_session_cache = PostgresSessionCache::new(pg_session_config.clone())
@@ -1,4 +1,5 @@
-use solana_lite_rpc_core::structures::epoch::EpochRef;
+use solana_lite_rpc_core::structures::epoch::{EpochCache, EpochRef};
+use solana_sdk::slot_history::Slot;
 
 pub struct PostgresEpoch {}
 
@@ -37,6 +38,25 @@ impl PostgresEpoch {
     }
 }
 
+/// name of the schema holding the blocks of `epoch`; for tools working on the same database
+pub fn schema_name_for_epoch(epoch: EpochRef) -> String {
+    PostgresEpoch::build_schema_name(epoch)
+}
+
+/// epoch (and thus schema) a slot is stored in
+pub fn epoch_for_slot(slot: Slot, epoch_cache: &EpochCache) -> EpochRef {
+    epoch_cache.get_epoch_at_slot(slot).into()
+}
+
+/// inverse of [schema_name_for_epoch]; None if `schema_name` is not an epoch schema
+pub fn epoch_from_schema_name(schema_name: &str) -> Option<EpochRef> {
+    schema_name
+        .strip_prefix(EPOCH_SCHEMA_PREFIX)
+        .filter(|epoch| !epoch.is_empty() && epoch.bytes().all(|c| c.is_ascii_digit()))
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .map(EpochRef::new)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -54,4 +74,29 @@ mod tests {
         let epoch = PostgresEpoch::parse_epoch_from_schema_name(schema);
         assert_eq!(644, epoch.get_epoch());
     }
+
+    #[test]
+    fn test_slot_to_schema_name_roundtrip() {
+        // 1000 slots per epoch
+        let epoch_cache = EpochCache::new_for_tests();
+        for (slot, expected_schema) in [
+            (0, "rpc2a_epoch_0"),
+            (999, "rpc2a_epoch_0"),
+            (1000, "rpc2a_epoch_1"),
+            (644_123, "rpc2a_epoch_644"),
+        ] {
+            let epoch = epoch_for_slot(slot, &epoch_cache);
+            let schema_name = schema_name_for_epoch(epoch);
+            assert_eq!(schema_name, expected_schema);
+            assert_eq!(epoch_from_schema_name(&schema_name), Some(epoch));
+        }
+    }
+
+    #[test]
+    fn test_epoch_from_invalid_schema_name() {
+        assert_eq!(epoch_from_schema_name("public"), None);
+        assert_eq!(epoch_from_schema_name("rpc2a_epoch_"), None);
+        assert_eq!(epoch_from_schema_name("rpc2a_epoch_64x"), None);
+        assert_eq!(epoch_from_schema_name("rpc2a_epoch_+1"), None);
+    }
 }
@@ -0,0 +1,74 @@
+use std::future::Future;
+
+use log::{info, warn};
+
+use super::postgres_session::PostgresSessionCache;
+
+/// a failed self-check panics unless `skip_self_checks` is set, then it is logged as warning
+pub fn self_check_failed(skip_self_checks: bool, message: &str) {
+    if skip_self_checks {
+        warn!("Self check failed - continue as skip_self_checks is set: {message}");
+    } else {
+        panic!("{message}");
+    }
+}
+
+/// checks that the postgres `role` required by the block store component exists
+pub async fn check_role(
+    session_cache: &PostgresSessionCache,
+    role: &str,
+    role_description: &str,
+    skip_self_checks: bool,
+) {
+    let statement = format!("SELECT 1 FROM pg_roles WHERE rolname='{role}'");
+    check_role_with(role, role_description, skip_self_checks, || async {
+        let session = session_cache.get_session().await?;
+        Ok(session.execute(&statement, &[]).await?)
+    })
+    .await
+}
+
+async fn check_role_with<F, Fut>(
+    role: &str,
+    role_description: &str,
+    skip_self_checks: bool,
+    count_roles: F,
+) where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = anyhow::Result<u64>>,
+{
+    match count_roles().await {
+        Ok(0) => self_check_failed(
+            skip_self_checks,
+            &format!(
+                "Missing mandatory postgres {role_description} '{role}' for Lite RPC - see permissions.sql"
+            ),
+        ),
+        Ok(_) => info!("Self check - found postgres {role_description} '{role}'"),
+        Err(err) => self_check_failed(
+            skip_self_checks,
+            &format!("Cannot check for postgres {role_description} '{role}': {err:?}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_skipped_self_checks_do_not_panic() {
+        // managed provider where the role is externally managed and not visible
+        check_role_with("ro_literpc", "query role", true, || async { Ok(0) }).await;
+        check_role_with("ro_literpc", "query role", true, || async {
+            Err(anyhow::anyhow!("permission denied for table pg_roles"))
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Missing mandatory postgres query role 'ro_literpc'")]
+    async fn test_failed_self_check_panics_by_default() {
+        check_role_with("ro_literpc", "query role", false, || async { Ok(0) }).await;
+    }
+}
@@ -1,21 +1,106 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::Context;
-use log::debug;
+use futures_util::{Stream, TryStreamExt};
+use log::{debug, warn};
 use native_tls::{Certificate, Identity, TlsConnector};
 use postgres_native_tls::MakeTlsConnector;
 use solana_lite_rpc_core::encoding::BinaryEncoding;
 use tokio::sync::RwLock;
 use tokio_postgres::{
-    config::SslMode, tls::MakeTlsConnect, types::ToSql, Client, CopyInSink, Error, NoTls, Row,
-    Socket,
+    config::SslMode, error::SqlState, tls::MakeTlsConnect, types::ToSql, Client, CopyInSink, Error,
+    NoTls, Row, Socket, Statement,
 };
 
-use super::postgres_config::{PostgresSessionConfig, PostgresSessionSslConfig};
+use super::postgres_config::{
+    redact_pg_config, PostgresSessionConfig, PostgresSessionSslConfig, PostgresTlsConfig,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PostgresQueryError {
+    /// query was cancelled by postgres after exceeding the session statement_timeout
+    #[error("Postgres query exceeded statement_timeout of {0:?}")]
+    StatementTimeout(Duration),
+    #[error("Postgres query failed: {0}")]
+    Postgres(#[from] Error),
+}
+
+impl PostgresQueryError {
+    pub fn is_undefined_column(&self) -> bool {
+        match self {
+            PostgresQueryError::Postgres(err) => err.code() == Some(&SqlState::UNDEFINED_COLUMN),
+            PostgresQueryError::StatementTimeout(_) => false,
+        }
+    }
+}
+
+// a ping not answered within this time marks the connection as dead
+const HEALTH_CHECK_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+// statements are keyed by text which contains the epoch schema; cache gets reset when full
+const PREPARED_STATEMENT_CACHE_CAPACITY: usize = 256;
+
+/// prepared statements by statement text; the handles are only valid for the connection they were prepared on
+pub struct PreparedStatementCache<S = Statement> {
+    statements: Mutex<HashMap<String, S>>,
+}
+
+impl<S: Clone> PreparedStatementCache<S> {
+    pub fn new() -> Self {
+        Self {
+            statements: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// returns the cached statement or calls `prepare` and caches the result
+    pub async fn get_or_prepare<F, Fut, E>(&self, statement: &str, prepare: F) -> Result<S, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<S, E>>,
+    {
+        if let Some(prepared) = self.statements.lock().unwrap().get(statement) {
+            return Ok(prepared.clone());
+        }
+
+        let prepared = prepare().await?;
+
+        let mut statements = self.statements.lock().unwrap();
+        if statements.len() >= PREPARED_STATEMENT_CACHE_CAPACITY {
+            statements.clear();
+        }
+        statements.insert(statement.to_string(), prepared.clone());
+        Ok(prepared)
+    }
+
+    pub fn invalidate(&self, statement: &str) {
+        self.statements.lock().unwrap().remove(statement);
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S: Clone> Default for PreparedStatementCache<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[derive(Clone)]
 pub struct PostgresSession {
     pub client: Arc<Client>,
+    statement_timeout: Option<Duration>,
+    // bound to client; a replaced connection comes with a new session and an empty cache
+    prepared_statements: Arc<PreparedStatementCache>,
 }
 
 impl PostgresSession {
@@ -26,12 +111,28 @@ impl PostgresSession {
         PostgresSession::new(pg_session_config).await
     }
 
-    pub async fn new(
-        PostgresSessionConfig { pg_config, ssl }: PostgresSessionConfig,
-    ) -> anyhow::Result<Self> {
-        let pg_config = pg_config.parse::<tokio_postgres::Config>()?;
+    pub async fn new(pg_session_config: PostgresSessionConfig) -> anyhow::Result<Self> {
+        let tls_config = pg_session_config.tls_config()?;
+        let redacted_pg_config = redact_pg_config(&pg_session_config.pg_config);
+        debug!("Connecting to Postgres {redacted_pg_config}");
+        let PostgresSessionConfig {
+            pg_config,
+            ssl,
+            ssl_mode,
+            ..
+        } = pg_session_config;
+
+        let mut pg_config = pg_config
+            .parse::<tokio_postgres::Config>()
+            .with_context(|| format!("Invalid pg_config {redacted_pg_config}"))?;
+        if let Some(ssl_mode) = ssl_mode {
+            pg_config.ssl_mode(ssl_mode.into());
+        }
 
-        let client = if let SslMode::Disable = pg_config.get_ssl_mode() {
+        let client = if let Some(tls_config) = tls_config {
+            let connector = Self::build_tls_connector(tls_config)?;
+            Self::spawn_connection(pg_config, MakeTlsConnector::new(connector)).await?
+        } else if let SslMode::Disable = pg_config.get_ssl_mode() {
             Self::spawn_connection(pg_config, NoTls).await?
         } else {
             let PostgresSessionSslConfig {
@@ -59,9 +160,62 @@ impl PostgresSession {
 
         Ok(Self {
             client: Arc::new(client),
+            statement_timeout: None,
+            prepared_statements: Arc::new(PreparedStatementCache::new()),
         })
     }
 
+    /// session which cancels every statement running longer than `statement_timeout`;
+    /// the timeout is set on a dedicated session because the client is shared between concurrent callers
+    /// and cannot open a transaction for SET LOCAL
+    pub async fn new_with_statement_timeout(
+        pg_session_config: PostgresSessionConfig,
+        statement_timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        let mut session = Self::new(pg_session_config).await?;
+
+        let statement = format!(
+            "SET SESSION statement_timeout = {}",
+            statement_timeout.as_millis()
+        );
+        session
+            .execute_multiple(&statement)
+            .await
+            .context("set statement_timeout")?;
+        session.statement_timeout = Some(statement_timeout);
+
+        Ok(session)
+    }
+
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        self.statement_timeout
+    }
+
+    fn build_tls_connector(
+        PostgresTlsConfig {
+            root_cert_pem,
+            client_cert_key_pem,
+            verify_certs,
+            verify_hostname,
+        }: PostgresTlsConfig,
+    ) -> anyhow::Result<TlsConnector> {
+        let mut builder = TlsConnector::builder();
+        if let Some(root_cert_pem) = root_cert_pem {
+            builder.add_root_certificate(
+                Certificate::from_pem(&root_cert_pem).context("ssl_root_cert")?,
+            );
+        }
+        if let Some((cert_pem, key_pem)) = client_cert_key_pem {
+            builder
+                .identity(Identity::from_pkcs8(&cert_pem, &key_pem).context("ssl_cert / ssl_key")?);
+        }
+        builder
+            .danger_accept_invalid_certs(!verify_certs)
+            .danger_accept_invalid_hostnames(!verify_hostname);
+
+        Ok(builder.build()?)
+    }
+
     async fn spawn_connection<T>(
         pg_config: tokio_postgres::Config,
         connector: T,
@@ -216,6 +370,75 @@ impl PostgresSession {
         self.client.query(statement, params).await
     }
 
+    /// like [Self::query_opt] but uses a cached prepared statement
+    /// and reports a statement_timeout as [PostgresQueryError::StatementTimeout]
+    pub async fn try_query_opt(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, PostgresQueryError> {
+        let prepared = self.prepare_cached(statement).await?;
+        self.client
+            .query_opt(&prepared, params)
+            .await
+            .map_err(|err| self.to_query_error_invalidating(statement, err))
+    }
+
+    /// like [Self::query_list] but uses a cached prepared statement
+    /// and reports a statement_timeout as [PostgresQueryError::StatementTimeout]
+    pub async fn try_query_list(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, PostgresQueryError> {
+        let prepared = self.prepare_cached(statement).await?;
+        self.client
+            .query(&prepared, params)
+            .await
+            .map_err(|err| self.to_query_error_invalidating(statement, err))
+    }
+
+    /// like [Self::try_query_list] but yields the rows as they arrive instead of loading all of them into memory
+    pub async fn try_query_stream(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<impl Stream<Item = Result<Row, PostgresQueryError>> + '_, PostgresQueryError> {
+        let prepared = self.prepare_cached(statement).await?;
+        let rows = self
+            .client
+            .query_raw(&prepared, params.iter().map(|param| *param as &dyn ToSql))
+            .await
+            .map_err(|err| self.to_query_error_invalidating(statement, err))?;
+        Ok(rows.map_err(|err| self.to_query_error(err)))
+    }
+
+    /// prepare statement once per connection and reuse the handle for identical statement text
+    pub async fn prepare_cached(&self, statement: &str) -> Result<Statement, PostgresQueryError> {
+        self.prepared_statements
+            .get_or_prepare(statement, || self.client.prepare_typed(statement, &[]))
+            .await
+            .map_err(|err| self.to_query_error(err))
+    }
+
+    // drop the cached statement as it might be stale (e.g. epoch schema was dropped)
+    fn to_query_error_invalidating(&self, statement: &str, err: Error) -> PostgresQueryError {
+        if err.code() != Some(&SqlState::QUERY_CANCELED) {
+            self.prepared_statements.invalidate(statement);
+        }
+        self.to_query_error(err)
+    }
+
+    fn to_query_error(&self, err: Error) -> PostgresQueryError {
+        match self.statement_timeout {
+            // statement_timeout is reported as "canceling statement due to statement timeout"
+            Some(statement_timeout) if err.code() == Some(&SqlState::QUERY_CANCELED) => {
+                PostgresQueryError::StatementTimeout(statement_timeout)
+            }
+            _ => PostgresQueryError::Postgres(err),
+        }
+    }
+
     pub async fn copy_in(&self, statement: &str) -> Result<CopyInSink<bytes::Bytes>, Error> {
         // BinaryCopyInWriter
         // https://github.com/sfackler/rust-postgres/blob/master/tokio-postgres/tests/test/binary_copy.rs
@@ -223,32 +446,136 @@ impl PostgresSession {
     }
 }
 
+/// session which is replaced by a new connection once the old one is closed or stops answering;
+/// the connection is pinged in the background if `health_check_interval_ms` is configured
 #[derive(Clone)]
 pub struct PostgresSessionCache {
     session: Arc<RwLock<PostgresSession>>,
     config: PostgresSessionConfig,
+    statement_timeout: Option<Duration>,
+    // false after a failed ping until the session was rebuilt
+    connected: Arc<AtomicBool>,
 }
 
 impl PostgresSessionCache {
     pub async fn new(config: PostgresSessionConfig) -> anyhow::Result<Self> {
-        let session = PostgresSession::new(config.clone()).await?;
-        Ok(Self {
+        Self::new_with_optional_statement_timeout(config, None).await
+    }
+
+    /// all sessions (including reconnects) get the statement_timeout applied
+    pub async fn new_with_statement_timeout(
+        config: PostgresSessionConfig,
+        statement_timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        Self::new_with_optional_statement_timeout(config, Some(statement_timeout)).await
+    }
+
+    async fn new_with_optional_statement_timeout(
+        config: PostgresSessionConfig,
+        statement_timeout: Option<Duration>,
+    ) -> anyhow::Result<Self> {
+        let session = Self::connect(&config, statement_timeout).await?;
+        let session_cache = Self {
             session: Arc::new(RwLock::new(session)),
             config,
-        })
+            statement_timeout,
+            connected: Arc::new(AtomicBool::new(true)),
+        };
+        if let Some(health_check_interval) = session_cache.config.health_check_interval() {
+            session_cache.spawn_health_check(health_check_interval);
+        }
+        Ok(session_cache)
+    }
+
+    async fn connect(
+        config: &PostgresSessionConfig,
+        statement_timeout: Option<Duration>,
+    ) -> anyhow::Result<PostgresSession> {
+        match statement_timeout {
+            Some(statement_timeout) => {
+                PostgresSession::new_with_statement_timeout(config.clone(), statement_timeout).await
+            }
+            None => PostgresSession::new(config.clone()).await,
+        }
+    }
+
+    /// false if the last health check failed and the connection could not be rebuilt yet
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
     }
 
     pub async fn get_session(&self) -> anyhow::Result<PostgresSession> {
         let session = self.session.read().await;
-        if session.client.is_closed() {
+        if session.client.is_closed() || !self.is_connected() {
             drop(session);
-            let session = PostgresSession::new(self.config.clone()).await?;
-            *self.session.write().await = session.clone();
-            Ok(session)
+            self.reconnect().await
         } else {
             Ok(session.clone())
         }
     }
+
+    async fn reconnect(&self) -> anyhow::Result<PostgresSession> {
+        let mut session = self.session.write().await;
+        // concurrent caller might have reconnected while we were waiting for the lock
+        if !session.client.is_closed() && self.is_connected() {
+            return Ok(session.clone());
+        }
+
+        *session = Self::connect(&self.config, self.statement_timeout).await?;
+        self.connected.store(true, Ordering::Relaxed);
+        debug!("Reconnected to Postgres");
+        Ok(session.clone())
+    }
+
+    /// ping the connection and rebuild the session if it does not answer;
+    /// returns the connection state after the check
+    pub async fn check_health(&self) -> bool {
+        let session = self.session.read().await.clone();
+        let ping = tokio::time::timeout(
+            HEALTH_CHECK_PING_TIMEOUT,
+            session.client.simple_query("SELECT 1"),
+        )
+        .await;
+
+        if !matches!(ping, Ok(Ok(_))) {
+            warn!("Postgres health check failed - reconnecting");
+            self.connected.store(false, Ordering::Relaxed);
+            if let Err(err) = self.reconnect().await {
+                warn!("Reconnecting to Postgres failed: {err:?}");
+            }
+        }
+
+        self.is_connected()
+    }
+
+    // the task holds a weak reference only and stops once the last clone of the cache is dropped
+    fn spawn_health_check(&self, health_check_interval: Duration) {
+        let session = Arc::downgrade(&self.session);
+        let config = self.config.clone();
+        let statement_timeout = self.statement_timeout;
+        let connected = self.connected.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(health_check_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // first tick completes immediately
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(session) = session.upgrade() else {
+                    debug!("Postgres session dropped - stopping health check");
+                    return;
+                };
+                let session_cache = PostgresSessionCache {
+                    session,
+                    config: config.clone(),
+                    statement_timeout,
+                    connected: connected.clone(),
+                };
+                session_cache.check_health().await;
+            }
+        });
+    }
 }
 
 #[derive(Clone)]
@@ -331,3 +658,94 @@ fn value_vec_test_types() {
     let values = PostgresSession::values_vec(3, &["text", "int", "int"]);
     assert_eq!(values, "(($1)::text,($2)::int,($3)::int)");
 }
+
+#[tokio::test]
+async fn prepared_statement_cache_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let cache = PreparedStatementCache::<usize>::new();
+    let prepare_count = &AtomicUsize::new(0);
+    let counting_prepare =
+        move || async move { Ok::<usize, ()>(prepare_count.fetch_add(1, Ordering::SeqCst)) };
+
+    let first = cache
+        .get_or_prepare("SELECT 1", counting_prepare)
+        .await
+        .unwrap();
+    let second = cache
+        .get_or_prepare("SELECT 1", counting_prepare)
+        .await
+        .unwrap();
+    assert_eq!(first, second);
+    assert_eq!(prepare_count.load(Ordering::SeqCst), 1);
+
+    cache
+        .get_or_prepare("SELECT 2", counting_prepare)
+        .await
+        .unwrap();
+    assert_eq!(prepare_count.load(Ordering::SeqCst), 2);
+    assert_eq!(cache.len(), 2);
+
+    cache.invalidate("SELECT 1");
+    cache
+        .get_or_prepare("SELECT 1", counting_prepare)
+        .await
+        .unwrap();
+    assert_eq!(prepare_count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+#[ignore = "need postgres database"]
+async fn session_cache_reconnects_after_connection_drop_test() {
+    let session_cache = PostgresSessionCache::new(PostgresSessionConfig::new_for_tests())
+        .await
+        .unwrap();
+    let session = session_cache.get_session().await.unwrap();
+    let backend_pid: i32 = session
+        .query_one("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()
+        .get(0);
+
+    // drop the connection from the server side like a postgres restart would
+    let admin_session = PostgresSession::new(PostgresSessionConfig::new_for_tests())
+        .await
+        .unwrap();
+    admin_session
+        .query_one("SELECT pg_terminate_backend($1)", &[&backend_pid])
+        .await
+        .unwrap();
+    assert!(session.query_one("SELECT 1", &[]).await.is_err());
+
+    assert!(session_cache.check_health().await);
+    assert!(session_cache.is_connected());
+
+    let session = session_cache.get_session().await.unwrap();
+    let new_backend_pid: i32 = session
+        .query_one("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_ne!(backend_pid, new_backend_pid);
+}
+
+#[tokio::test]
+#[ignore = "need postgres database"]
+async fn statement_timeout_test() {
+    let session = PostgresSession::new_with_statement_timeout(
+        PostgresSessionConfig::new_for_tests(),
+        Duration::from_millis(50),
+    )
+    .await
+    .unwrap();
+
+    let result = session.try_query_list("SELECT pg_sleep(2)", &[]).await;
+    assert!(
+        matches!(result, Err(PostgresQueryError::StatementTimeout(timeout)) if timeout == Duration::from_millis(50)),
+        "expected statement timeout but got {result:?}"
+    );
+
+    // session must still be usable after the timeout
+    let rows = session.try_query_list("SELECT 1", &[]).await.unwrap();
+    assert_eq!(rows.len(), 1);
+}
@@ -1,11 +1,12 @@
 use std::str::FromStr;
 
 use futures_util::pin_mut;
-use log::debug;
+use log::{debug, warn};
 use solana_lite_rpc_core::encoding::BinaryEncoding;
 use solana_lite_rpc_core::solana_utils::hash_from_str;
 use solana_lite_rpc_core::structures::epoch::EpochRef;
 use solana_lite_rpc_core::{encoding::BASE64, structures::produced_block::TransactionInfo};
+use solana_sdk::message::VersionedMessage;
 use solana_sdk::signature::Signature;
 use solana_sdk::slot_history::Slot;
 use solana_sdk::transaction::TransactionError;
@@ -14,9 +15,13 @@ use tokio_postgres::binary_copy::BinaryCopyInWriter;
 use tokio_postgres::types::Type;
 use tokio_postgres::CopyInSink;
 
+use super::postgres_config::PostgresMessageCompression;
 use super::postgres_epoch::*;
 use super::postgres_session::*;
 
+// version marker of zstd compressed messages; base64 never contains ':' so uncompressed rows cannot collide
+const COMPRESSED_MESSAGE_PREFIX: &str = "zstd1:";
+
 #[derive(Debug)]
 pub struct PostgresTransaction {
     pub signature: String,
@@ -28,10 +33,20 @@ pub struct PostgresTransaction {
     pub cu_consumed: Option<i64>,
     pub recent_blockhash: String,
     pub message: String,
+    // position in the block; None for rows written before the column was added
+    pub idx_in_block: Option<i32>,
 }
 
 impl PostgresTransaction {
     pub fn new(value: &TransactionInfo, slot: Slot) -> Self {
+        Self::new_with_compression(value, slot, &PostgresMessageCompression::default())
+    }
+
+    pub fn new_with_compression(
+        value: &TransactionInfo,
+        slot: Slot,
+        compression: &PostgresMessageCompression,
+    ) -> Self {
         Self {
             signature: value.signature.to_string(),
             err: value
@@ -43,25 +58,21 @@ impl PostgresTransaction {
             prioritization_fees: value.prioritization_fees.map(|x| x as i64),
             cu_consumed: value.cu_consumed.map(|x| x as i64),
             recent_blockhash: value.recent_blockhash.to_string(),
-            message: BinaryEncoding::Base64.encode(value.message.serialize()),
+            message: Self::encode_message(&value.message, compression),
             slot: slot as i64,
+            idx_in_block: None,
         }
     }
 
     pub fn to_transaction_info(&self) -> TransactionInfo {
         TransactionInfo {
             signature: Signature::from_str(self.signature.as_str()).unwrap(),
-            err: self
-                .err
-                .as_ref()
-                .and_then(|x| BASE64.deserialize::<TransactionError>(x).ok()),
+            err: Self::decode_err(self.err.as_deref()),
             cu_requested: self.cu_requested.map(|x| x as u32),
             prioritization_fees: self.prioritization_fees.map(|x| x as u64),
             cu_consumed: self.cu_consumed.map(|x| x as u64),
             recent_blockhash: hash_from_str(&self.recent_blockhash).expect("valid blockhash"),
-            message: BinaryEncoding::Base64
-                .deserialize(&self.message)
-                .expect("serialized message"),
+            message: Self::decode_message(&self.message).expect("serialized message"),
             // TODO readable_accounts etc.
             readable_accounts: vec![],
             writable_accounts: vec![],
@@ -70,6 +81,42 @@ impl PostgresTransaction {
         }
     }
 
+    pub fn decode_err(err: Option<&str>) -> Option<TransactionError> {
+        err.and_then(|x| BASE64.deserialize::<TransactionError>(x).ok())
+    }
+
+    fn encode_message(
+        message: &VersionedMessage,
+        compression: &PostgresMessageCompression,
+    ) -> String {
+        let message = message.serialize();
+        if compression.enabled && message.len() >= compression.min_size_bytes {
+            match zstd::bulk::compress(&message, compression.level) {
+                Ok(compressed) if compressed.len() < message.len() => {
+                    return format!(
+                        "{COMPRESSED_MESSAGE_PREFIX}{}",
+                        BinaryEncoding::Base64.encode(compressed)
+                    );
+                }
+                // incompressible - store as is
+                Ok(_) => {}
+                Err(err) => warn!("Failed to compress transaction message: {err}"),
+            }
+        }
+        BinaryEncoding::Base64.encode(message)
+    }
+
+    fn decode_message(stored: &str) -> anyhow::Result<VersionedMessage> {
+        let message = match stored.strip_prefix(COMPRESSED_MESSAGE_PREFIX) {
+            Some(compressed) => {
+                let compressed = BinaryEncoding::Base64.decode(compressed)?;
+                zstd::stream::decode_all(compressed.as_slice())?
+            }
+            None => BinaryEncoding::Base64.decode(stored)?,
+        };
+        Ok(bincode::deserialize(&message)?)
+    }
+
     pub fn build_create_table_statement(epoch: EpochRef) -> String {
         let schema = PostgresEpoch::build_schema_name(epoch);
         format!(
@@ -93,7 +140,8 @@ impl PostgresTransaction {
                     cu_consumed bigint,
                     recent_blockhash text NOT NULL,
                     err text,
-                    message text NOT NULL
+                    message text NOT NULL,
+                    idx_in_block int
                     -- model_transaction_blockdata
                 ) WITH (FILLFACTOR=90,TOAST_TUPLE_TARGET=128);
                 CREATE INDEX idx_slot ON {schema}.transaction_blockdata USING btree (slot) WITH (FILLFACTOR=90);
@@ -102,6 +150,17 @@ impl PostgresTransaction {
         )
     }
 
+    // migrates epoch schemas created before the idx_in_block column was added; existing rows get NULL
+    pub fn build_add_idx_in_block_column_statement(epoch: EpochRef) -> String {
+        let schema = PostgresEpoch::build_schema_name(epoch);
+        format!(
+            r#"
+                ALTER TABLE {schema}.transaction_blockdata ADD COLUMN IF NOT EXISTS idx_in_block int;
+            "#,
+            schema = schema
+        )
+    }
+
     // removed the foreign key as it slows down inserts
     pub fn build_foreign_key_statement(epoch: EpochRef) -> String {
         let schema = PostgresEpoch::build_schema_name(epoch);
@@ -130,7 +189,8 @@ impl PostgresTransaction {
                 cu_consumed bigint,
                 recent_blockhash text STORAGE PLAIN,
                 err text STORAGE PLAIN,
-                message text STORAGE PLAIN
+                message text STORAGE PLAIN,
+                idx_in_block int
                 -- model_transaction_blockdata
             );
             TRUNCATE transaction_raw_blockdata;
@@ -146,7 +206,8 @@ impl PostgresTransaction {
                 cu_consumed,
                 recent_blockhash,
                 err,
-                message
+                message,
+                idx_in_block
                 -- model_transaction_blockdata
             ) FROM STDIN BINARY
         "#;
@@ -162,7 +223,8 @@ impl PostgresTransaction {
                 Type::INT8,
                 Type::TEXT,
                 Type::TEXT,
-                Type::TEXT, // model_transaction_blockdata
+                Type::TEXT,
+                Type::INT4, // model_transaction_blockdata
             ],
         );
         pin_mut!(writer);
@@ -177,6 +239,7 @@ impl PostgresTransaction {
                 err,
                 recent_blockhash,
                 message,
+                idx_in_block,
                 // model_transaction_blockdata
             } = tx;
 
@@ -191,6 +254,7 @@ impl PostgresTransaction {
                     &err,
                     &recent_blockhash,
                     &message,
+                    &idx_in_block,
                     // model_transaction_blockdata
                 ])
                 .await?;
@@ -220,7 +284,18 @@ impl PostgresTransaction {
 
         let statement = format!(
             r#"
-                INSERT INTO {schema}.transaction_blockdata
+                INSERT INTO {schema}.transaction_blockdata(
+                    transaction_id,
+                    slot,
+                    cu_requested,
+                    prioritization_fees,
+                    cu_consumed,
+                    err,
+                    recent_blockhash,
+                    message,
+                    idx_in_block
+                    -- model_transaction_blockdata
+                )
                 SELECT
                     ( SELECT transaction_id FROM {schema}.transaction_ids tx_lkup WHERE tx_lkup.signature = transaction_raw_blockdata.signature ),
                     slot,
@@ -229,7 +304,8 @@ impl PostgresTransaction {
                     cu_consumed,
                     err,
                     recent_blockhash,
-                    message
+                    message,
+                    idx_in_block
                     -- model_transaction_blockdata
                 FROM transaction_raw_blockdata
         "#,
@@ -246,7 +322,17 @@ impl PostgresTransaction {
         Ok(())
     }
 
-    pub fn build_query_statement(epoch: EpochRef, slot: Slot) -> String {
+    // slot is passed as $1 so the statement can be reused for all slots of an epoch
+    pub fn build_query_statement(epoch: EpochRef) -> String {
+        Self::build_query_statement_with_idx_in_block(epoch, "idx_in_block")
+    }
+
+    // for epoch schemas without the idx_in_block column (not yet migrated by the writer)
+    pub fn build_query_statement_without_idx_in_block(epoch: EpochRef) -> String {
+        Self::build_query_statement_with_idx_in_block(epoch, "NULL::int AS idx_in_block")
+    }
+
+    fn build_query_statement_with_idx_in_block(epoch: EpochRef, idx_in_block: &str) -> String {
         format!(
             r#"
                 SELECT
@@ -256,13 +342,114 @@ impl PostgresTransaction {
                     cu_consumed,
                     err,
                     recent_blockhash,
-                    message
+                    message,
+                    {idx_in_block}
                     -- model_transaction_blockdata
                 FROM {schema}.transaction_blockdata
-                WHERE slot = {}
+                WHERE slot = $1
+                ORDER BY idx_in_block
+                LIMIT $2
             "#,
-            slot,
             schema = PostgresEpoch::build_schema_name(epoch),
+            idx_in_block = idx_in_block,
         )
     }
+
+    pub fn build_count_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT count(*) AS count
+                FROM {schema}.transaction_blockdata
+                WHERE slot = $1
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    // signatures are passed as text[] in $1
+    pub fn build_query_signature_statuses_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT
+                    tx_ids.signature,
+                    transaction_blockdata.slot,
+                    transaction_blockdata.err
+                FROM {schema}.transaction_ids tx_ids
+                INNER JOIN {schema}.transaction_blockdata USING(transaction_id)
+                WHERE tx_ids.signature = ANY($1)
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::Message;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+
+    fn create_test_tx(nb_instructions: usize) -> TransactionInfo {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let instructions = (0..nb_instructions)
+            .map(|_| system_instruction::transfer(&payer, &recipient, 42))
+            .collect::<Vec<_>>();
+        TransactionInfo {
+            signature: Signature::new_unique(),
+            is_vote: false,
+            err: None,
+            cu_requested: Some(40000),
+            prioritization_fees: Some(5000),
+            cu_consumed: Some(32000),
+            recent_blockhash: Hash::new_unique(),
+            message: VersionedMessage::Legacy(Message::new(&instructions, Some(&payer))),
+            writable_accounts: vec![],
+            readable_accounts: vec![],
+            address_lookup_tables: vec![],
+        }
+    }
+
+    fn compression(enabled: bool) -> PostgresMessageCompression {
+        PostgresMessageCompression {
+            enabled,
+            ..PostgresMessageCompression::default()
+        }
+    }
+
+    #[test]
+    fn test_compressed_message_roundtrip() {
+        let tx = create_test_tx(20);
+        let uncompressed_size = BinaryEncoding::Base64.encode(tx.message.serialize()).len();
+
+        let row = PostgresTransaction::new_with_compression(&tx, 42, &compression(true));
+        assert!(row.message.starts_with(COMPRESSED_MESSAGE_PREFIX));
+        assert!(row.message.len() < uncompressed_size);
+        assert_eq!(row.to_transaction_info().message, tx.message);
+    }
+
+    #[test]
+    fn test_uncompressed_message_roundtrip() {
+        let tx = create_test_tx(20);
+
+        // old rows / compression disabled
+        let row = PostgresTransaction::new_with_compression(&tx, 42, &compression(false));
+        assert!(!row.message.starts_with(COMPRESSED_MESSAGE_PREFIX));
+        assert_eq!(row.to_transaction_info().message, tx.message);
+
+        // below threshold
+        let small_tx = create_test_tx(1);
+        let row = PostgresTransaction::new_with_compression(
+            &small_tx,
+            42,
+            &PostgresMessageCompression {
+                min_size_bytes: 10_000,
+                ..compression(true)
+            },
+        );
+        assert!(!row.message.starts_with(COMPRESSED_MESSAGE_PREFIX));
+        assert_eq!(row.to_transaction_info().message, small_tx.message);
+    }
 }
@@ -0,0 +1,92 @@
+use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+use solana_lite_rpc_core::structures::epoch::{EpochCache, EpochRef};
+use solana_lite_rpc_core::structures::produced_block::{
+    ProducedBlock, ProducedBlockInner, TransactionInfo,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::slot_history::Slot;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::Reward;
+
+/// block without transactions and rewards; block height is the slot
+pub fn create_test_block_inner(slot: Slot) -> ProducedBlockInner {
+    ProducedBlockInner {
+        transactions: vec![],
+        leader_id: None,
+        blockhash: Hash::new_unique(),
+        block_height: slot,
+        slot,
+        parent_slot: slot - 1,
+        block_time: 1699260872,
+        previous_blockhash: Hash::new_unique(),
+        rewards: None,
+    }
+}
+
+pub fn create_test_block(slot: Slot, commitment_config: CommitmentConfig) -> ProducedBlock {
+    ProducedBlock::new(create_test_block_inner(slot), commitment_config)
+}
+
+pub fn create_test_transaction(err: Option<TransactionError>) -> TransactionInfo {
+    TransactionInfo {
+        signature: Signature::new_unique(),
+        is_vote: false,
+        err,
+        cu_requested: None,
+        prioritization_fees: None,
+        cu_consumed: None,
+        recent_blockhash: Hash::new_unique(),
+        message: VersionedMessage::Legacy(Message::new(&[], Some(&Pubkey::new_unique()))),
+        writable_accounts: vec![],
+        readable_accounts: vec![],
+        address_lookup_tables: vec![],
+    }
+}
+
+/// drops and creates the epoch schemas so that a test starts with empty epochs;
+/// the writer must use the epochs of [EpochCache::new_for_tests]
+pub async fn recreate_epoch_schemas(writer: &PostgresBlockStore, epochs: &[u64]) {
+    let epoch_cache = EpochCache::new_for_tests();
+    for epoch in epochs {
+        writer
+            .drop_epoch_schema(EpochRef::new(*epoch))
+            .await
+            .unwrap();
+        writer
+            .prepare_epoch_schema(epoch_cache.get_first_slot_in_epoch(*epoch))
+            .await
+            .unwrap();
+    }
+}
+
+/// saves the blocks with confirmed commitment
+pub async fn save_blocks(
+    writer: &PostgresBlockStore,
+    blocks: impl IntoIterator<Item = ProducedBlockInner>,
+) {
+    for block in blocks {
+        writer
+            .save_block(&ProducedBlock::new(block, CommitmentConfig::confirmed()))
+            .await
+            .unwrap();
+    }
+}
+
+/// saves blocks without transactions given as (slot, block height, rewards)
+pub async fn seed_blocks(writer: &PostgresBlockStore, blocks: &[(Slot, u64, Option<Vec<Reward>>)]) {
+    save_blocks(
+        writer,
+        blocks
+            .iter()
+            .map(|(slot, block_height, rewards)| ProducedBlockInner {
+                block_height: *block_height,
+                rewards: rewards.clone(),
+                ..create_test_block_inner(*slot)
+            }),
+    )
+    .await;
+}
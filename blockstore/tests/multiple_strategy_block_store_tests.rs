@@ -9,6 +9,7 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::reward_type::RewardType;
 use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
 use solana_transaction_status::Reward;
+use std::sync::Arc;
 
 pub fn create_test_block(slot: u64, commitment_config: CommitmentConfig) -> ProducedBlock {
     let inner = ProducedBlockInner {
@@ -42,7 +43,7 @@ async fn test_in_multiple_stategy_block_store() {
         PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
     let block_storage_query = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
     let multi_store = MultipleStrategyBlockStorage::new(
-        block_storage_query.clone(),
+        Arc::new(block_storage_query),
         None, // not supported
     );
 
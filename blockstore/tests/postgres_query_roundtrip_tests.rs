@@ -0,0 +1,165 @@
+//! round-trip tests of the SQL in [PostgresQueryBlockStore] against a real database
+//!
+//! enable with `--features postgres-integration-tests` and provide a (disposable) database
+//! via `PG_TEST_CONFIG`, e.g. `host=localhost dbname=literpc_test user=postgres password=secret sslmode=disable`;
+//! the tests are skipped if the variable is not set or the database is not reachable
+#![cfg(feature = "postgres-integration-tests")]
+
+use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_query::PostgresQueryBlockStore;
+use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+use solana_lite_rpc_blockstore::block_stores::postgres::{
+    PostgresSession, PostgresSessionConfig, LITERPC_QUERY_ROLE, LITERPC_ROLE,
+};
+use solana_lite_rpc_core::structures::epoch::{EpochCache, EpochRef};
+use solana_lite_rpc_core::structures::produced_block::{
+    ProducedBlock, ProducedBlockInner, TransactionInfo,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::env;
+
+// 1000 slots per epoch (EpochCache::new_for_tests); unlikely to collide with other test data
+const TEST_EPOCH: u64 = 55;
+
+/// None if no test database is configured or reachable
+async fn setup_test_database() -> Option<PostgresSessionConfig> {
+    let Ok(pg_config) = env::var("PG_TEST_CONFIG") else {
+        eprintln!("PG_TEST_CONFIG not set - skipping postgres integration test");
+        return None;
+    };
+    let pg_session_config = PostgresSessionConfig {
+        pg_config,
+        ..PostgresSessionConfig::new_for_tests()
+    };
+
+    let session = match PostgresSession::new(pg_session_config.clone()).await {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("test database not reachable ({err}) - skipping postgres integration test");
+            return None;
+        }
+    };
+    // roles from permissions.sql required by the epoch schema grants and the query store self-check
+    let create_roles = [LITERPC_ROLE, LITERPC_QUERY_ROLE]
+        .iter()
+        .map(|role| {
+            format!(
+                "DO $$ BEGIN CREATE ROLE {role}; EXCEPTION WHEN duplicate_object THEN NULL; END $$;"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    session
+        .execute_multiple(&create_roles)
+        .await
+        .expect("must create literpc roles");
+
+    Some(pg_session_config)
+}
+
+fn create_test_block(slot: u64, transactions: Vec<TransactionInfo>) -> ProducedBlock {
+    let inner = ProducedBlockInner {
+        transactions,
+        leader_id: Some(Pubkey::new_unique().to_string()),
+        blockhash: Hash::new_unique(),
+        block_height: slot,
+        slot,
+        parent_slot: slot - 1,
+        block_time: 1699260872,
+        previous_blockhash: Hash::new_unique(),
+        rewards: None,
+    };
+    ProducedBlock::new(inner, CommitmentConfig::confirmed())
+}
+
+fn create_test_tx(cu_consumed: u64) -> TransactionInfo {
+    TransactionInfo {
+        signature: Signature::new_unique(),
+        is_vote: false,
+        err: None,
+        cu_requested: Some(40000),
+        prioritization_fees: Some(5000),
+        cu_consumed: Some(cu_consumed),
+        recent_blockhash: Hash::new_unique(),
+        message: VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                ..MessageHeader::default()
+            },
+            account_keys: vec![Pubkey::new_unique()],
+            ..v0::Message::default()
+        }),
+        writable_accounts: vec![],
+        readable_accounts: vec![],
+        address_lookup_tables: vec![],
+    }
+}
+
+#[tokio::test]
+async fn test_block_roundtrip() {
+    let Some(pg_session_config) = setup_test_database().await else {
+        return;
+    };
+    let epoch_cache = EpochCache::new_for_tests();
+    let first_slot = TEST_EPOCH * 1000 + 100;
+    let last_slot = TEST_EPOCH * 1000 + 200;
+
+    let writer = PostgresBlockStore::new(epoch_cache.clone(), pg_session_config.clone()).await;
+    writer
+        .drop_epoch_schema(EpochRef::new(TEST_EPOCH))
+        .await
+        .unwrap();
+    writer.prepare_epoch_schema(first_slot).await.unwrap();
+
+    let block = create_test_block(
+        first_slot,
+        vec![create_test_tx(32000), create_test_tx(1000)],
+    );
+    writer.save_block(&block).await.unwrap();
+    writer
+        .save_block(&create_test_block(last_slot, vec![]))
+        .await
+        .unwrap();
+
+    let query_store = PostgresQueryBlockStore::new(epoch_cache, pg_session_config).await;
+
+    let queried = query_store.query_block(first_slot).await.unwrap();
+    assert_eq!(queried.slot, block.slot);
+    assert_eq!(queried.blockhash, block.blockhash);
+    assert_eq!(queried.previous_blockhash, block.previous_blockhash);
+    assert_eq!(queried.parent_slot, block.parent_slot);
+    assert_eq!(queried.block_height, block.block_height);
+    assert_eq!(queried.leader_id, block.leader_id);
+    assert_eq!(queried.transactions.len(), 2);
+    for expected in &block.transactions {
+        let tx = queried
+            .transactions
+            .iter()
+            .find(|tx| tx.signature == expected.signature)
+            .expect("transaction must be stored");
+        assert_eq!(tx.cu_consumed, expected.cu_consumed);
+        assert_eq!(tx.prioritization_fees, expected.prioritization_fees);
+        assert_eq!(tx.recent_blockhash, expected.recent_blockhash);
+        assert_eq!(tx.message, expected.message);
+    }
+
+    let slot_range_by_epoch = query_store.get_slot_range_by_epoch().await.unwrap();
+    assert_eq!(
+        slot_range_by_epoch.get(&EpochRef::new(TEST_EPOCH)),
+        Some(&(first_slot..=last_slot))
+    );
+    let slot_range = query_store.get_slot_range().await.unwrap().unwrap();
+    assert!(slot_range.contains(&first_slot) && slot_range.contains(&last_slot));
+
+    assert!(query_store.is_block_in_range(first_slot).await);
+    assert!(query_store.is_block_in_range(first_slot + 50).await);
+    assert!(query_store.is_block_in_range(last_slot).await);
+    // same epoch but outside the archived slots
+    assert!(!query_store.is_block_in_range(first_slot - 1).await);
+    assert!(!query_store.is_block_in_range(last_slot + 1).await);
+    // epoch not archived
+    assert!(!query_store.is_block_in_range(999_999_000).await);
+}
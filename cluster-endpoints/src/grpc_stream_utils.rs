@@ -1,54 +1,184 @@
+use futures::stream::select_all;
 use futures::{Stream, StreamExt};
 use log::{debug, info, trace, warn};
+use prometheus::{
+    opts, register_gauge_vec, register_int_counter_vec, register_int_gauge_vec, GaugeVec,
+    IntCounterVec, IntGaugeVec,
+};
+use rand::Rng;
+use solana_lite_rpc_core::structures::produced_block::{BlockReplaced, ProducedBlock};
+use solana_lite_rpc_core::types::{BlockReplacedStream, BlockStream, TransactionStream};
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::slot_history::Slot;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::future::Future;
+use std::hash::Hash;
 use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::spawn;
 use tokio::sync::broadcast::error::SendError;
 use tokio::sync::broadcast::Receiver;
-use tokio::task::AbortHandle;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_util::sync::CancellationToken;
 
-const BROADCAST_CHANNEL_WARNING_THRESHOLD: usize = 10;
+lazy_static::lazy_static! {
+    static ref BROADCAST_MESSAGES_DROPPED: IntCounterVec =
+        register_int_counter_vec!(opts!("broadcast_messages_dropped_total", "Messages dropped by plugger because broadcast channel had no receivers"), &["channel"]).unwrap();
+    static ref BROADCAST_CHANNEL_DEPTH: IntGaugeVec =
+        register_int_gauge_vec!(opts!("broadcast_channel_depth", "Messages in broadcast channel fed by plugger"), &["channel"]).unwrap();
+    static ref BLOCK_STREAM_LATEST_SLOT: IntGaugeVec =
+        register_int_gauge_vec!(opts!("block_stream_latest_slot", "Slot of the latest block received from the block stream"), &["channel"]).unwrap();
+    static ref BLOCK_STREAM_LAG_SECONDS: IntGaugeVec =
+        register_int_gauge_vec!(opts!("block_stream_lag_seconds", "Wall clock time minus block_time of the latest block received from the block stream"), &["channel"]).unwrap();
+    static ref BLOCK_STREAM_BLOCKS_PER_SECOND: GaugeVec =
+        register_gauge_vec!(opts!("block_stream_blocks_per_second", "Blocks received from the block stream per second"), &["channel"]).unwrap();
+    static ref BLOCK_STREAM_BLOCKS_REPLACED: IntCounterVec =
+        register_int_counter_vec!(opts!("block_stream_blocks_replaced_total", "Blocks received for an already seen slot with a different blockhash"), &["channel"]).unwrap();
+}
+
+// warn if broadcast channel is filled above this percentage of its capacity
+const DEFAULT_WARN_THRESHOLD_PERCENTAGE: usize = 80;
+
+// number of recently seen message keys to remember for deduplication of merged streams
+const DEDUP_RECENT_KEYS_CAPACITY: usize = 1024;
+
+// number of recent slots whose blockhash is remembered to detect replaced blocks
+const REPLACED_BLOCKS_RECENT_SLOTS: usize = 512;
+
+// backoff between reconnects doubles on consecutive failures up to this limit
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+// how often a plugger in backpressure mode checks if the receivers caught up
+const BACKPRESSURE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// collects the abort handles of channelizer and plugger tasks so that they can be shut down together
+///
+/// clones share the same set of tasks
+#[derive(Clone, Default)]
+pub struct TaskGroup {
+    handles: Arc<Mutex<Vec<AbortHandle>>>,
+}
+
+impl TaskGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, abort_handle: AbortHandle) {
+        self.handles.lock().unwrap().push(abort_handle);
+    }
+
+    /// number of registered tasks that have not finished yet; forgets about finished tasks
+    pub fn live_tasks(&self) -> usize {
+        let mut handles = self.handles.lock().unwrap();
+        handles.retain(|handle| !handle.is_finished());
+        handles.len()
+    }
+
+    /// hard kill of all registered tasks; the group is empty afterwards
+    pub fn abort_all(&self) {
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap());
+        debug!("aborting {} tasks of task group", handles.len());
+        for handle in handles {
+            handle.abort();
+        }
+    }
+}
+
+/// how a plugger reacts to a broadcast channel filled above the warn threshold
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PluggerBackpressure {
+    /// keep pushing into the broadcast channel; slow receivers lag and lose the oldest messages
+    #[default]
+    PushThrough,
+    /// stop draining the upstream mpsc until all receivers caught up;
+    /// the bounded mpsc buffer fills up and blocks the upstream sender
+    Propagate,
+}
 
-/// note: backpressure will NOT get propagated to upstream but pushed down into broadcast channel
+/// called by a plugger with each message it dropped because the broadcast channel had no receivers,
+/// e.g. to log, count or persist the dropped messages
+pub type PluggerDropCallback<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+/// note: with [PluggerBackpressure::PushThrough] backpressure will NOT get propagated to upstream but pushed down into broadcast channel;
+/// use [PluggerBackpressure::Propagate] for sources where dropping messages is unacceptable
 /// service will shut down if upstream gets closed
 /// service will NOT shut down if downstream has no receivers
 ///
-/// use `debug_label` to identify the plugger in
+/// use `debug_label` to identify the plugger in logs and metrics (label `channel`)
+/// `warn_threshold` defaults to 80% of `broadcast_channel_capacity`
+/// the plugger task gets registered in `task_group` if given
+/// `on_drop` gets called with each message dropped because a downstream had no receivers
 /// note: Clone is required
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_plugger_mpcs_to_broadcast_channels<T: Send + Clone + 'static>(
     mut upstream: tokio::sync::mpsc::Receiver<T>,
-    downstreams: Vec<tokio::sync::broadcast::Sender<T>>,
+    nb_downstreams: usize,
+    broadcast_channel_capacity: usize,
+    warn_threshold: Option<usize>,
+    backpressure: PluggerBackpressure,
     debug_label: &str,
-) {
+    task_group: Option<&TaskGroup>,
+    on_drop: Option<PluggerDropCallback<T>>,
+) -> (Vec<Receiver<T>>, JoinHandle<()>) {
     let debug_label = debug_label.to_string();
     assert!(
-        !downstreams.is_empty(),
+        nb_downstreams > 0,
         "at least one downstream must be provided"
     );
+    let warn_threshold =
+        warn_threshold.unwrap_or_else(|| default_warn_threshold(broadcast_channel_capacity));
+
+    let (downstreams, downstream_receivers): (Vec<_>, Vec<_>) = (0..nb_downstreams)
+        .map(|_| tokio::sync::broadcast::channel::<T>(broadcast_channel_capacity))
+        .unzip();
+
+    let channel_labels = (0..downstreams.len())
+        .map(|idx| format!("{debug_label}-downstream-{idx}"))
+        .collect::<Vec<_>>();
 
     // abort plugger task by closing the sender
-    let _jh_task = spawn(async move {
+    let jh_plugger = spawn(async move {
         'main_loop: loop {
+            if backpressure == PluggerBackpressure::Propagate {
+                for downstream in &downstreams {
+                    wait_below_threshold(downstream, warn_threshold).await;
+                }
+            }
             match upstream.recv().await {
                 Some(msg) => {
                     for (idx, downstream) in downstreams.iter().enumerate() {
+                        let channel_label = channel_labels[idx].as_str();
                         match downstream.send(msg.clone()) {
                             Ok(receivers) => {
                                 trace!("sent data to {} receivers for downstream-{idx} ({debug_label})", receivers);
                             }
                             Err(send_error) => match send_error {
-                                SendError(_msg) => {
+                                SendError(msg) => {
+                                    BROADCAST_MESSAGES_DROPPED
+                                        .with_label_values(&[channel_label])
+                                        .inc();
+                                    if let Some(on_drop) = &on_drop {
+                                        on_drop(&msg);
+                                    }
                                     debug!("no active receivers for downstream-{idx} on channel {debug_label} - skipping message");
                                     continue 'main_loop;
                                 }
                             },
                         };
-                        if downstream.len() < BROADCAST_CHANNEL_WARNING_THRESHOLD {
-                            debug!(
+                        BROADCAST_CHANNEL_DEPTH
+                            .with_label_values(&[channel_label])
+                            .set(downstream.len() as i64);
+                        if is_above_warn_threshold(downstream.len(), warn_threshold) {
+                            warn!(
                                 "messages in downstream-{idx} channel {debug_label}: {}",
                                 downstream.len()
                             );
                         } else {
-                            warn!(
+                            debug!(
                                 "messages in downstream-{idx} channel {debug_label}: {}",
                                 downstream.len()
                             );
@@ -62,19 +192,33 @@ pub fn spawn_plugger_mpcs_to_broadcast_channels<T: Send + Clone + 'static>(
             }
         }
     });
+
+    register_in_task_group(task_group, &jh_plugger);
+    (downstream_receivers, jh_plugger)
 }
 
 // note: sending to one broadcast does not require Clone
 pub fn spawn_plugger_mpcs_to_broadcast_channel<T: Send + 'static>(
     mut upstream: tokio::sync::mpsc::Receiver<T>,
-    downstream: tokio::sync::broadcast::Sender<T>,
+    broadcast_channel_capacity: usize,
+    warn_threshold: Option<usize>,
+    backpressure: PluggerBackpressure,
     debug_label: &str,
-) {
+    task_group: Option<&TaskGroup>,
+    on_drop: Option<PluggerDropCallback<T>>,
+) -> (Receiver<T>, JoinHandle<()>) {
     let debug_label = debug_label.to_string();
+    let warn_threshold =
+        warn_threshold.unwrap_or_else(|| default_warn_threshold(broadcast_channel_capacity));
+    let (downstream, downstream_receiver) =
+        tokio::sync::broadcast::channel::<T>(broadcast_channel_capacity);
 
     // abort plugger task by closing the sender
-    let _jh_task = spawn(async move {
+    let jh_plugger = spawn(async move {
         'main_loop: loop {
+            if backpressure == PluggerBackpressure::Propagate {
+                wait_below_threshold(&downstream, warn_threshold).await;
+            }
             match upstream.recv().await {
                 Some(msg) => {
                     match downstream.send(msg) {
@@ -85,19 +229,28 @@ pub fn spawn_plugger_mpcs_to_broadcast_channel<T: Send + 'static>(
                             );
                         }
                         Err(send_error) => match send_error {
-                            SendError(_msg) => {
+                            SendError(msg) => {
+                                BROADCAST_MESSAGES_DROPPED
+                                    .with_label_values(&[&debug_label])
+                                    .inc();
+                                if let Some(on_drop) = &on_drop {
+                                    on_drop(&msg);
+                                }
                                 debug!("no active receivers for downstream on channel {debug_label} - skipping message");
                                 continue 'main_loop;
                             }
                         },
                     };
-                    if downstream.len() < BROADCAST_CHANNEL_WARNING_THRESHOLD {
-                        debug!(
+                    BROADCAST_CHANNEL_DEPTH
+                        .with_label_values(&[&debug_label])
+                        .set(downstream.len() as i64);
+                    if is_above_warn_threshold(downstream.len(), warn_threshold) {
+                        warn!(
                             "messages in downstream channel {debug_label}: {}",
                             downstream.len()
                         );
                     } else {
-                        warn!(
+                        debug!(
                             "messages in downstream channel {debug_label}: {}",
                             downstream.len()
                         );
@@ -110,22 +263,100 @@ pub fn spawn_plugger_mpcs_to_broadcast_channel<T: Send + 'static>(
             }
         }
     });
+
+    register_in_task_group(task_group, &jh_plugger);
+    (downstream_receiver, jh_plugger)
 }
 
+// the broadcast channel has no notification when receivers consume messages
+async fn wait_below_threshold<T>(
+    downstream: &tokio::sync::broadcast::Sender<T>,
+    warn_threshold: usize,
+) {
+    while is_above_warn_threshold(downstream.len(), warn_threshold) {
+        tokio::time::sleep(BACKPRESSURE_POLL_INTERVAL).await;
+    }
+}
+
+/// `delay` plus a random duration in 0..=jitter
+fn jittered_delay(delay: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return delay;
+    }
+    delay + rand::thread_rng().gen_range(Duration::ZERO..=jitter)
+}
+
+fn register_in_task_group<T>(task_group: Option<&TaskGroup>, join_handle: &JoinHandle<T>) {
+    if let Some(task_group) = task_group {
+        task_group.register(join_handle.abort_handle());
+    }
+}
+
+fn default_warn_threshold(broadcast_channel_capacity: usize) -> usize {
+    (broadcast_channel_capacity * DEFAULT_WARN_THRESHOLD_PERCENTAGE / 100).max(1)
+}
+
+fn is_above_warn_threshold(channel_len: usize, warn_threshold: usize) -> bool {
+    channel_len >= warn_threshold
+}
+
+/// use `cancellation_token` for graceful shutdown: the channelizer stops after the current message
+/// and closes the broadcast channel (receivers get the buffered messages and then `RecvError::Closed`);
+/// use the `AbortHandle` for hard kills only; the channelizer task gets registered in `task_group` if given
 pub fn channelize_stream<T>(
     grpc_source_stream: impl Stream<Item = T> + Send + 'static,
     broadcast_channel_capacity: usize,
+    cancellation_token: Option<CancellationToken>,
+    task_group: Option<&TaskGroup>,
 ) -> (Receiver<T>, AbortHandle)
 where
     T: Clone + Send + 'static,
 {
-    let (sender_tx, output_rx) = tokio::sync::broadcast::channel::<T>(broadcast_channel_capacity);
+    channelize_stream_with(
+        grpc_source_stream,
+        broadcast_channel_capacity,
+        Some,
+        cancellation_token,
+        task_group,
+    )
+}
+
+/// same as [channelize_stream] but only broadcasts messages for which `transform` returns `Some`
+pub fn channelize_stream_with<T, U>(
+    grpc_source_stream: impl Stream<Item = T> + Send + 'static,
+    broadcast_channel_capacity: usize,
+    mut transform: impl FnMut(T) -> Option<U> + Send + 'static,
+    cancellation_token: Option<CancellationToken>,
+    task_group: Option<&TaskGroup>,
+) -> (Receiver<U>, AbortHandle)
+where
+    T: Send + 'static,
+    U: Clone + Send + 'static,
+{
+    let (sender_tx, output_rx) = tokio::sync::broadcast::channel::<U>(broadcast_channel_capacity);
+    let cancellation_token = cancellation_token.unwrap_or_default();
+    let warn_threshold = default_warn_threshold(broadcast_channel_capacity);
 
     let jh_channelizer = spawn(async move {
         let mut source_stream = pin!(grpc_source_stream);
         'main_loop: loop {
-            match source_stream.next().await {
+            let next_msg = tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => {
+                    info!(
+                        "channelizer was cancelled - shutting down with {} messages in broadcast channel",
+                        sender_tx.len()
+                    );
+                    return;
+                }
+                next_msg = source_stream.next() => next_msg,
+            };
+            match next_msg {
                 Some(msg) => {
+                    let Some(msg) = transform(msg) else {
+                        trace!("message filtered out by transform");
+                        continue 'main_loop;
+                    };
                     match sender_tx.send(msg) {
                         Ok(receivers) => {
                             trace!("sent data to {} receivers", receivers);
@@ -137,7 +368,11 @@ where
                             }
                         },
                     };
-                    debug!("messages in broadcast channel: {}", sender_tx.len());
+                    if is_above_warn_threshold(sender_tx.len(), warn_threshold) {
+                        warn!("messages in broadcast channel: {}", sender_tx.len());
+                    } else {
+                        debug!("messages in broadcast channel: {}", sender_tx.len());
+                    }
                 }
                 None => {
                     info!("channelizer source stream was closed - aborting channelizer task");
@@ -147,5 +382,894 @@ where
         }
     });
 
+    register_in_task_group(task_group, &jh_channelizer);
+    (output_rx, jh_channelizer.abort_handle())
+}
+
+/// same as [channelize_stream] but the source stream gets rebuilt via `make_stream` when it ends or
+/// cannot be created; subscribers keep the same receiver across reconnects
+///
+/// waits `backoff` before each reconnect; doubled on consecutive failures (capped) and reset once
+/// the new stream delivered a message
+///
+/// a random delay of up to `jitter` is added to each reconnect and to the initial subscription so that
+/// many channelizers sharing an upstream do not reconnect all at once; use `Duration::ZERO` to disable
+pub fn channelize_stream_reconnecting<T, S, F, Fut>(
+    make_stream: F,
+    broadcast_channel_capacity: usize,
+    backoff: Duration,
+    jitter: Duration,
+    cancellation_token: Option<CancellationToken>,
+    task_group: Option<&TaskGroup>,
+) -> (Receiver<T>, AbortHandle)
+where
+    T: Clone + Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<S>> + Send,
+{
+    let (sender_tx, output_rx) = tokio::sync::broadcast::channel::<T>(broadcast_channel_capacity);
+    let cancellation_token = cancellation_token.unwrap_or_default();
+    let warn_threshold = default_warn_threshold(broadcast_channel_capacity);
+
+    let jh_channelizer = spawn(async move {
+        let mut current_backoff = backoff;
+        for attempt in 0u64.. {
+            let delay = if attempt == 0 {
+                jittered_delay(Duration::ZERO, jitter)
+            } else {
+                jittered_delay(current_backoff, jitter)
+            };
+            if !delay.is_zero() {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => {
+                        info!("channelizer was cancelled while reconnecting");
+                        return;
+                    }
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+            if attempt > 0 {
+                current_backoff = (current_backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                info!("channelizer reconnecting source stream (attempt {attempt})");
+            }
+
+            let source_stream = match make_stream().await {
+                Ok(source_stream) => source_stream,
+                Err(err) => {
+                    warn!("channelizer failed to create source stream: {err:?}");
+                    continue;
+                }
+            };
+            let mut source_stream = pin!(source_stream);
+
+            'main_loop: loop {
+                let next_msg = tokio::select! {
+                    biased;
+                    _ = cancellation_token.cancelled() => {
+                        info!(
+                            "channelizer was cancelled - shutting down with {} messages in broadcast channel",
+                            sender_tx.len()
+                        );
+                        return;
+                    }
+                    next_msg = source_stream.next() => next_msg,
+                };
+                match next_msg {
+                    Some(msg) => {
+                        current_backoff = backoff;
+                        match sender_tx.send(msg) {
+                            Ok(receivers) => {
+                                trace!("sent data to {} receivers", receivers);
+                            }
+                            Err(send_error) => match send_error {
+                                SendError(_msg) => {
+                                    debug!("no active receivers - skipping message");
+                                    continue 'main_loop;
+                                }
+                            },
+                        };
+                        if is_above_warn_threshold(sender_tx.len(), warn_threshold) {
+                            warn!("messages in broadcast channel: {}", sender_tx.len());
+                        } else {
+                            debug!("messages in broadcast channel: {}", sender_tx.len());
+                        }
+                    }
+                    None => {
+                        warn!("channelizer source stream was closed - reconnecting");
+                        break 'main_loop;
+                    }
+                }
+            }
+        }
+    });
+
+    register_in_task_group(task_group, &jh_channelizer);
     (output_rx, jh_channelizer.abort_handle())
 }
+
+/// forward every block unchanged while exporting the latest slot, the lag behind real-time and
+/// the throughput of the block stream (label `channel`)
+/// service will shut down if the block stream gets closed
+pub fn monitor_block_stream(
+    mut block_stream: BlockStream,
+    broadcast_channel_capacity: usize,
+    debug_label: &str,
+) -> BlockStream {
+    let debug_label = debug_label.to_string();
+    let (sender_tx, output_rx) = tokio::sync::broadcast::channel(broadcast_channel_capacity);
+
+    spawn(async move {
+        let latest_slot = BLOCK_STREAM_LATEST_SLOT.with_label_values(&[&debug_label]);
+        let lag_seconds = BLOCK_STREAM_LAG_SECONDS.with_label_values(&[&debug_label]);
+        let blocks_per_second = BLOCK_STREAM_BLOCKS_PER_SECOND.with_label_values(&[&debug_label]);
+        let mut window_started_at = Instant::now();
+        let mut blocks_in_window = 0u64;
+        loop {
+            let block = match block_stream.recv().await {
+                Ok(block) => block,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("block stream monitor {debug_label} lagged behind by {skipped} blocks");
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    info!("block stream monitor {debug_label} source was closed - aborting");
+                    return;
+                }
+            };
+
+            latest_slot.set(block.slot as i64);
+            let now_seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since_epoch| since_epoch.as_secs())
+                .unwrap_or_default();
+            lag_seconds.set(now_seconds.saturating_sub(block.block_time) as i64);
+
+            blocks_in_window += 1;
+            let window_elapsed = window_started_at.elapsed();
+            if window_elapsed >= Duration::from_secs(1) {
+                blocks_per_second.set(blocks_in_window as f64 / window_elapsed.as_secs_f64());
+                window_started_at = Instant::now();
+                blocks_in_window = 0;
+            }
+
+            if sender_tx.send(block).is_err() {
+                debug!(
+                    "no active receivers for monitored block stream {debug_label} - skipping block"
+                );
+            }
+        }
+    });
+
+    output_rx
+}
+
+/// forward every block unchanged and emit a [BlockReplaced] on the second stream if a block arrives for
+/// an already seen slot with a different blockhash, e.g. after a fork switch; only the most recent
+/// REPLACED_BLOCKS_RECENT_SLOTS slots are remembered
+/// service will shut down if the block stream gets closed
+pub fn detect_replaced_blocks(
+    mut block_stream: BlockStream,
+    broadcast_channel_capacity: usize,
+    debug_label: &str,
+) -> (BlockStream, BlockReplacedStream) {
+    let debug_label = debug_label.to_string();
+    let (block_sender, block_rx) = tokio::sync::broadcast::channel(broadcast_channel_capacity);
+    let (replaced_sender, replaced_rx) =
+        tokio::sync::broadcast::channel(broadcast_channel_capacity);
+
+    spawn(async move {
+        let blocks_replaced = BLOCK_STREAM_BLOCKS_REPLACED.with_label_values(&[&debug_label]);
+        let mut blockhashes = RecentBlockhashes::new(REPLACED_BLOCKS_RECENT_SLOTS);
+        loop {
+            let block = match block_stream.recv().await {
+                Ok(block) => block,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "replaced block detection {debug_label} lagged behind by {skipped} blocks"
+                    );
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    info!("replaced block detection {debug_label} source was closed - aborting");
+                    return;
+                }
+            };
+
+            if let Some(replaced) = blockhashes.insert(block.slot, block.blockhash) {
+                warn!(
+                    "block of slot {} replaced on {debug_label}: {} -> {}",
+                    replaced.slot, replaced.old_hash, replaced.new_hash
+                );
+                blocks_replaced.inc();
+                if replaced_sender.send(replaced).is_err() {
+                    debug!(
+                        "no active receivers for replaced blocks of {debug_label} - skipping event"
+                    );
+                }
+            }
+
+            if block_sender.send(block).is_err() {
+                debug!("no active receivers for block stream {debug_label} - skipping block");
+            }
+        }
+    });
+
+    (block_rx, replaced_rx)
+}
+
+/// slot -> blockhash of the most recent slots; the lowest slot gets evicted when full
+struct RecentBlockhashes {
+    capacity: usize,
+    blockhashes: BTreeMap<Slot, solana_sdk::hash::Hash>,
+}
+
+impl RecentBlockhashes {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        Self {
+            capacity,
+            blockhashes: BTreeMap::new(),
+        }
+    }
+
+    /// remember the blockhash of the slot; returns the replacement if the slot had a different blockhash
+    fn insert(&mut self, slot: Slot, blockhash: solana_sdk::hash::Hash) -> Option<BlockReplaced> {
+        let replaced = match self.blockhashes.insert(slot, blockhash) {
+            Some(old_hash) if old_hash != blockhash => Some(BlockReplaced {
+                slot,
+                old_hash,
+                new_hash: blockhash,
+            }),
+            _ => None,
+        };
+        if self.blockhashes.len() > self.capacity {
+            self.blockhashes.pop_first();
+        }
+        replaced
+    }
+}
+
+/// fan out each block of the block stream into its transactions
+/// service will shut down if the block stream gets closed
+pub fn channelize_transactions_from_blocks(
+    block_stream: BlockStream,
+    broadcast_channel_capacity: usize,
+    cancellation_token: Option<CancellationToken>,
+    task_group: Option<&TaskGroup>,
+) -> (TransactionStream, AbortHandle) {
+    let transactions = BroadcastStream::new(block_stream)
+        .filter_map(|block| async move {
+            match block {
+                Ok(block) => Some(futures::stream::iter(
+                    block.processed_transactions().collect::<Vec<_>>(),
+                )),
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    warn!("transaction fan-out lagged behind block stream by {skipped} blocks");
+                    None
+                }
+            }
+        })
+        .flatten();
+
+    channelize_stream(
+        transactions,
+        broadcast_channel_capacity,
+        cancellation_token,
+        task_group,
+    )
+}
+
+/// forward only the blocks at `commitment` or above (processed < confirmed < finalized)
+/// service will shut down if the block stream gets closed
+pub fn filtered_block_stream(
+    block_stream: BlockStream,
+    commitment: CommitmentConfig,
+    broadcast_channel_capacity: usize,
+) -> BlockStream {
+    let blocks = BroadcastStream::new(block_stream).filter_map(|block| async move {
+        match block {
+            Ok(block) => Some(block),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!("block commitment filter lagged behind block stream by {skipped} blocks");
+                None
+            }
+        }
+    });
+
+    let (filtered_rx, _abort_handle) = channelize_stream_with(
+        blocks,
+        broadcast_channel_capacity,
+        move |block: ProducedBlock| {
+            is_at_least_commitment(block.commitment_config, commitment).then_some(block)
+        },
+        None,
+        None,
+    );
+    filtered_rx
+}
+
+fn is_at_least_commitment(
+    block_commitment: CommitmentConfig,
+    commitment: CommitmentConfig,
+) -> bool {
+    match commitment.commitment {
+        CommitmentLevel::Finalized => block_commitment.is_finalized(),
+        CommitmentLevel::Confirmed => block_commitment.is_at_least_confirmed(),
+        _ => true,
+    }
+}
+
+/// merge multiple redundant sources (e.g. geyser subscriptions) and forward only the first copy of each message
+///
+/// messages are identified by `dedup_key`; only the last DEDUP_RECENT_KEYS_CAPACITY keys are remembered
+/// service will shut down if all sources get closed
+pub fn channelize_merged_streams<T, K, S>(
+    sources: Vec<S>,
+    dedup_key: impl Fn(&T) -> K + Send + 'static,
+    broadcast_channel_capacity: usize,
+) -> (Receiver<T>, AbortHandle)
+where
+    T: Clone + Send + 'static,
+    K: Eq + Hash + Clone + Send + 'static,
+    S: Stream<Item = T> + Send + 'static,
+{
+    assert!(!sources.is_empty(), "at least one source must be provided");
+    let (sender_tx, output_rx) = tokio::sync::broadcast::channel::<T>(broadcast_channel_capacity);
+
+    let jh_channelizer = spawn(async move {
+        let mut merged_stream = select_all(sources.into_iter().map(Box::pin));
+        let mut recent_keys = RecentKeys::new(DEDUP_RECENT_KEYS_CAPACITY);
+        'main_loop: loop {
+            match merged_stream.next().await {
+                Some(msg) => {
+                    if !recent_keys.insert(dedup_key(&msg)) {
+                        trace!("skipping duplicate message from merged streams");
+                        continue 'main_loop;
+                    }
+                    match sender_tx.send(msg) {
+                        Ok(receivers) => {
+                            trace!("sent data to {} receivers", receivers);
+                        }
+                        Err(send_error) => match send_error {
+                            SendError(_msg) => {
+                                debug!("no active receivers - skipping message");
+                                continue 'main_loop;
+                            }
+                        },
+                    };
+                    debug!("messages in broadcast channel: {}", sender_tx.len());
+                }
+                None => {
+                    info!("all merged source streams were closed - aborting channelizer task");
+                    return; // abort task
+                }
+            }
+        }
+    });
+
+    (output_rx, jh_channelizer.abort_handle())
+}
+
+/// bounded set of recently seen keys; the oldest key gets evicted when full
+struct RecentKeys<K> {
+    capacity: usize,
+    keys: HashSet<K>,
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + Hash + Clone> RecentKeys<K> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keys: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// returns false if the key was seen recently
+    fn insert(&mut self, key: K) -> bool {
+        if self.keys.contains(&key) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.keys.insert(key.clone());
+        self.order.push_back(key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_lite_rpc_core::structures::produced_block::{
+        ProducedBlock, ProducedBlockInner, TransactionInfo,
+    };
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::Signature;
+
+    #[tokio::test]
+    async fn test_merged_streams_dedup() {
+        let source_a = futures::stream::iter(vec![1, 2, 3, 4]);
+        let source_b = futures::stream::iter(vec![2, 3, 5]);
+        let (mut rx, _abort_handle) =
+            channelize_merged_streams(vec![source_a, source_b], |x: &u64| *x, 16);
+
+        let mut received = vec![];
+        while let Ok(msg) = rx.recv().await {
+            received.push(msg);
+        }
+        received.sort();
+        assert_eq!(received, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_plugger_counts_dropped_messages() {
+        let (upstream_tx, upstream_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let (downstream_rx, jh_plugger) = spawn_plugger_mpcs_to_broadcast_channel(
+            upstream_rx,
+            16,
+            None,
+            PluggerBackpressure::default(),
+            "test-dropped",
+            None,
+            None,
+        );
+        drop(downstream_rx);
+
+        upstream_tx.send(1).await.unwrap();
+        upstream_tx.send(2).await.unwrap();
+        drop(upstream_tx);
+        jh_plugger.await.unwrap();
+
+        assert_eq!(
+            BROADCAST_MESSAGES_DROPPED
+                .with_label_values(&["test-dropped"])
+                .get(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugger_reports_dropped_messages() {
+        let dropped = Arc::new(Mutex::new(vec![]));
+        let dropped_by_callback = dropped.clone();
+        let (upstream_tx, upstream_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let (mut downstream_rx, jh_plugger) = spawn_plugger_mpcs_to_broadcast_channel(
+            upstream_rx,
+            16,
+            None,
+            PluggerBackpressure::default(),
+            "test-on-drop",
+            None,
+            Some(Box::new(move |msg: &u64| {
+                dropped_by_callback.lock().unwrap().push(*msg)
+            })),
+        );
+
+        upstream_tx.send(1).await.unwrap();
+        assert_eq!(downstream_rx.recv().await.unwrap(), 1);
+
+        // no receivers left
+        drop(downstream_rx);
+        upstream_tx.send(2).await.unwrap();
+        upstream_tx.send(3).await.unwrap();
+        drop(upstream_tx);
+        jh_plugger.await.unwrap();
+
+        assert_eq!(*dropped.lock().unwrap(), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_channelize_stream_cancellation() {
+        let (source_tx, source_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let cancellation_token = CancellationToken::new();
+        let (mut rx, _abort_handle) = channelize_stream(
+            tokio_stream::wrappers::ReceiverStream::new(source_rx),
+            16,
+            Some(cancellation_token.clone()),
+            None,
+        );
+
+        source_tx.send(1).await.unwrap();
+        source_tx.send(2).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), 1);
+        assert_eq!(rx.recv().await.unwrap(), 2);
+
+        source_tx.send(3).await.unwrap();
+        // wait until the message got forwarded
+        while rx.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        cancellation_token.cancel();
+
+        // buffered message is still delivered, then the channel gets closed
+        assert_eq!(rx.len(), 1);
+        assert_eq!(rx.recv().await.unwrap(), 3);
+        assert!(matches!(
+            rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_channelize_stream_with_filter() {
+        let source = futures::stream::iter(1..=10u64);
+        let (mut rx, _abort_handle) = channelize_stream_with(
+            source,
+            16,
+            |x| if x % 2 == 0 { None } else { Some(x * 10) },
+            None,
+            None,
+        );
+
+        let mut received = vec![];
+        while let Ok(msg) = rx.recv().await {
+            received.push(msg);
+        }
+        assert_eq!(received, vec![10, 30, 50, 70, 90]);
+    }
+
+    #[tokio::test]
+    async fn test_channelize_stream_reconnecting() {
+        let nb_connects = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let make_stream = {
+            let nb_connects = nb_connects.clone();
+            move || {
+                let connect = nb_connects.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                async move {
+                    if connect == 1 {
+                        anyhow::bail!("connection refused");
+                    }
+                    // ends after 3 items
+                    Ok(futures::stream::iter(
+                        (1..=3).map(move |x| connect * 10 + x),
+                    ))
+                }
+            }
+        };
+        let cancellation_token = CancellationToken::new();
+        let (mut rx, _abort_handle) = channelize_stream_reconnecting(
+            make_stream,
+            64,
+            Duration::from_millis(10),
+            Duration::from_millis(5),
+            Some(cancellation_token.clone()),
+            None,
+        );
+
+        let mut received = vec![];
+        for _ in 0..6 {
+            received.push(rx.recv().await.unwrap());
+        }
+        cancellation_token.cancel();
+
+        // second connect failed, third one succeeded
+        assert_eq!(received, vec![1, 2, 3, 21, 22, 23]);
+        assert!(nb_connects.load(std::sync::atomic::Ordering::SeqCst) >= 3);
+    }
+
+    fn produced_block(slot: u64, nb_transactions: usize) -> ProducedBlock {
+        let transactions = (0..nb_transactions)
+            .map(|_| TransactionInfo {
+                signature: Signature::new_unique(),
+                is_vote: false,
+                err: None,
+                cu_requested: None,
+                prioritization_fees: None,
+                cu_consumed: None,
+                recent_blockhash: Hash::new_unique(),
+                message: VersionedMessage::Legacy(Message::default()),
+                writable_accounts: vec![],
+                readable_accounts: vec![],
+                address_lookup_tables: vec![],
+            })
+            .collect();
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions,
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time: 0,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+            },
+            CommitmentConfig::confirmed(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_channelize_transactions_from_blocks() {
+        let (block_tx, block_rx) = tokio::sync::broadcast::channel(16);
+        let (mut transaction_rx, _abort_handle) =
+            channelize_transactions_from_blocks(block_rx, 16, None, None);
+
+        block_tx.send(produced_block(100, 2)).unwrap();
+        block_tx.send(produced_block(101, 1)).unwrap();
+        drop(block_tx);
+
+        let mut slots = vec![];
+        while let Ok(transaction) = transaction_rx.recv().await {
+            slots.push(transaction.slot);
+        }
+        assert_eq!(slots, vec![100, 100, 101]);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_block_stream() {
+        let (block_tx, block_rx) = tokio::sync::broadcast::channel(16);
+        let mut confirmed_rx = filtered_block_stream(block_rx, CommitmentConfig::confirmed(), 16);
+
+        let block = |slot: u64, commitment: CommitmentConfig| {
+            let mut block = produced_block(slot, 0);
+            block.commitment_config = commitment;
+            block
+        };
+        block_tx
+            .send(block(100, CommitmentConfig::processed()))
+            .unwrap();
+        block_tx
+            .send(block(100, CommitmentConfig::confirmed()))
+            .unwrap();
+        block_tx
+            .send(block(101, CommitmentConfig::processed()))
+            .unwrap();
+        block_tx
+            .send(block(99, CommitmentConfig::finalized()))
+            .unwrap();
+        drop(block_tx);
+
+        let mut received = vec![];
+        while let Ok(block) = confirmed_rx.recv().await {
+            received.push((block.slot, block.commitment_config.commitment));
+        }
+        assert_eq!(
+            received,
+            vec![
+                (100, CommitmentLevel::Confirmed),
+                (99, CommitmentLevel::Finalized)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jittered_delay_within_window() {
+        let backoff = Duration::from_millis(100);
+        let jitter = Duration::from_millis(50);
+        for _ in 0..1000 {
+            let delay = jittered_delay(backoff, jitter);
+            assert!(delay >= backoff && delay <= backoff + jitter, "{delay:?}");
+        }
+        assert_eq!(jittered_delay(backoff, Duration::ZERO), backoff);
+        assert!(jittered_delay(Duration::ZERO, jitter) <= jitter);
+    }
+
+    #[test]
+    fn test_warn_threshold() {
+        assert_eq!(default_warn_threshold(100), 80);
+        assert_eq!(default_warn_threshold(1), 1);
+        assert!(!is_above_warn_threshold(79, default_warn_threshold(100)));
+        assert!(is_above_warn_threshold(80, default_warn_threshold(100)));
+        assert!(!is_above_warn_threshold(10, default_warn_threshold(1000)));
+    }
+
+    #[tokio::test]
+    async fn test_plugger_to_multiple_downstreams() {
+        let (upstream_tx, upstream_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let (mut downstream_rxs, jh_plugger) = spawn_plugger_mpcs_to_broadcast_channels(
+            upstream_rx,
+            2,
+            16,
+            Some(4),
+            PluggerBackpressure::default(),
+            "test-multi",
+            None,
+            None,
+        );
+        upstream_tx.send(42).await.unwrap();
+        drop(upstream_tx);
+        jh_plugger.await.unwrap();
+
+        for downstream_rx in downstream_rxs.iter_mut() {
+            assert_eq!(downstream_rx.recv().await.unwrap(), 42);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugger_propagates_backpressure() {
+        let (upstream_tx, upstream_rx) = tokio::sync::mpsc::channel::<u64>(2);
+        let (mut downstream_rx, _jh_plugger) = spawn_plugger_mpcs_to_broadcast_channel(
+            upstream_rx,
+            8,
+            Some(3),
+            PluggerBackpressure::Propagate,
+            "test-backpressure",
+            None,
+            None,
+        );
+
+        // 3 messages in the broadcast channel (threshold) + 2 in the mpsc buffer
+        for msg in 0..5 {
+            upstream_tx.send(msg).await.unwrap();
+        }
+        let blocked_send =
+            tokio::time::timeout(Duration::from_millis(100), upstream_tx.send(5)).await;
+        assert!(blocked_send.is_err(), "upstream sender must block");
+
+        // downstream catches up and unblocks the upstream
+        assert_eq!(downstream_rx.recv().await.unwrap(), 0);
+        tokio::time::timeout(Duration::from_secs(1), upstream_tx.send(5))
+            .await
+            .expect("upstream sender must get unblocked")
+            .unwrap();
+
+        let mut received = vec![];
+        for _ in 1..=5 {
+            received.push(downstream_rx.recv().await.unwrap());
+        }
+        assert_eq!(received, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_task_group_abort_all() {
+        let task_group = TaskGroup::new();
+        // sources stay open so that the tasks only stop when aborted
+        let (_source_tx, source_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let (mut channelized_rx, _abort_handle) = channelize_stream(
+            tokio_stream::wrappers::ReceiverStream::new(source_rx),
+            16,
+            None,
+            Some(&task_group),
+        );
+        let (_upstream_tx, upstream_rx) = tokio::sync::mpsc::channel::<u64>(16);
+        let (mut downstream_rx, jh_plugger) = spawn_plugger_mpcs_to_broadcast_channel(
+            upstream_rx,
+            16,
+            None,
+            PluggerBackpressure::default(),
+            "test-task-group",
+            Some(&task_group),
+            None,
+        );
+        let (_downstream_rxs, jh_finished_plugger) = spawn_plugger_mpcs_to_broadcast_channels(
+            tokio::sync::mpsc::channel::<u64>(16).1,
+            2,
+            16,
+            None,
+            PluggerBackpressure::default(),
+            "test-task-group-multi",
+            Some(&task_group),
+            None,
+        );
+        // upstream of this plugger is closed already, so it does not count as live
+        jh_finished_plugger.await.unwrap();
+        assert_eq!(task_group.live_tasks(), 2);
+
+        task_group.abort_all();
+        assert!(jh_plugger.await.unwrap_err().is_cancelled());
+        assert!(matches!(
+            channelized_rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        ));
+        assert!(matches!(
+            downstream_rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        ));
+        assert_eq!(task_group.live_tasks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_block_stream() {
+        let (block_tx, block_rx) = tokio::sync::broadcast::channel(16);
+        let mut monitored_rx = monitor_block_stream(block_rx, 16, "test-monitor");
+
+        let five_seconds_ago = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 5;
+        for slot in [200, 201] {
+            block_tx
+                .send(ProducedBlock::new(
+                    ProducedBlockInner {
+                        transactions: vec![],
+                        leader_id: None,
+                        blockhash: Hash::new_unique(),
+                        block_height: slot,
+                        slot,
+                        parent_slot: slot - 1,
+                        block_time: five_seconds_ago,
+                        previous_blockhash: Hash::new_unique(),
+                        rewards: None,
+                    },
+                    CommitmentConfig::confirmed(),
+                ))
+                .unwrap();
+        }
+
+        assert_eq!(monitored_rx.recv().await.unwrap().slot, 200);
+        assert_eq!(monitored_rx.recv().await.unwrap().slot, 201);
+        assert_eq!(
+            BLOCK_STREAM_LATEST_SLOT
+                .with_label_values(&["test-monitor"])
+                .get(),
+            201
+        );
+        let lag_seconds = BLOCK_STREAM_LAG_SECONDS
+            .with_label_values(&["test-monitor"])
+            .get();
+        assert!((5..=6).contains(&lag_seconds), "lag {lag_seconds}s");
+    }
+
+    #[tokio::test]
+    async fn test_detect_replaced_blocks() {
+        let (block_tx, block_rx) = tokio::sync::broadcast::channel(16);
+        let (mut forwarded_rx, mut replaced_rx) = detect_replaced_blocks(block_rx, 16, "test-fork");
+
+        let original = produced_block(300, 0);
+        // same block again at a higher commitment level is not a replacement
+        let mut finalized = original.clone();
+        finalized.commitment_config = CommitmentConfig::finalized();
+        let fork = produced_block(300, 1);
+        for block in [
+            original.clone(),
+            finalized,
+            produced_block(301, 0),
+            fork.clone(),
+        ] {
+            block_tx.send(block).unwrap();
+        }
+        drop(block_tx);
+
+        let forwarded_slots = [
+            forwarded_rx.recv().await.unwrap().slot,
+            forwarded_rx.recv().await.unwrap().slot,
+            forwarded_rx.recv().await.unwrap().slot,
+            forwarded_rx.recv().await.unwrap().slot,
+        ];
+        assert_eq!(forwarded_slots, [300, 300, 301, 300]);
+
+        assert_eq!(
+            replaced_rx.recv().await.unwrap(),
+            BlockReplaced {
+                slot: 300,
+                old_hash: original.blockhash,
+                new_hash: fork.blockhash,
+            }
+        );
+        assert!(matches!(
+            replaced_rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Closed)
+        ));
+    }
+
+    #[test]
+    fn test_recent_blockhashes_eviction() {
+        let mut blockhashes = RecentBlockhashes::new(2);
+        assert_eq!(blockhashes.insert(10, Hash::new_unique()), None);
+        assert_eq!(blockhashes.insert(11, Hash::new_unique()), None);
+        assert_eq!(blockhashes.insert(12, Hash::new_unique()), None);
+        // 10 got evicted and is not reported as replaced
+        assert_eq!(blockhashes.insert(10, Hash::new_unique()), None);
+        assert!(blockhashes.insert(12, Hash::new_unique()).is_some());
+    }
+
+    #[test]
+    fn test_recent_keys_eviction() {
+        let mut recent_keys = RecentKeys::new(2);
+        assert!(recent_keys.insert(1));
+        assert!(!recent_keys.insert(1));
+        assert!(recent_keys.insert(2));
+        assert!(recent_keys.insert(3));
+        // 1 got evicted
+        assert!(recent_keys.insert(1));
+    }
+}
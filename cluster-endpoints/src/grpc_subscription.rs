@@ -258,7 +258,8 @@ pub fn create_grpc_subscription(
         create_grpc_multiplex_blocks_subscription(grpc_sources.clone());
 
     let cluster_info_polling = poll_cluster_info(rpc_client.clone(), cluster_info_sx);
-    let vote_accounts_polling = poll_vote_accounts(rpc_client.clone(), va_sx);
+    let vote_accounts_polling =
+        poll_vote_accounts(rpc_client.clone(), va_sx, rpc_client.commitment());
     // accounts
     if !accounts_filter.is_empty() {
         let (account_sender, accounts_stream) =
@@ -36,7 +36,8 @@ pub fn create_json_rpc_polling_subscription(
     let cluster_info_polling = poll_cluster_info(rpc_client.clone(), cluster_info_sx);
     endpoint_tasks.push(cluster_info_polling);
 
-    let vote_accounts_polling = poll_vote_accounts(rpc_client.clone(), va_sx);
+    let vote_accounts_polling =
+        poll_vote_accounts(rpc_client.clone(), va_sx, rpc_client.commitment());
     endpoint_tasks.push(vote_accounts_polling);
 
     let streamers = EndpointStreaming {
@@ -1,7 +1,9 @@
 use log::{debug, warn};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_lite_rpc_core::structures::identity_stakes::VoteAccountsSnapshot;
 use solana_lite_rpc_core::AnyhowJoinHandle;
-use solana_rpc_client_api::response::{RpcContactInfo, RpcVoteAccountStatus};
+use solana_rpc_client_api::response::RpcContactInfo;
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::broadcast::Sender;
 
@@ -31,18 +33,28 @@ pub fn poll_cluster_info(
 
 pub fn poll_vote_accounts(
     rpc_client: Arc<RpcClient>,
-    vote_account_sender: Sender<RpcVoteAccountStatus>,
+    vote_account_sender: Sender<VoteAccountsSnapshot>,
+    commitment_config: CommitmentConfig,
 ) -> AnyhowJoinHandle {
     // task MUST not terminate but might be aborted from outside
     tokio::spawn(async move {
         loop {
-            match rpc_client.get_vote_accounts().await {
-                Ok(vote_accounts) => {
+            // getVoteAccounts has no context - take the slot right before as a lower bound
+            let vote_accounts = match rpc_client.get_slot_with_commitment(commitment_config).await {
+                Ok(slot) => rpc_client
+                    .get_vote_accounts_with_commitment(commitment_config)
+                    .await
+                    .map(|vote_accounts| VoteAccountsSnapshot::new(slot, vote_accounts)),
+                Err(error) => Err(error),
+            };
+            match vote_accounts {
+                Ok(snapshot) => {
                     debug!(
-                        "get vote_accounts from rpc: {:?}",
-                        vote_accounts.current.len()
+                        "get vote_accounts from rpc at slot {}: {:?}",
+                        snapshot.slot,
+                        snapshot.vote_accounts.current.len()
                     );
-                    if let Err(e) = vote_account_sender.send(vote_accounts) {
+                    if let Err(e) = vote_account_sender.send(snapshot) {
                         warn!("rpc_vote_accounts channel has no receivers {e:?}");
                     }
                     tokio::time::sleep(Duration::from_secs(600)).await;
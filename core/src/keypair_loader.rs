@@ -1,24 +1,103 @@
-use anyhow::Context;
+use anyhow::{bail, Context};
 use solana_sdk::signature::Keypair;
 use std::env;
+use std::path::Path;
 
+/// load identity from IDENTITY env variable (takes precedence) or the provided keypair config;
+/// see [parse_identity_keypair] for the supported formats
 pub async fn load_identity_keypair(
-    identity_keyfile_path: Option<String>,
+    identity_keypair: Option<String>,
 ) -> anyhow::Result<Option<Keypair>> {
-    let identity_jsonarray_str = if let Ok(identity_env_var) = env::var("IDENTITY") {
-        identity_env_var
-    } else if let Some(identity_path) = identity_keyfile_path {
-        tokio::fs::read_to_string(identity_path)
-            .await
-            .context("Cannot find the identity file provided")?
-    } else {
-        return Ok(None);
-    };
-
-    let identity_bytes: Vec<u8> = serde_json::from_str(&identity_jsonarray_str)
-        .context("Invalid identity format expected Vec<u8>")?;
-
-    Ok(Some(
-        Keypair::from_bytes(identity_bytes.as_slice()).context("Invalid identity")?,
-    ))
+    resolve_identity_keypair(identity_keypair.as_deref())
+}
+
+/// same as [load_identity_keypair] but without async file access
+pub fn resolve_identity_keypair(identity_keypair: Option<&str>) -> anyhow::Result<Option<Keypair>> {
+    if let Ok(identity_env_var) = env::var("IDENTITY") {
+        return parse_identity_keypair(&identity_env_var)
+            .context("Invalid identity in IDENTITY env variable")
+            .map(Some);
+    }
+
+    match identity_keypair {
+        Some(identity_keypair) => parse_identity_keypair(identity_keypair).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// parse keypair from one of:
+/// * literal JSON byte array (as in Solana CLI keypair files)
+/// * path to a Solana CLI JSON keypair file
+/// * base58 encoded secret key (64 bytes)
+pub fn parse_identity_keypair(input: &str) -> anyhow::Result<Keypair> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Identity keypair is empty");
+    }
+
+    if input.starts_with('[') {
+        return keypair_from_json_array(input);
+    }
+
+    if Path::new(input).is_file() {
+        let content = std::fs::read_to_string(input)
+            .with_context(|| format!("Cannot read identity file {input}"))?;
+        return keypair_from_json_array(content.trim())
+            .with_context(|| format!("Invalid identity file {input}"));
+    }
+
+    let identity_bytes = bs58::decode(input).into_vec().context(
+        "Identity is neither a JSON byte array, an existing keypair file nor a base58 secret key",
+    )?;
+    Keypair::from_bytes(&identity_bytes).context("Invalid base58 identity secret key")
+}
+
+fn keypair_from_json_array(json_array: &str) -> anyhow::Result<Keypair> {
+    let identity_bytes: Vec<u8> =
+        serde_json::from_str(json_array).context("Invalid identity format expected Vec<u8>")?;
+    Keypair::from_bytes(identity_bytes.as_slice()).context("Invalid identity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn test_parse_json_array() {
+        let keypair = Keypair::new();
+        let json_array = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        let parsed = parse_identity_keypair(&json_array).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_parse_base58() {
+        let keypair = Keypair::new();
+        let parsed = parse_identity_keypair(&keypair.to_base58_string()).unwrap();
+        assert_eq!(parsed.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_parse_keypair_file() {
+        let keypair = Keypair::new();
+        let path = env::temp_dir().join(format!("identity-{}.json", keypair.pubkey()));
+        std::fs::write(
+            &path,
+            serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap(),
+        )
+        .unwrap();
+
+        let parsed = parse_identity_keypair(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parsed.unwrap().pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_identity_keypair("").is_err());
+        assert!(parse_identity_keypair("[1, 2, 3]").is_err());
+        assert!(parse_identity_keypair("/does/not/exist.json").is_err());
+        assert!(parse_identity_keypair("not-base58-0OIl").is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use crate::commitment_utils::Commitment;
-use crate::{structures::produced_block::TransactionInfo, types::SubscptionHanderSink};
+use crate::{structures::produced_block::TransactionInfo, types::SubscriptionHandlerSink};
 use dashmap::DashMap;
 use solana_client::rpc_response::{ProcessedSignatureResult, RpcSignatureResult};
 use solana_sdk::signature::Signature;
@@ -10,7 +10,7 @@ use tokio::time::Instant;
 #[derive(Clone, Default)]
 pub struct SubscriptionStore {
     pub signature_subscribers:
-        Arc<DashMap<(Signature, Commitment), (SubscptionHanderSink, Instant)>>,
+        Arc<DashMap<(Signature, Commitment), (SubscriptionHandlerSink, Instant)>>,
 }
 
 impl SubscriptionStore {
@@ -18,7 +18,7 @@ impl SubscriptionStore {
         &self,
         signature: Signature,
         commitment_config: CommitmentConfig,
-        sink: SubscptionHanderSink,
+        sink: SubscriptionHandlerSink,
     ) {
         self.signature_subscribers.insert(
             (signature, Commitment::from(commitment_config)),
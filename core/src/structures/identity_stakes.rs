@@ -1,11 +1,22 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::bail;
 use itertools::Itertools;
-use solana_rpc_client_api::response::RpcVoteAccountStatus;
+use solana_rpc_client_api::response::{RpcVoteAccountInfo, RpcVoteAccountStatus};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::slot_history::Slot;
 use solana_streamer::nonblocking::quic::ConnectionPeerType;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
 
+use crate::types::VoteAccountStream;
+use crate::AnyhowJoinHandle;
+
 #[derive(Debug, Copy, Clone)]
 pub struct IdentityStakesData {
     pub peer_type: ConnectionPeerType,
@@ -13,6 +24,10 @@ pub struct IdentityStakesData {
     pub total_stakes: u64,
     pub min_stakes: u64,
     pub max_stakes: u64,
+    // identity has stake only in delinquent vote accounts
+    pub is_delinquent: bool,
+    // fraction of total stake held by identities with less or equal stake (0.0 for unstaked)
+    pub stake_percentile: f64,
 }
 
 impl Default for IdentityStakesData {
@@ -23,46 +38,97 @@ impl Default for IdentityStakesData {
             total_stakes: 0,
             max_stakes: 0,
             min_stakes: 0,
+            is_delinquent: false,
+            stake_percentile: 0.0,
         }
     }
 }
 
+// vote accounts are polled every 10min - allow a few missed polls before rejecting the stakes
+pub const DEFAULT_MAX_VOTE_ACCOUNTS_SNAPSHOT_AGE: Duration = Duration::from_secs(30 * 60);
+
+/// vote accounts as seen by the cluster at `slot`, sent through [VoteAccountStream]
+#[derive(Debug, Clone)]
+pub struct VoteAccountsSnapshot {
+    pub slot: Slot,
+    pub received_at: Instant,
+    pub vote_accounts: RpcVoteAccountStatus,
+}
+
+impl VoteAccountsSnapshot {
+    pub fn new(slot: Slot, vote_accounts: RpcVoteAccountStatus) -> Self {
+        Self {
+            slot,
+            received_at: Instant::now(),
+            vote_accounts,
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+}
+
+#[derive(Debug, Default)]
+struct IdentityStakesState {
+    data: IdentityStakesData,
+    // slot and receive time of the snapshot the data was taken from; None until the first update
+    updated_from: Option<(Slot, Instant)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct IdentityStakes {
     identity: Pubkey,
-    stakes_data: Arc<RwLock<IdentityStakesData>>,
+    max_snapshot_age: Duration,
+    stakes_data: Arc<RwLock<IdentityStakesState>>,
 }
 
 impl IdentityStakes {
     pub fn new(identity: Pubkey) -> Self {
+        Self::new_with_max_snapshot_age(identity, DEFAULT_MAX_VOTE_ACCOUNTS_SNAPSHOT_AGE)
+    }
+
+    pub fn new_with_max_snapshot_age(identity: Pubkey, max_snapshot_age: Duration) -> Self {
         Self {
             identity,
-            stakes_data: Arc::new(RwLock::new(IdentityStakesData::default())),
+            max_snapshot_age,
+            stakes_data: Arc::new(RwLock::new(IdentityStakesState::default())),
         }
     }
 
-    pub async fn get_stakes(&self) -> IdentityStakesData {
-        *self.stakes_data.read().await
+    /// fails if the stakes were taken from a vote account snapshot older than the staleness bound;
+    /// before the first snapshot the identity is considered unstaked
+    pub async fn get_stakes(&self) -> anyhow::Result<IdentityStakesData> {
+        let state = self.stakes_data.read().await;
+        check_not_stale(&self.identity, state.updated_from, self.max_snapshot_age)?;
+        Ok(state.data)
     }
 
+    /// update from vote accounts which were just fetched (e.g. bootstrap)
     pub async fn update_stakes_for_identity(&self, vote_accounts: RpcVoteAccountStatus) {
-        let map_of_stakes: HashMap<String, u64> = vote_accounts
-            .current
-            .iter()
-            .chain(vote_accounts.delinquent.iter())
-            .map(|x| (x.node_pubkey.clone(), x.activated_stake))
-            .collect();
-
-        if let Some(stakes) = map_of_stakes.get(&self.identity.to_string()) {
-            let only_stakes = map_of_stakes.iter().map(|x| *x.1).collect_vec();
-            let identity_stakes = IdentityStakesData {
-                peer_type: ConnectionPeerType::Staked(*stakes), // Staked -> Staked(*stakes) .. not sure if that is correct
-                stakes: *stakes,
-                min_stakes: only_stakes.iter().min().map_or(0, |x| *x),
-                max_stakes: only_stakes.iter().max().map_or(0, |x| *x),
-                total_stakes: only_stakes.iter().sum(),
-            };
+        self.update_stakes(&vote_accounts, 0, Instant::now()).await;
+    }
+
+    /// snapshots older than the staleness bound are rejected and the previous stakes are kept
+    pub async fn update_stakes_from_snapshot(
+        &self,
+        snapshot: &VoteAccountsSnapshot,
+    ) -> anyhow::Result<()> {
+        check_snapshot_age(snapshot, self.max_snapshot_age)?;
+        self.update_stakes(&snapshot.vote_accounts, snapshot.slot, snapshot.received_at)
+            .await;
+        Ok(())
+    }
+
+    async fn update_stakes(
+        &self,
+        vote_accounts: &RpcVoteAccountStatus,
+        slot: Slot,
+        received_at: Instant,
+    ) {
+        let stakes_snapshot = StakesSnapshot::from_vote_accounts(vote_accounts);
 
+        if let Some(identity_stakes) = stakes_snapshot.get(&self.identity) {
             log::info!(
                 "Identity stakes {}, {}, {}, {}",
                 identity_stakes.total_stakes,
@@ -70,7 +136,442 @@ impl IdentityStakes {
                 identity_stakes.max_stakes,
                 identity_stakes.stakes
             );
-            *self.stakes_data.write().await = identity_stakes;
+            *self.stakes_data.write().await = IdentityStakesState {
+                data: identity_stakes,
+                updated_from: Some((slot, received_at)),
+            };
         }
     }
 }
+
+fn check_snapshot_age(
+    snapshot: &VoteAccountsSnapshot,
+    max_snapshot_age: Duration,
+) -> anyhow::Result<()> {
+    let age = snapshot.age();
+    if age > max_snapshot_age {
+        bail!(
+            "rejecting vote accounts snapshot from slot {}: {:?} old (max {:?})",
+            snapshot.slot,
+            age,
+            max_snapshot_age
+        );
+    }
+    Ok(())
+}
+
+fn check_not_stale(
+    identity: &Pubkey,
+    updated_from: Option<(Slot, Instant)>,
+    max_snapshot_age: Duration,
+) -> anyhow::Result<()> {
+    if let Some((slot, received_at)) = updated_from {
+        let age = received_at.elapsed();
+        if age > max_snapshot_age {
+            bail!(
+                "stakes of identity {} are stale: vote accounts from slot {} are {:?} old (max {:?})",
+                identity,
+                slot,
+                age,
+                max_snapshot_age
+            );
+        }
+    }
+    Ok(())
+}
+
+/// stakes of all vote accounts (current and delinquent) with precomputed aggregates;
+/// delinquency is usually transient so delinquent identities keep their stake
+#[derive(Debug, Default)]
+struct StakesSnapshot {
+    stakes: HashMap<Pubkey, u64>,
+    // identities found only in the delinquent set
+    delinquent: HashSet<Pubkey>,
+    total_stakes: u64,
+    min_stakes: u64,
+    max_stakes: u64,
+    // stakes sorted ascending with cumulative sums for percentile lookups
+    sorted_stakes: Vec<u64>,
+    cumulative_stakes: Vec<u64>,
+}
+
+impl StakesSnapshot {
+    fn from_vote_accounts(vote_accounts: &RpcVoteAccountStatus) -> Self {
+        let parse_node_stakes = |vote_account: &RpcVoteAccountInfo| {
+            Pubkey::from_str(&vote_account.node_pubkey)
+                .ok()
+                .map(|node_pubkey| (node_pubkey, vote_account.activated_stake))
+        };
+
+        // an identity may have multiple vote accounts
+        let mut stakes: HashMap<Pubkey, u64> = HashMap::new();
+        for (node_pubkey, activated_stake) in
+            vote_accounts.current.iter().filter_map(parse_node_stakes)
+        {
+            *stakes.entry(node_pubkey).or_default() += activated_stake;
+        }
+
+        let mut delinquent = HashSet::new();
+        for (node_pubkey, activated_stake) in vote_accounts
+            .delinquent
+            .iter()
+            .filter_map(parse_node_stakes)
+        {
+            if !stakes.contains_key(&node_pubkey) || delinquent.contains(&node_pubkey) {
+                delinquent.insert(node_pubkey);
+            }
+            *stakes.entry(node_pubkey).or_default() += activated_stake;
+        }
+
+        let sorted_stakes = stakes.values().copied().sorted().collect_vec();
+        let cumulative_stakes = cumulative_sums(&sorted_stakes);
+        Self {
+            total_stakes: cumulative_stakes.last().copied().unwrap_or_default(),
+            min_stakes: sorted_stakes.first().copied().unwrap_or_default(),
+            max_stakes: sorted_stakes.last().copied().unwrap_or_default(),
+            stakes,
+            delinquent,
+            sorted_stakes,
+            cumulative_stakes,
+        }
+    }
+
+    fn get(&self, identity: &Pubkey) -> Option<IdentityStakesData> {
+        self.stakes.get(identity).map(|stakes| IdentityStakesData {
+            peer_type: ConnectionPeerType::Staked(*stakes), // Staked -> Staked(*stakes) .. not sure if that is correct
+            stakes: *stakes,
+            min_stakes: self.min_stakes,
+            max_stakes: self.max_stakes,
+            total_stakes: self.total_stakes,
+            is_delinquent: self.delinquent.contains(identity),
+            stake_percentile: stake_percentile_sorted(
+                *stakes,
+                &self.sorted_stakes,
+                &self.cumulative_stakes,
+            ),
+        })
+    }
+}
+
+/// percentile rank of an identity in the stake distribution:
+/// fraction of the total stake held by identities with less or equal stake than `identity_stakes`
+pub fn stake_percentile(identity_stakes: u64, all_stakes: &[u64]) -> f64 {
+    let sorted_stakes = all_stakes.iter().copied().sorted().collect_vec();
+    let cumulative_stakes = cumulative_sums(&sorted_stakes);
+    stake_percentile_sorted(identity_stakes, &sorted_stakes, &cumulative_stakes)
+}
+
+fn cumulative_sums(sorted_stakes: &[u64]) -> Vec<u64> {
+    sorted_stakes
+        .iter()
+        .scan(0u64, |sum, stake| {
+            *sum += stake;
+            Some(*sum)
+        })
+        .collect_vec()
+}
+
+fn stake_percentile_sorted(
+    identity_stakes: u64,
+    sorted_stakes: &[u64],
+    cumulative_stakes: &[u64],
+) -> f64 {
+    let total_stakes = cumulative_stakes.last().copied().unwrap_or_default();
+    if identity_stakes == 0 || total_stakes == 0 {
+        return 0.0;
+    }
+    let nb_less_or_equal = sorted_stakes.partition_point(|stake| *stake <= identity_stakes);
+    if nb_less_or_equal == 0 {
+        return 0.0;
+    }
+    cumulative_stakes[nb_less_or_equal - 1] as f64 / total_stakes as f64
+}
+
+#[derive(Debug, Default)]
+struct StakeCacheState {
+    snapshot: StakesSnapshot,
+    // slot and receive time of the vote accounts snapshot; None until the first update
+    updated_from: Option<(Slot, Instant)>,
+}
+
+/// stakes of all identities, refreshed on each vote account snapshot;
+/// use this instead of [IdentityStakes] when querying stakes for many identities
+#[derive(Debug, Clone)]
+pub struct StakeCache {
+    max_snapshot_age: Duration,
+    state: Arc<std::sync::RwLock<StakeCacheState>>,
+}
+
+impl Default for StakeCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StakeCache {
+    pub fn new() -> Self {
+        Self::new_with_max_snapshot_age(DEFAULT_MAX_VOTE_ACCOUNTS_SNAPSHOT_AGE)
+    }
+
+    pub fn new_with_max_snapshot_age(max_snapshot_age: Duration) -> Self {
+        Self {
+            max_snapshot_age,
+            state: Arc::new(std::sync::RwLock::new(StakeCacheState::default())),
+        }
+    }
+
+    /// update from vote accounts which were just fetched
+    pub fn update(&self, vote_accounts: &RpcVoteAccountStatus) {
+        self.update_stakes(vote_accounts, 0, Instant::now());
+    }
+
+    /// snapshots older than the staleness bound are rejected and the previous stakes are kept
+    pub fn update_from_snapshot(&self, snapshot: &VoteAccountsSnapshot) -> anyhow::Result<()> {
+        check_snapshot_age(snapshot, self.max_snapshot_age)?;
+        self.update_stakes(&snapshot.vote_accounts, snapshot.slot, snapshot.received_at);
+        Ok(())
+    }
+
+    fn update_stakes(
+        &self,
+        vote_accounts: &RpcVoteAccountStatus,
+        slot: Slot,
+        received_at: Instant,
+    ) {
+        let stakes_snapshot = StakesSnapshot::from_vote_accounts(vote_accounts);
+        *self.state.write().expect("stake cache lock poisoned") = StakeCacheState {
+            snapshot: stakes_snapshot,
+            updated_from: Some((slot, received_at)),
+        };
+    }
+
+    /// identities without stake are returned as unstaked; does not check the staleness of the stakes
+    pub fn get(&self, identity: &Pubkey) -> IdentityStakesData {
+        let state = self.state.read().expect("stake cache lock poisoned");
+        Self::stakes_from_snapshot(&state.snapshot, identity)
+    }
+
+    /// like [Self::get] but fails if the stakes were taken from a vote account snapshot older than
+    /// the staleness bound, same as [IdentityStakes::get_stakes]
+    pub fn get_stakes(&self, identity: &Pubkey) -> anyhow::Result<IdentityStakesData> {
+        let state = self.state.read().expect("stake cache lock poisoned");
+        check_not_stale(identity, state.updated_from, self.max_snapshot_age)?;
+        Ok(Self::stakes_from_snapshot(&state.snapshot, identity))
+    }
+
+    fn stakes_from_snapshot(snapshot: &StakesSnapshot, identity: &Pubkey) -> IdentityStakesData {
+        snapshot.get(identity).unwrap_or(IdentityStakesData {
+            total_stakes: snapshot.total_stakes,
+            min_stakes: snapshot.min_stakes,
+            max_stakes: snapshot.max_stakes,
+            ..IdentityStakesData::default()
+        })
+    }
+
+    /// keep the cache updated from the vote account stream until the stream is closed
+    pub fn start_updater(&self, mut vote_account_stream: VoteAccountStream) -> AnyhowJoinHandle {
+        let stake_cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match vote_account_stream.recv().await {
+                    Ok(snapshot) => {
+                        if let Err(err) = stake_cache.update_from_snapshot(&snapshot) {
+                            log::warn!("{err:?} - keeping previous stakes in stake cache");
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "stake cache lagged behind vote account stream by {skipped} messages"
+                        );
+                    }
+                    Err(RecvError::Closed) => {
+                        bail!("vote account stream closed - stopping stake cache updater");
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote_account(node_pubkey: &Pubkey, activated_stake: u64) -> RpcVoteAccountInfo {
+        RpcVoteAccountInfo {
+            vote_pubkey: Pubkey::new_unique().to_string(),
+            node_pubkey: node_pubkey.to_string(),
+            activated_stake,
+            commission: 0,
+            epoch_vote_account: true,
+            epoch_credits: vec![],
+            last_vote: 0,
+            root_slot: 0,
+        }
+    }
+
+    #[test]
+    fn test_stake_cache_lookup() {
+        let identity_a = Pubkey::new_unique();
+        let identity_b = Pubkey::new_unique();
+        let stake_cache = StakeCache::new();
+        stake_cache.update(&RpcVoteAccountStatus {
+            current: vec![
+                vote_account(&identity_a, 100),
+                vote_account(&identity_b, 300),
+            ],
+            delinquent: vec![],
+        });
+
+        let stakes_a = stake_cache.get(&identity_a);
+        assert_eq!(stakes_a.stakes, 100);
+        assert_eq!(stakes_a.total_stakes, 400);
+        assert_eq!(stakes_a.min_stakes, 100);
+        assert_eq!(stakes_a.max_stakes, 300);
+        assert!(matches!(
+            stakes_a.peer_type,
+            ConnectionPeerType::Staked(100)
+        ));
+
+        let unknown = stake_cache.get(&Pubkey::new_unique());
+        assert_eq!(unknown.stakes, 0);
+        assert_eq!(unknown.total_stakes, 400);
+        assert!(matches!(unknown.peer_type, ConnectionPeerType::Unstaked));
+    }
+
+    #[test]
+    fn test_delinquent_identity_keeps_stake() {
+        let current_identity = Pubkey::new_unique();
+        let delinquent_identity = Pubkey::new_unique();
+        let stake_cache = StakeCache::new();
+        stake_cache.update(&RpcVoteAccountStatus {
+            current: vec![vote_account(&current_identity, 100)],
+            delinquent: vec![vote_account(&delinquent_identity, 50)],
+        });
+
+        let delinquent_stakes = stake_cache.get(&delinquent_identity);
+        assert_eq!(delinquent_stakes.stakes, 50);
+        assert_eq!(delinquent_stakes.total_stakes, 150);
+        assert_eq!(delinquent_stakes.min_stakes, 50);
+        assert!(delinquent_stakes.is_delinquent);
+        assert!(matches!(
+            delinquent_stakes.peer_type,
+            ConnectionPeerType::Staked(50)
+        ));
+
+        assert!(!stake_cache.get(&current_identity).is_delinquent);
+    }
+
+    #[tokio::test]
+    async fn test_identity_stakes_with_delinquent_identity() {
+        let identity = Pubkey::new_unique();
+        let identity_stakes = IdentityStakes::new(identity);
+        identity_stakes
+            .update_stakes_for_identity(RpcVoteAccountStatus {
+                current: vec![vote_account(&Pubkey::new_unique(), 100)],
+                delinquent: vec![vote_account(&identity, 30)],
+            })
+            .await;
+
+        let stakes = identity_stakes.get_stakes().await.unwrap();
+        assert_eq!(stakes.stakes, 30);
+        assert!(stakes.is_delinquent);
+    }
+
+    #[test]
+    fn test_stake_cache_rejects_stale_snapshot() {
+        let identity = Pubkey::new_unique();
+        let stake_cache = StakeCache::new_with_max_snapshot_age(Duration::from_secs(60));
+
+        let stale = VoteAccountsSnapshot {
+            slot: 200,
+            received_at: Instant::now() - Duration::from_secs(120),
+            vote_accounts: RpcVoteAccountStatus {
+                current: vec![vote_account(&identity, 50)],
+                delinquent: vec![],
+            },
+        };
+        assert!(stake_cache.update_from_snapshot(&stale).is_err());
+        assert_eq!(stake_cache.get_stakes(&identity).unwrap().stakes, 0);
+
+        let fresh = VoteAccountsSnapshot::new(
+            100,
+            RpcVoteAccountStatus {
+                current: vec![vote_account(&identity, 30)],
+                delinquent: vec![],
+            },
+        );
+        stake_cache.update_from_snapshot(&fresh).unwrap();
+        assert_eq!(stake_cache.get_stakes(&identity).unwrap().stakes, 30);
+
+        // stakes which were not refreshed within the staleness bound are rejected
+        let outdated_cache = StakeCache::new_with_max_snapshot_age(Duration::from_millis(1));
+        outdated_cache.update(&fresh.vote_accounts);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(outdated_cache.get_stakes(&identity).is_err());
+        assert_eq!(outdated_cache.get(&identity).stakes, 30);
+    }
+
+    #[tokio::test]
+    async fn test_identity_stakes_rejects_stale_snapshot() {
+        let identity = Pubkey::new_unique();
+        let identity_stakes =
+            IdentityStakes::new_with_max_snapshot_age(identity, Duration::from_secs(60));
+
+        let fresh = VoteAccountsSnapshot::new(
+            100,
+            RpcVoteAccountStatus {
+                current: vec![vote_account(&identity, 30)],
+                delinquent: vec![],
+            },
+        );
+        identity_stakes
+            .update_stakes_from_snapshot(&fresh)
+            .await
+            .unwrap();
+        assert_eq!(identity_stakes.get_stakes().await.unwrap().stakes, 30);
+
+        let stale = VoteAccountsSnapshot {
+            slot: 200,
+            received_at: Instant::now() - Duration::from_secs(120),
+            vote_accounts: RpcVoteAccountStatus {
+                current: vec![vote_account(&identity, 50)],
+                delinquent: vec![],
+            },
+        };
+        assert!(identity_stakes
+            .update_stakes_from_snapshot(&stale)
+            .await
+            .is_err());
+        // previous stakes are kept
+        assert_eq!(identity_stakes.get_stakes().await.unwrap().stakes, 30);
+
+        // stakes taken from a snapshot which got stale since are rejected on lookup
+        identity_stakes.stakes_data.write().await.updated_from =
+            Some((100, Instant::now() - Duration::from_secs(120)));
+        assert!(identity_stakes.get_stakes().await.is_err());
+    }
+
+    #[test]
+    fn test_stake_percentile() {
+        let all_stakes = [40, 10, 30, 20];
+        assert_eq!(stake_percentile(0, &all_stakes), 0.0);
+        assert_eq!(stake_percentile(10, &all_stakes), 0.1);
+        assert_eq!(stake_percentile(20, &all_stakes), 0.3);
+        assert_eq!(stake_percentile(25, &all_stakes), 0.3);
+        assert_eq!(stake_percentile(40, &all_stakes), 1.0);
+        assert_eq!(stake_percentile(10, &[]), 0.0);
+
+        let identity = Pubkey::new_unique();
+        let stake_cache = StakeCache::new();
+        stake_cache.update(&RpcVoteAccountStatus {
+            current: vec![
+                vote_account(&Pubkey::new_unique(), 10),
+                vote_account(&identity, 30),
+                vote_account(&Pubkey::new_unique(), 60),
+            ],
+            delinquent: vec![],
+        });
+        assert_eq!(stake_cache.get(&identity).stake_percentile, 0.4);
+    }
+}
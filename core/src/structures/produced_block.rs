@@ -6,6 +6,7 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use solana_sdk::{slot_history::Slot, transaction::TransactionError};
 use solana_transaction_status::Reward;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -25,6 +26,26 @@ pub struct TransactionInfo {
     pub address_lookup_tables: Vec<MessageAddressTableLookup>,
 }
 
+/// single transaction of a produced block with the context of the block
+#[derive(Debug, Clone)]
+pub struct ProcessedTransaction {
+    pub slot: Slot,
+    pub blockhash: Hash,
+    pub commitment_config: CommitmentConfig,
+    pub transaction: TransactionInfo,
+}
+
+// Arc is required for channels
+pub type ProcessedTransactionShared = Arc<ProcessedTransaction>;
+
+/// a block arrived for an already seen slot with a different blockhash - the slot was replaced by a fork
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockReplaced {
+    pub slot: Slot,
+    pub old_hash: Hash,
+    pub new_hash: Hash,
+}
+
 #[derive(Clone)]
 pub struct ProducedBlock {
     // Arc is required for channels
@@ -73,6 +94,18 @@ pub struct ProducedBlockInner {
 }
 
 impl ProducedBlock {
+    /// split the block into its transactions
+    pub fn processed_transactions(&self) -> impl Iterator<Item = ProcessedTransactionShared> + '_ {
+        self.transactions.iter().map(|transaction| {
+            Arc::new(ProcessedTransaction {
+                slot: self.slot,
+                blockhash: self.blockhash,
+                commitment_config: self.commitment_config,
+                transaction: transaction.clone(),
+            })
+        })
+    }
+
     /// moving commitment level to confirmed
     pub fn to_confirmed_block(&self) -> Self {
         ProducedBlock {
@@ -89,3 +122,256 @@ impl ProducedBlock {
         }
     }
 }
+
+/// header field with different values in the compared blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+/// transaction present in both blocks with different values in `fields`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionMismatch {
+    pub signature: Signature,
+    pub fields: Vec<&'static str>,
+}
+
+/// differences between two blocks of the same slot, see [ProducedBlock::diff]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockDiff {
+    pub header: Vec<FieldMismatch>,
+    /// transactions of the left block missing in the right block, in block order
+    pub missing_transactions: Vec<Signature>,
+    /// transactions of the right block not in the left block, in block order
+    pub extra_transactions: Vec<Signature>,
+    pub changed_transactions: Vec<TransactionMismatch>,
+}
+
+impl BlockDiff {
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.missing_transactions.is_empty()
+            && self.extra_transactions.is_empty()
+            && self.changed_transactions.is_empty()
+    }
+}
+
+impl ProducedBlock {
+    /// compare the block (left, e.g. fetched from the cluster) with `other` (right, e.g. from the archive);
+    /// transactions are matched by signature, a transaction at a different position in the block is changed
+    /// in "index"; the commitment level is not compared
+    pub fn diff(&self, other: &ProducedBlock) -> BlockDiff {
+        let mut header = vec![];
+        let mut compare = |field: &'static str, left: String, right: String| {
+            if left != right {
+                header.push(FieldMismatch { field, left, right });
+            }
+        };
+        compare("slot", self.slot.to_string(), other.slot.to_string());
+        compare(
+            "blockhash",
+            self.blockhash.to_string(),
+            other.blockhash.to_string(),
+        );
+        compare(
+            "previous_blockhash",
+            self.previous_blockhash.to_string(),
+            other.previous_blockhash.to_string(),
+        );
+        compare(
+            "parent_slot",
+            self.parent_slot.to_string(),
+            other.parent_slot.to_string(),
+        );
+        compare(
+            "block_height",
+            self.block_height.to_string(),
+            other.block_height.to_string(),
+        );
+        compare(
+            "block_time",
+            self.block_time.to_string(),
+            other.block_time.to_string(),
+        );
+        compare(
+            "leader_id",
+            format!("{:?}", self.leader_id),
+            format!("{:?}", other.leader_id),
+        );
+        compare(
+            "rewards",
+            format!("{:?}", self.rewards),
+            format!("{:?}", other.rewards),
+        );
+
+        let other_transactions: HashMap<Signature, (usize, &TransactionInfo)> = other
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| (tx.signature, (index, tx)))
+            .collect();
+        let mut missing_transactions = vec![];
+        let mut changed_transactions = vec![];
+        for (index, tx) in self.transactions.iter().enumerate() {
+            match other_transactions.get(&tx.signature) {
+                Some((other_index, other_tx)) => {
+                    let mut fields = tx.diff_fields(other_tx);
+                    if index != *other_index {
+                        fields.insert(0, "index");
+                    }
+                    if !fields.is_empty() {
+                        changed_transactions.push(TransactionMismatch {
+                            signature: tx.signature,
+                            fields,
+                        });
+                    }
+                }
+                None => missing_transactions.push(tx.signature),
+            }
+        }
+        let signatures = self
+            .transactions
+            .iter()
+            .map(|tx| tx.signature)
+            .collect::<HashSet<_>>();
+        let extra_transactions = other
+            .transactions
+            .iter()
+            .map(|tx| tx.signature)
+            .filter(|signature| !signatures.contains(signature))
+            .collect();
+
+        BlockDiff {
+            header,
+            missing_transactions,
+            extra_transactions,
+            changed_transactions,
+        }
+    }
+}
+
+impl TransactionInfo {
+    fn diff_fields(&self, other: &TransactionInfo) -> Vec<&'static str> {
+        [
+            ("is_vote", self.is_vote == other.is_vote),
+            ("err", self.err == other.err),
+            ("cu_requested", self.cu_requested == other.cu_requested),
+            (
+                "prioritization_fees",
+                self.prioritization_fees == other.prioritization_fees,
+            ),
+            ("cu_consumed", self.cu_consumed == other.cu_consumed),
+            (
+                "recent_blockhash",
+                self.recent_blockhash == other.recent_blockhash,
+            ),
+            ("message", self.message == other.message),
+            (
+                "writable_accounts",
+                self.writable_accounts == other.writable_accounts,
+            ),
+            (
+                "readable_accounts",
+                self.readable_accounts == other.readable_accounts,
+            ),
+            (
+                "address_lookup_tables",
+                self.address_lookup_tables == other.address_lookup_tables,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, equal)| !equal)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::{v0, MessageHeader};
+
+    fn create_test_tx(signature: Signature, cu_consumed: u64) -> TransactionInfo {
+        TransactionInfo {
+            signature,
+            is_vote: false,
+            err: None,
+            cu_requested: Some(40000),
+            prioritization_fees: Some(5000),
+            cu_consumed: Some(cu_consumed),
+            recent_blockhash: Hash::default(),
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    ..MessageHeader::default()
+                },
+                account_keys: vec![Pubkey::default()],
+                ..v0::Message::default()
+            }),
+            writable_accounts: vec![],
+            readable_accounts: vec![],
+            address_lookup_tables: vec![],
+        }
+    }
+
+    fn create_test_block(transactions: Vec<TransactionInfo>) -> ProducedBlock {
+        let inner = ProducedBlockInner {
+            transactions,
+            leader_id: None,
+            blockhash: Hash::new_from_array([1; 32]),
+            block_height: 42,
+            slot: 223555999,
+            parent_slot: 223555998,
+            block_time: 1699260872,
+            previous_blockhash: Hash::new_from_array([2; 32]),
+            rewards: None,
+        };
+        ProducedBlock::new(inner, CommitmentConfig::confirmed())
+    }
+
+    #[test]
+    fn test_diff_pinpoints_transaction() {
+        let [sig1, sig2, sig3, sig4] = [1, 2, 3, 4].map(|i| Signature::from([i; 64]));
+        let live = create_test_block(vec![
+            create_test_tx(sig1, 1000),
+            create_test_tx(sig2, 2000),
+            create_test_tx(sig3, 3000),
+        ]);
+        assert!(live.diff(&live.to_finalized_block()).is_empty());
+
+        // archive lost sig2
+        let archived =
+            create_test_block(vec![create_test_tx(sig1, 1000), create_test_tx(sig3, 3000)]);
+        let diff = live.diff(&archived);
+        assert_eq!(diff.missing_transactions, vec![sig2]);
+        assert!(diff.extra_transactions.is_empty());
+        assert!(diff.header.is_empty());
+        assert_eq!(
+            diff.changed_transactions,
+            vec![TransactionMismatch {
+                signature: sig3,
+                fields: vec!["index"],
+            }]
+        );
+
+        // archive has a corrupt and an unknown transaction
+        let archived = create_test_block(vec![
+            create_test_tx(sig1, 1000),
+            create_test_tx(sig2, 2001),
+            create_test_tx(sig3, 3000),
+            create_test_tx(sig4, 4000),
+        ]);
+        let diff = live.diff(&archived);
+        assert!(diff.missing_transactions.is_empty());
+        assert_eq!(diff.extra_transactions, vec![sig4]);
+        assert_eq!(
+            diff.changed_transactions,
+            vec![TransactionMismatch {
+                signature: sig2,
+                fields: vec!["cu_consumed"],
+            }]
+        );
+    }
+}
@@ -1,6 +1,15 @@
-use std::sync::{atomic::AtomicU64, Arc};
+use std::{
+    collections::VecDeque,
+    sync::{atomic::AtomicU64, Arc, Mutex},
+};
 
+use anyhow::bail;
+use futures::{Stream, StreamExt};
 use solana_sdk::slot_history::Slot;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::types::SlotStream;
+use crate::AnyhowJoinHandle;
 
 pub type AtomicSlot = Arc<AtomicU64>;
 
@@ -9,3 +18,150 @@ pub struct SlotNotification {
     pub processed_slot: Slot,
     pub estimated_processed_slot: Slot,
 }
+
+/// keeps the last `capacity` slot notifications so that late subscribers can backfill
+#[derive(Debug, Clone)]
+pub struct SlotHistory {
+    capacity: usize,
+    history: Arc<Mutex<VecDeque<SlotNotification>>>,
+    // notifications are re-broadcasted while holding the history lock - see subscribe_with_history
+    sender: broadcast::Sender<SlotNotification>,
+}
+
+impl SlotHistory {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(16));
+        Self {
+            capacity,
+            history: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            sender,
+        }
+    }
+
+    pub fn push(&self, notification: SlotNotification) {
+        let mut history = self.history.lock().expect("slot history lock poisoned");
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        if self.capacity > 0 {
+            history.push_back(notification.clone());
+        }
+        // no live subscribers is fine
+        let _ = self.sender.send(notification);
+    }
+
+    /// last `n` notifications, oldest first
+    pub fn recent(&self, n: usize) -> Vec<SlotNotification> {
+        let history = self.history.lock().expect("slot history lock poisoned");
+        history
+            .iter()
+            .skip(history.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    pub fn subscribe(&self) -> SlotStream {
+        self.sender.subscribe()
+    }
+
+    /// replays the retained notifications and then tails the live ones, without gap or duplicates;
+    /// the stream ends when the history is dropped
+    pub fn subscribe_with_history(&self) -> impl Stream<Item = SlotNotification> + Send + 'static {
+        let (replay, live) = {
+            // subscribe under the same lock as push to not miss or repeat any notification
+            let history = self.history.lock().expect("slot history lock poisoned");
+            (
+                history.iter().cloned().collect::<Vec<_>>(),
+                self.sender.subscribe(),
+            )
+        };
+
+        let tail = futures::stream::unfold(live, |mut live| async move {
+            loop {
+                match live.recv().await {
+                    Ok(notification) => return Some((notification, live)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("slot history subscriber lagged by {skipped} notifications");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        futures::stream::iter(replay).chain(tail)
+    }
+
+    /// feed the history from the slot stream until the stream is closed
+    pub fn start_updater(&self, mut slot_stream: SlotStream) -> AnyhowJoinHandle {
+        let slot_history = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match slot_stream.recv().await {
+                    Ok(notification) => slot_history.push(notification),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("slot history lagged behind slot stream by {skipped} messages");
+                    }
+                    Err(RecvError::Closed) => {
+                        bail!("slot stream closed - stopping slot history updater");
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_notification(slot: Slot) -> SlotNotification {
+        SlotNotification {
+            processed_slot: slot,
+            estimated_processed_slot: slot,
+        }
+    }
+
+    #[test]
+    fn test_recent() {
+        let slot_history = SlotHistory::new(3);
+        for slot in 1..=5 {
+            slot_history.push(slot_notification(slot));
+        }
+        let recent = |n| {
+            slot_history
+                .recent(n)
+                .iter()
+                .map(|n| n.processed_slot)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(recent(2), vec![4, 5]);
+        assert_eq!(recent(10), vec![3, 4, 5]);
+        assert!(recent(0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_with_history_has_no_gap() {
+        let (slot_sender, slot_stream) = broadcast::channel(16);
+        let slot_history = SlotHistory::new(4);
+        let updater = slot_history.start_updater(slot_stream);
+
+        for slot in 1..=6 {
+            slot_sender.send(slot_notification(slot)).unwrap();
+        }
+        while slot_history.recent(1).first().map(|n| n.processed_slot) != Some(6) {
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+
+        let late_subscriber = slot_history.subscribe_with_history();
+        for slot in 7..=9 {
+            slot_sender.send(slot_notification(slot)).unwrap();
+        }
+
+        let slots = late_subscriber
+            .take(7)
+            .map(|n| n.processed_slot)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(slots, vec![3, 4, 5, 6, 7, 8, 9]);
+        updater.abort();
+    }
+}
@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
-use solana_rpc_client_api::response::{RpcContactInfo, RpcVoteAccountStatus};
+use solana_rpc_client_api::response::RpcContactInfo;
 use tokio::sync::broadcast::Receiver;
 
 use crate::structures::block_info::BlockInfo;
+use crate::structures::identity_stakes::VoteAccountsSnapshot;
+use crate::structures::produced_block::{BlockReplaced, ProcessedTransactionShared};
 use crate::{
     structures::{produced_block::ProducedBlock, slot_notification::SlotNotification},
     traits::subscription_sink::SubscriptionSink,
@@ -18,7 +20,27 @@ pub type BlockStream = Receiver<ProducedBlock>;
 // note: there is no guarantee about the order wrt commitment level
 pub type BlockInfoStream = Receiver<BlockInfo>;
 pub type SlotStream = Receiver<SlotNotification>;
+// slots of the BlockStream which got a block with a different blockhash (fork switch)
+pub type BlockReplacedStream = Receiver<BlockReplaced>;
+// transactions of full blocks (see BlockStream), one message per transaction
+// note: transactions of one block are sent in block order
+pub type TransactionStream = Receiver<ProcessedTransactionShared>;
 
-pub type VoteAccountStream = Receiver<RpcVoteAccountStatus>;
+pub type VoteAccountStream = Receiver<VoteAccountsSnapshot>;
 pub type ClusterInfoStream = Receiver<Vec<RpcContactInfo>>;
-pub type SubscptionHanderSink = Arc<dyn SubscriptionSink>;
+pub type SubscriptionHandlerSink = Arc<dyn SubscriptionSink>;
+#[deprecated(note = "use SubscriptionHandlerSink")]
+pub type SubscptionHanderSink = SubscriptionHandlerSink;
+
+// streams and sinks are moved across tokio tasks - make sure they stay Send + Sync
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BlockStream>();
+    assert_send_sync::<BlockInfoStream>();
+    assert_send_sync::<SlotStream>();
+    assert_send_sync::<BlockReplacedStream>();
+    assert_send_sync::<TransactionStream>();
+    assert_send_sync::<VoteAccountStream>();
+    assert_send_sync::<ClusterInfoStream>();
+    assert_send_sync::<SubscriptionHandlerSink>();
+};
@@ -35,7 +35,7 @@ use solana_lite_rpc_services::{
         tpu_connection_path::TpuConnectionPath,
         tpu_service::{TpuService, TpuServiceConfig},
     },
-    transaction_replayer::TransactionReplayer,
+    transaction_replayer::{RetryPolicy, TransactionReplayer},
     transaction_service::TransactionServiceBuilder,
     tx_sender::TxSender,
 };
@@ -376,7 +376,7 @@ async fn main() -> anyhow::Result<()> {
         TransactionReplayer::new(
             tpu_service.clone(),
             data_cache.clone(),
-            Duration::from_secs(1),
+            RetryPolicy::linear(Duration::from_secs(1), 10),
         ),
         tpu_service,
         10000,
@@ -0,0 +1,428 @@
+use std::{str::FromStr, time::Duration};
+
+use serde_json::json;
+use solana_lite_rpc_core::{
+    encoding::{BASE58, BASE64},
+    structures::produced_block::{ProducedBlock, TransactionInfo},
+    types::{BlockStream, SubscriptionHandlerSink},
+};
+use solana_rpc_client_api::config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter};
+use solana_sdk::{
+    commitment_config::CommitmentLevel, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodableWithMeta, EncodedTransaction, TransactionBinaryEncoding, TransactionDetails,
+    UiTransactionEncoding,
+};
+use tokio::{
+    sync::broadcast::error::RecvError::{Closed, Lagged},
+    task::JoinHandle,
+};
+
+// how often a subscription without matching blocks checks for a closed sink
+const CLOSED_SINK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// parsed blockSubscribe params
+///
+/// only the first signature and the message of a transaction are known: `transactionDetails` supports
+/// "full" (default), "signatures" and "none"; full transactions carry no status meta (`meta` is null)
+/// and the signatures after the first one are reported as default signatures
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSubscription {
+    mentions: Option<Pubkey>,
+    commitment: CommitmentLevel,
+    show_rewards: bool,
+    transaction_details: TransactionDetails,
+    encoding: UiTransactionEncoding,
+    max_supported_transaction_version: Option<u8>,
+}
+
+impl BlockSubscription {
+    pub fn new(
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) -> Result<Self, String> {
+        let mentions = match filter {
+            RpcBlockSubscribeFilter::All => None,
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram(pubkey) => Some(
+                Pubkey::from_str(&pubkey)
+                    .map_err(|_| format!("Invalid mentionsAccountOrProgram {pubkey}"))?,
+            ),
+        };
+
+        let config = config.unwrap_or_default();
+        let commitment = config.commitment.unwrap_or_default().commitment;
+        let transaction_details = config
+            .transaction_details
+            .unwrap_or(TransactionDetails::Full);
+        if transaction_details == TransactionDetails::Accounts {
+            return Err(
+                "transactionDetails accounts is not supported - use full, signatures or none"
+                    .to_string(),
+            );
+        }
+        let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
+        if !matches!(
+            encoding,
+            UiTransactionEncoding::Json
+                | UiTransactionEncoding::Base58
+                | UiTransactionEncoding::Base64
+        ) {
+            return Err(format!(
+                "encoding {encoding:?} is not supported - use json, base58 or base64"
+            ));
+        }
+
+        Ok(Self {
+            mentions,
+            commitment,
+            show_rewards: config.show_rewards.unwrap_or(true),
+            transaction_details,
+            encoding,
+            max_supported_transaction_version: config.max_supported_transaction_version,
+        })
+    }
+
+    /// blockNotification value (RpcBlockUpdate) or None if the block does not match the subscription
+    pub fn to_block_update(&self, block: &ProducedBlock) -> Option<serde_json::Value> {
+        if block.commitment_config.commitment != self.commitment {
+            return None;
+        }
+
+        let transactions = block
+            .transactions
+            .iter()
+            .filter(|tx| self.mentions.map_or(true, |pubkey| mentions(tx, &pubkey)))
+            .collect::<Vec<_>>();
+        if self.mentions.is_some() && transactions.is_empty() {
+            return None;
+        }
+
+        let mut ui_block = json!({
+            "previousBlockhash": block.previous_blockhash.to_string(),
+            "blockhash": block.blockhash.to_string(),
+            "parentSlot": block.parent_slot,
+            "blockTime": block.block_time,
+            "blockHeight": block.block_height,
+        });
+        match self.transaction_details {
+            TransactionDetails::Full => {
+                ui_block["transactions"] = json!(transactions
+                    .iter()
+                    .map(|tx| self.encode_transaction(tx))
+                    .collect::<Vec<_>>());
+            }
+            TransactionDetails::Signatures => {
+                ui_block["signatures"] = json!(transactions
+                    .iter()
+                    .map(|tx| tx.signature.to_string())
+                    .collect::<Vec<_>>());
+            }
+            TransactionDetails::None | TransactionDetails::Accounts => {}
+        }
+        if self.show_rewards {
+            ui_block["rewards"] = json!(block.rewards.clone().unwrap_or_default());
+        }
+
+        Some(json!({
+            "slot": block.slot,
+            "block": ui_block,
+            "err": null,
+        }))
+    }
+}
+
+impl BlockSubscription {
+    // EncodedTransactionWithStatusMeta
+    fn encode_transaction(&self, tx: &TransactionInfo) -> serde_json::Value {
+        let num_signatures = (tx.message.header().num_required_signatures as usize).max(1);
+        let mut signatures = vec![Signature::default(); num_signatures];
+        signatures[0] = tx.signature;
+        let transaction = VersionedTransaction {
+            signatures,
+            message: tx.message.clone(),
+        };
+
+        let encoded = match self.encoding {
+            UiTransactionEncoding::Base58 => EncodedTransaction::Binary(
+                BASE58
+                    .serialize(&transaction)
+                    .expect("transaction is serializable"),
+                TransactionBinaryEncoding::Base58,
+            ),
+            UiTransactionEncoding::Base64 => EncodedTransaction::Binary(
+                BASE64
+                    .serialize(&transaction)
+                    .expect("transaction is serializable"),
+                TransactionBinaryEncoding::Base64,
+            ),
+            // other encodings are rejected on subscribe
+            _ => transaction.json_encode(),
+        };
+        let mut encoded_tx = json!({
+            "transaction": encoded,
+            "meta": null,
+        });
+        if self.max_supported_transaction_version.is_some() {
+            encoded_tx["version"] = json!(transaction.version());
+        }
+        encoded_tx
+    }
+}
+
+fn mentions(tx: &TransactionInfo, pubkey: &Pubkey) -> bool {
+    tx.message.static_account_keys().contains(pubkey)
+        || tx.writable_accounts.contains(pubkey)
+        || tx.readable_accounts.contains(pubkey)
+}
+
+/// forward matching blocks to the sink until it is closed; dropping the stream on close
+/// removes the receiver from the broadcast channel
+pub fn spawn_block_subscription(
+    mut block_stream: BlockStream,
+    subscription: BlockSubscription,
+    sink: SubscriptionHandlerSink,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match tokio::time::timeout(CLOSED_SINK_CHECK_INTERVAL, block_stream.recv()).await {
+                Ok(Ok(block)) => {
+                    if sink.is_closed() {
+                        break;
+                    }
+                    if let Some(block_update) = subscription.to_block_update(&block) {
+                        sink.send(block.slot, block_update).await;
+                    }
+                }
+                Ok(Err(Lagged(lagged))) => {
+                    log::warn!("block subscriber lagged {lagged} blocks - continue");
+                }
+                Ok(Err(Closed)) => {
+                    log::error!("failed to receive block, sender closed - aborting");
+                    break;
+                }
+                Err(_elapsed) => {
+                    if sink.is_closed() {
+                        break;
+                    }
+                }
+            }
+        }
+        log::debug!("Stopping block subscription task");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_lite_rpc_core::{
+        structures::produced_block::ProducedBlockInner, traits::subscription_sink::SubscriptionSink,
+    };
+    use solana_sdk::{
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        message::{v0, VersionedMessage},
+        signature::Signature,
+        slot_history::Slot,
+    };
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    #[derive(Default)]
+    struct CollectingSink {
+        messages: Mutex<Vec<(Slot, serde_json::Value)>>,
+        closed: AtomicBool,
+    }
+
+    #[async_trait]
+    impl SubscriptionSink for CollectingSink {
+        async fn send(&self, slot: Slot, message: serde_json::Value) {
+            self.messages.lock().unwrap().push((slot, message));
+        }
+
+        fn is_closed(&self) -> bool {
+            self.closed.load(Ordering::Relaxed)
+        }
+    }
+
+    fn create_tx(account: Pubkey) -> TransactionInfo {
+        TransactionInfo {
+            signature: Signature::new_unique(),
+            is_vote: false,
+            err: None,
+            cu_requested: None,
+            prioritization_fees: None,
+            cu_consumed: None,
+            recent_blockhash: Hash::new_unique(),
+            message: VersionedMessage::V0(v0::Message {
+                account_keys: vec![account],
+                ..v0::Message::default()
+            }),
+            writable_accounts: vec![],
+            readable_accounts: vec![],
+            address_lookup_tables: vec![],
+        }
+    }
+
+    fn create_block(
+        slot: Slot,
+        transactions: Vec<TransactionInfo>,
+        commitment_config: CommitmentConfig,
+    ) -> ProducedBlock {
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions,
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time: 1699260872,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+            },
+            commitment_config,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_block_is_delivered() {
+        let account = Pubkey::new_unique();
+        let subscription = BlockSubscription::new(
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram(account.to_string()),
+            Some(RpcBlockSubscribeConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                transaction_details: Some(TransactionDetails::Signatures),
+                ..RpcBlockSubscribeConfig::default()
+            }),
+        )
+        .unwrap();
+        let (block_sender, block_stream) = tokio::sync::broadcast::channel(16);
+        let sink = Arc::new(CollectingSink::default());
+        let jh = spawn_block_subscription(block_stream, subscription, sink.clone());
+
+        let matching_tx = create_tx(account);
+        let matching_signature = matching_tx.signature;
+        let block = create_block(
+            100,
+            vec![matching_tx, create_tx(Pubkey::new_unique())],
+            CommitmentConfig::confirmed(),
+        );
+        // wrong commitment
+        block_sender.send(block.to_finalized_block()).unwrap();
+        // does not mention the account
+        block_sender
+            .send(create_block(
+                101,
+                vec![create_tx(Pubkey::new_unique())],
+                CommitmentConfig::confirmed(),
+            ))
+            .unwrap();
+        block_sender.send(block.clone()).unwrap();
+
+        while sink.messages.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let (slot, block_update) = sink.messages.lock().unwrap()[0].clone();
+        assert_eq!(slot, 100);
+        assert_eq!(block_update["slot"], 100);
+        assert_eq!(
+            block_update["block"]["blockhash"],
+            block.blockhash.to_string()
+        );
+        assert_eq!(
+            block_update["block"]["signatures"],
+            json!([matching_signature.to_string()])
+        );
+        assert_eq!(block_update["block"]["rewards"], json!([]));
+
+        // task stops once the sink is closed
+        sink.closed.store(true, Ordering::Relaxed);
+        tokio::time::timeout(CLOSED_SINK_CHECK_INTERVAL * 2, jh)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block_sender.receiver_count(), 0);
+        assert_eq!(sink.messages.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_full_transaction_details_by_default() {
+        let subscription = BlockSubscription::new(RpcBlockSubscribeFilter::All, None).unwrap();
+        assert_eq!(subscription.transaction_details, TransactionDetails::Full);
+        assert_eq!(subscription.encoding, UiTransactionEncoding::Json);
+
+        let tx = create_tx(Pubkey::new_unique());
+        let signature = tx.signature;
+        let block = create_block(100, vec![tx], CommitmentConfig::finalized());
+        let block_update = subscription.to_block_update(&block).unwrap();
+
+        assert!(block_update["block"].get("signatures").is_none());
+        let transactions = block_update["block"]["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(
+            transactions[0]["transaction"]["signatures"],
+            json!([signature.to_string()])
+        );
+        assert!(transactions[0]["meta"].is_null());
+        assert!(transactions[0].get("version").is_none());
+    }
+
+    #[test]
+    fn test_full_transaction_details_binary_encoding() {
+        let subscription = BlockSubscription::new(
+            RpcBlockSubscribeFilter::All,
+            Some(RpcBlockSubscribeConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                max_supported_transaction_version: Some(0),
+                ..RpcBlockSubscribeConfig::default()
+            }),
+        )
+        .unwrap();
+
+        let tx = create_tx(Pubkey::new_unique());
+        let block = create_block(100, vec![tx.clone()], CommitmentConfig::finalized());
+        let block_update = subscription.to_block_update(&block).unwrap();
+
+        let encoded_tx = &block_update["block"]["transactions"][0];
+        assert_eq!(encoded_tx["version"], json!(0));
+        let transaction: VersionedTransaction = BASE64
+            .deserialize(&encoded_tx["transaction"][0].as_str().unwrap().to_string())
+            .unwrap();
+        assert_eq!(encoded_tx["transaction"][1], json!("base64"));
+        assert_eq!(transaction.signatures, vec![tx.signature]);
+        assert_eq!(transaction.message, tx.message);
+    }
+
+    #[test]
+    fn test_unsupported_params() {
+        let with_config = |config: RpcBlockSubscribeConfig| {
+            BlockSubscription::new(RpcBlockSubscribeFilter::All, Some(config))
+        };
+        assert!(with_config(RpcBlockSubscribeConfig {
+            transaction_details: Some(TransactionDetails::Accounts),
+            ..RpcBlockSubscribeConfig::default()
+        })
+        .is_err());
+        assert!(with_config(RpcBlockSubscribeConfig {
+            encoding: Some(UiTransactionEncoding::JsonParsed),
+            ..RpcBlockSubscribeConfig::default()
+        })
+        .is_err());
+        assert!(with_config(RpcBlockSubscribeConfig {
+            commitment: Some(CommitmentConfig::processed()),
+            ..RpcBlockSubscribeConfig::default()
+        })
+        .is_ok());
+        assert!(BlockSubscription::new(
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram("invalid".to_string()),
+            None
+        )
+        .is_err());
+        assert!(BlockSubscription::new(RpcBlockSubscribeFilter::All, None).is_ok());
+    }
+}
@@ -31,6 +31,7 @@ use solana_transaction_status::{
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use solana_lite_rpc_blockstore::history::History;
 use solana_lite_rpc_core::solana_utils::hash_from_str;
@@ -41,14 +42,23 @@ use solana_lite_rpc_services::{
     transaction_service::TransactionService, tx_sender::TXS_IN_CHANNEL,
 };
 
+use crate::configs::SendTransactionWithConfirmationConfig;
 use crate::rpc_errors::RpcErrors;
+use crate::transaction_confirmation::{
+    send_and_await_confirmation, SendTransactionConfirmation, DEFAULT_CONFIRMATION_TIMEOUT,
+    MAX_CONFIRMATION_TIMEOUT,
+};
 use crate::{configs::IsBlockHashValidConfig, rpc::LiteRpcServer};
-use solana_lite_rpc_prioritization_fees::rpc_data::{AccountPrioFeesStats, PrioFeesStats};
+use solana_lite_rpc_prioritization_fees::rpc_data::{
+    AccountPrioFeesStats, PrioFeesStats, VersionedPrioFeesStats,
+};
 use solana_lite_rpc_prioritization_fees::PrioFeesService;
 
 lazy_static::lazy_static! {
     static ref RPC_SEND_TX: IntCounter =
     register_int_counter!(opts!("literpc_rpc_send_tx", "RPC call send transaction")).unwrap();
+    static ref RPC_SEND_TX_WITH_CONFIRMATION: IntCounter =
+    register_int_counter!(opts!("literpc_rpc_send_tx_with_confirmation", "RPC call send transaction with confirmation")).unwrap();
     static ref RPC_GET_LATEST_BLOCKHASH: IntCounter =
     register_int_counter!(opts!("literpc_rpc_get_latest_blockhash", "RPC call to get latest block hash")).unwrap();
     static ref RPC_IS_BLOCKHASH_VALID: IntCounter =
@@ -61,6 +71,51 @@ lazy_static::lazy_static! {
     register_int_counter!(opts!("literpc_rpc_airdrop", "RPC call to request airdrop")).unwrap();
 }
 
+fn decode_wire_transaction(tx: String, encoding: UiTransactionEncoding) -> RpcResult<Vec<u8>> {
+    // Copied these constants from solana labs code
+    const MAX_BASE58_SIZE: usize = 1683;
+    const MAX_BASE64_SIZE: usize = 1644;
+
+    let expected_size = match encoding {
+        UiTransactionEncoding::Base58 => MAX_BASE58_SIZE,
+        UiTransactionEncoding::Base64 => MAX_BASE64_SIZE,
+        _ => usize::MAX,
+    };
+    if tx.len() > expected_size {
+        return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+    }
+
+    let binary_encoding = encoding
+        .into_binary_encoding()
+        .ok_or(jsonrpsee::types::error::ErrorCode::InvalidParams)?;
+
+    let wire_output = match binary_encoding {
+        TransactionBinaryEncoding::Base58 => {
+            if tx.len() > MAX_BASE58_SIZE {
+                return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+            }
+            BASE58
+                .decode(tx)
+                .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
+        }
+        TransactionBinaryEncoding::Base64 => {
+            if tx.len() > MAX_BASE64_SIZE {
+                return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+            }
+            BASE64
+                .decode(tx)
+                .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
+        }
+    };
+    if wire_output.len() > PACKET_DATA_SIZE {
+        return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+    }
+    Ok(wire_output)
+}
+
+// same as MAX_NUM_RECENT_BLOCKS of solana prioritization fee cache; limited by the blocks retained in PrioFeesService
+const RECENT_PRIOFEES_BLOCKS: usize = 150;
+
 /// A bridge between clients and tpu
 #[allow(dead_code)]
 pub struct LiteBridge {
@@ -307,6 +362,24 @@ impl LiteRpcServer for LiteBridge {
         &self,
         pubkey_strs: Vec<String>,
     ) -> RpcResult<Vec<RpcPrioritizationFee>> {
+        if pubkey_strs.is_empty() {
+            // same shape as solana rpc: one entry per recent slot
+            let recent_prio_fees = self
+                .prio_fees_service
+                .get_recent_priofees(RECENT_PRIOFEES_BLOCKS)
+                .await;
+            return Ok(recent_prio_fees
+                .into_iter()
+                .map(|(slot, stats)| RpcPrioritizationFee {
+                    slot,
+                    prioritization_fee: stats
+                        .get_percentile(0.5)
+                        .map(|(_by_tx, by_cu)| by_cu)
+                        .unwrap_or_default(),
+                })
+                .collect_vec());
+        }
+
         // This method will get the latest global and account prioritization fee stats and then send the maximum p75
         const PERCENTILE: f32 = 0.75;
         let accounts = pubkey_strs
@@ -351,51 +424,14 @@ impl LiteRpcServer for LiteBridge {
     ) -> RpcResult<String> {
         RPC_SEND_TX.inc();
 
-        // Copied these constants from solana labs code
-        const MAX_BASE58_SIZE: usize = 1683;
-        const MAX_BASE64_SIZE: usize = 1644;
-
         let RpcSendTransactionConfig {
             encoding,
             max_retries,
             ..
         } = send_transaction_config.unwrap_or_default();
 
-        let encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let expected_size = match encoding {
-            UiTransactionEncoding::Base58 => MAX_BASE58_SIZE,
-            UiTransactionEncoding::Base64 => MAX_BASE64_SIZE,
-            _ => usize::MAX,
-        };
-        if tx.len() > expected_size {
-            return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-        }
-
-        let binary_encoding = encoding
-            .into_binary_encoding()
-            .ok_or(jsonrpsee::types::error::ErrorCode::InvalidParams)?;
-
-        let wire_output = match binary_encoding {
-            TransactionBinaryEncoding::Base58 => {
-                if tx.len() > MAX_BASE58_SIZE {
-                    return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-                }
-                BASE58
-                    .decode(tx)
-                    .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
-            }
-            TransactionBinaryEncoding::Base64 => {
-                if tx.len() > MAX_BASE64_SIZE {
-                    return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-                }
-                BASE64
-                    .decode(tx)
-                    .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
-            }
-        };
-        if wire_output.len() > PACKET_DATA_SIZE {
-            return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-        }
+        let wire_output =
+            decode_wire_transaction(tx, encoding.unwrap_or(UiTransactionEncoding::Base58))?;
         let max_retries = max_retries.map(|x| x as u16);
         match self
             .transaction_service
@@ -411,6 +447,54 @@ impl LiteRpcServer for LiteBridge {
         }
     }
 
+    async fn send_transaction_with_confirmation(
+        &self,
+        tx: String,
+        config: Option<SendTransactionWithConfirmationConfig>,
+    ) -> RpcResult<SendTransactionConfirmation> {
+        RPC_SEND_TX_WITH_CONFIRMATION.inc();
+
+        let SendTransactionWithConfirmationConfig {
+            send_transaction_config:
+                RpcSendTransactionConfig {
+                    encoding,
+                    max_retries,
+                    ..
+                },
+            timeout_ms,
+        } = config.unwrap_or_default();
+
+        let wire_output =
+            decode_wire_transaction(tx, encoding.unwrap_or(UiTransactionEncoding::Base64))?;
+        let max_retries = max_retries.map(|x| x as u16);
+        let timeout = timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_CONFIRMATION_TIMEOUT)
+            .min(MAX_CONFIRMATION_TIMEOUT);
+
+        // forwarded to the leaders of the configured fanout like sendTransaction;
+        // the tx store gets updated from the block stream
+        let confirmation = send_and_await_confirmation(
+            || {
+                self.transaction_service
+                    .send_wire_transaction(wire_output, max_retries)
+            },
+            |signature| {
+                self.data_cache
+                    .txs
+                    .get(signature)
+                    .and_then(|tx_props| tx_props.status)
+                    .map(|status| status.slot)
+            },
+            timeout,
+        )
+        .await
+        .map_err(|_| jsonrpsee::types::error::ErrorCode::InternalError)?;
+        TXS_IN_CHANNEL.inc();
+
+        Ok(confirmation)
+    }
+
     fn get_version(&self) -> RpcResult<RpcVersionInfo> {
         RPC_GET_VERSION.inc();
 
@@ -502,6 +586,31 @@ impl LiteRpcServer for LiteBridge {
         }
     }
 
+    async fn get_lite_rpc_priofees_stats(
+        &self,
+        nb_blocks: Option<usize>,
+    ) -> RpcResult<RpcResponse<VersionedPrioFeesStats>> {
+        let nb_blocks = nb_blocks.unwrap_or(RECENT_PRIOFEES_BLOCKS);
+        if nb_blocks == 0 {
+            return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
+        }
+
+        match self
+            .prio_fees_service
+            .get_last_n_priofees_aggregate(nb_blocks)
+            .await
+        {
+            Some((slot, priofees)) => Ok(RpcResponse {
+                context: RpcResponseContext {
+                    slot,
+                    api_version: None,
+                },
+                value: VersionedPrioFeesStats::from(&priofees),
+            }),
+            None => Err(jsonrpsee::types::error::ErrorCode::InternalError.into()),
+        }
+    }
+
     async fn get_latest_account_priofees(
         &self,
         account: String,
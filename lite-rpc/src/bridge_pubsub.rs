@@ -10,12 +10,14 @@ use std::{str::FromStr, sync::Arc, time::Duration};
 use tokio::sync::broadcast::error::RecvError::{Closed, Lagged};
 
 use crate::{
+    block_subscription::{spawn_block_subscription, BlockSubscription},
     jsonrpsee_subscrption_handler_sink::JsonRpseeSubscriptionHandlerSink,
     rpc_pubsub::LiteRpcPubSubServer,
+    ws_keepalive::{send_to_subscriber, WsKeepalive},
 };
 use jsonrpsee::{
     core::{StringError, SubscriptionResult},
-    DisconnectError, PendingSubscriptionSink,
+    PendingSubscriptionSink,
 };
 use solana_lite_rpc_prioritization_fees::{
     account_prio_service::AccountPrioService,
@@ -34,6 +36,8 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 
 lazy_static::lazy_static! {
+    static ref RPC_BLOCK_SUBSCRIBE: IntCounter =
+    register_int_counter!(opts!("literpc_rpc_block_subscribe", "RPC call to subscribe to blocks")).unwrap();
     static ref RPC_SIGNATURE_SUBSCRIBE: IntCounter =
     register_int_counter!(opts!("literpc_rpc_signature_subscribe", "RPC call to subscribe to signature")).unwrap();
     static ref RPC_BLOCK_PRIOFEES_SUBSCRIBE: IntCounter =
@@ -50,9 +54,10 @@ pub struct LitePubSubBridge {
     data_cache: DataCache,
     prio_fees_service: PrioFeesService,
     account_priofees_service: AccountPrioService,
-    _block_stream: BlockStream,
+    block_stream: BlockStream,
     block_info_stream: BlockInfoStream,
     accounts_service: Option<AccountService>,
+    ws_keepalive: Option<WsKeepalive>,
 }
 
 impl LitePubSubBridge {
@@ -63,14 +68,16 @@ impl LitePubSubBridge {
         block_stream: BlockStream,
         block_info_stream: BlockInfoStream,
         accounts_service: Option<AccountService>,
+        ws_keepalive: Option<WsKeepalive>,
     ) -> Self {
         Self {
             data_cache,
             prio_fees_service,
             account_priofees_service,
-            _block_stream: block_stream,
+            block_stream,
             block_info_stream,
             accounts_service,
+            ws_keepalive,
         }
     }
 }
@@ -80,6 +87,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
     async fn slot_subscribe(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
         let sink = pending.accept().await?;
         let mut block_info_stream = self.block_info_stream.resubscribe();
+        let keepalive = self.ws_keepalive;
         tokio::spawn(async move {
             loop {
                 match block_info_stream.recv().await {
@@ -91,13 +99,15 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
                         };
                         let result_message = jsonrpsee::SubscriptionMessage::from_json(&slot_info);
 
-                        match sink.send(result_message.unwrap()).await {
+                        match send_to_subscriber(sink.send(result_message.unwrap()), keepalive)
+                            .await
+                        {
                             Ok(()) => {
                                 // success
                                 continue;
                             }
-                            Err(DisconnectError(_subscription_message)) => {
-                                log::debug!("Stopping subscription task on disconnect");
+                            Err(err) => {
+                                log::debug!("Stopping subscription task: {err}");
                                 return;
                             }
                         };
@@ -119,11 +129,23 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
 
     async fn block_subscribe(
         &self,
-        _pending: PendingSubscriptionSink,
-        _filter: RpcBlockSubscribeFilter,
-        _config: Option<RpcBlockSubscribeConfig>,
+        pending: PendingSubscriptionSink,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
     ) -> SubscriptionResult {
-        todo!()
+        RPC_BLOCK_SUBSCRIBE.inc();
+        let subscription = BlockSubscription::new(filter, config).map_err(StringError::from)?;
+        let sink = pending.accept().await?;
+
+        spawn_block_subscription(
+            self.block_stream.resubscribe(),
+            subscription,
+            Arc::new(JsonRpseeSubscriptionHandlerSink::new(
+                sink,
+                self.ws_keepalive,
+            )),
+        );
+        Ok(())
     }
 
     async fn logs_subscribe(
@@ -146,7 +168,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
         let signature = Signature::from_str(&signature)?;
         let sink = pending.accept().await?;
 
-        let jsonrpsee_sink = JsonRpseeSubscriptionHandlerSink::new(sink);
+        let jsonrpsee_sink = JsonRpseeSubscriptionHandlerSink::new(sink, self.ws_keepalive);
         self.data_cache.tx_subs.signature_subscribe(
             signature,
             config.commitment.unwrap_or_default(),
@@ -175,6 +197,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
         let sink = pending.accept().await?;
 
         let mut block_fees_stream = self.prio_fees_service.block_fees_stream.subscribe();
+        let keepalive = self.ws_keepalive;
         tokio::spawn(async move {
             RPC_BLOCK_PRIOFEES_SUBSCRIBE.inc();
 
@@ -193,13 +216,15 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
                                 value: priofees_stats,
                             });
 
-                        match sink.send(result_message.unwrap()).await {
+                        match send_to_subscriber(sink.send(result_message.unwrap()), keepalive)
+                            .await
+                        {
                             Ok(()) => {
                                 // success
                                 continue 'recv_loop;
                             }
-                            Err(DisconnectError(_subscription_message)) => {
-                                log::debug!("Stopping subscription task on disconnect");
+                            Err(err) => {
+                                log::debug!("Stopping subscription task: {err}");
                                 return;
                             }
                         };
@@ -236,6 +261,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
             .account_priofees_service
             .priofees_update_sender
             .subscribe();
+        let keepalive = self.ws_keepalive;
         tokio::spawn(async move {
             RPC_BLOCK_PRIOFEES_SUBSCRIBE.inc();
 
@@ -256,13 +282,15 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
                                     value: account_stats,
                                 });
 
-                            match sink.send(result_message.unwrap()).await {
+                            match send_to_subscriber(sink.send(result_message.unwrap()), keepalive)
+                                .await
+                            {
                                 Ok(()) => {
                                     // success
                                     continue 'recv_loop;
                                 }
-                                Err(DisconnectError(_subscription_message)) => {
-                                    log::debug!("Stopping subscription task on disconnect");
+                                Err(err) => {
+                                    log::debug!("Stopping subscription task: {err}");
                                     return;
                                 }
                             };
@@ -316,6 +344,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
         let sink = pending.accept().await?;
         let mut accounts_stream = accounts_service.account_notification_sender.subscribe();
 
+        let keepalive = self.ws_keepalive;
         tokio::spawn(async move {
             RPC_ACCOUNT_SUBSCRIBE.inc();
 
@@ -353,13 +382,15 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
                                 ),
                             });
 
-                        match sink.send(result_message.unwrap()).await {
+                        match send_to_subscriber(sink.send(result_message.unwrap()), keepalive)
+                            .await
+                        {
                             Ok(()) => {
                                 // success
                                 continue;
                             }
-                            Err(DisconnectError(_subscription_message)) => {
-                                log::debug!("Stopping subscription task on disconnect");
+                            Err(err) => {
+                                log::debug!("Stopping subscription task: {err}");
                                 return;
                             }
                         };
@@ -416,6 +447,7 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
             .min_context_slot
             .unwrap_or_default();
 
+        let keepalive = self.ws_keepalive;
         tokio::spawn(async move {
             RPC_ACCOUNT_SUBSCRIBE.inc();
 
@@ -463,13 +495,15 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
                                 },
                                 value,
                             });
-                        match sink.send(result_message.unwrap()).await {
+                        match send_to_subscriber(sink.send(result_message.unwrap()), keepalive)
+                            .await
+                        {
                             Ok(()) => {
                                 // success
                                 continue;
                             }
-                            Err(DisconnectError(_subscription_message)) => {
-                                log::debug!("Stopping subscription task on disconnect");
+                            Err(err) => {
+                                log::debug!("Stopping subscription task: {err}");
                                 return;
                             }
                         };
@@ -1,19 +1,31 @@
 use std::borrow::Cow;
 use std::fmt::{Debug, Display, Formatter};
-use std::net::SocketAddr;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
 use std::str::FromStr;
 use std::{env, time::Duration};
 
 use crate::postgres_logger::{self, PostgresSessionConfig};
 use crate::{
-    DEFAULT_FANOUT_SIZE, DEFAULT_GRPC_ADDR, DEFAULT_RETRY_TIMEOUT, DEFAULT_RPC_ADDR,
-    DEFAULT_WS_ADDR, MAX_RETRIES,
+    DEFAULT_FANOUT_SIZE, DEFAULT_GRPC_ADDR, DEFAULT_HEALTH_MAX_SLOT_AGE_MS, DEFAULT_RETRY_TIMEOUT,
+    DEFAULT_RPC_ADDR, DEFAULT_RPC_FAILOVER_MAX_LATENCY_MS, DEFAULT_WS_ADDR,
+    DEFAULT_WS_PING_INTERVAL_SECS, MAX_RETRIES,
 };
-use anyhow::Context;
-use clap::Parser;
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
+use itertools::Itertools;
+use solana_lite_rpc_blockstore::block_stores::postgres::redact_pg_config;
+use solana_lite_rpc_cluster_endpoints::geyser_grpc_connector::{
+    GrpcConnectionTimeouts, GrpcSourceConfig,
+};
+use solana_lite_rpc_core::keypair_loader::{parse_identity_keypair, resolve_identity_keypair};
 use solana_lite_rpc_services::quic_connection_utils::QuicConnectionParameters;
+use solana_lite_rpc_services::tpu_utils::identity_selector::IdentitySelectionStrategy;
+use solana_lite_rpc_services::transaction_replayer::RetryPolicy;
 use solana_rpc_client_api::client_error::reqwest::Url;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -21,29 +33,65 @@ pub struct Args {
     /// config.json
     #[arg(short, long)]
     pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Load and validate the config (incl. env overrides), print the effective config and exit
+    Validate,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct Config {
     #[serde(default = "Config::default_rpc_addr")]
     pub rpc_addr: String,
+    /// RPC endpoints used in this order if rpc_addr fails getHealth or responds too slow
+    #[serde(default)]
+    pub rpc_fallback_addrs: Vec<String>,
+    /// getHealth roundtrip above which an RPC endpoint is considered unhealthy
+    #[serde(default = "Config::default_rpc_failover_max_latency_ms")]
+    pub rpc_failover_max_latency_ms: u64,
     #[serde(default = "Config::default_ws_addr")]
     pub ws_addr: String,
     #[serde(default = "Config::default_lite_rpc_http_addr")]
     pub lite_rpc_http_addr: String,
     #[serde(default = "Config::default_lite_rpc_ws_addr")]
     pub lite_rpc_ws_addr: String,
+    /// websocket ping interval of the subscription server; subscribers not taking messages for 3 intervals are dropped; 0 disables
+    #[serde(default = "Config::default_ws_ping_interval_secs")]
+    pub ws_ping_interval_secs: u64,
     #[serde(default = "Config::default_fanout_size")]
     pub fanout_size: u64,
-    // Identity keypair path
+    /// Identity keypair: path to keypair file, base58 secret key or JSON byte array
     #[serde(default)]
     pub identity_keypair: Option<String>,
+    /// more identities in the same formats as identity_keypair, used in addition to it
+    #[serde(default)]
+    pub identity_keypairs: Vec<String>,
+    /// how the identity for sending each transaction to the next leaders is picked if there are multiple identities
+    #[serde(default)]
+    pub identity_selection: IdentitySelectionStrategy,
     #[serde(default = "Config::default_prometheus_addr")]
     pub prometheus_addr: String,
+    /// http endpoint /health reflecting if slot notifications are received
+    #[serde(default = "Config::default_health_addr")]
+    pub health_addr: String,
+    /// /health reports unhealthy if the last slot notification is older
+    #[serde(default = "Config::default_health_max_slot_age_ms")]
+    pub health_max_slot_age_ms: u64,
     #[serde(default = "Config::default_maximum_retries_per_tx")]
     pub maximum_retries_per_tx: usize,
     #[serde(default = "Config::default_transaction_retry_after_secs")]
     pub transaction_retry_after_secs: u64,
+    /// resend delay grows exponentially by this factor; linear growth if not set
+    #[serde(default)]
+    pub transaction_retry_backoff_multiplier: Option<f64>,
+    /// upper bound for the delay between two resends of a transaction
+    #[serde(default)]
+    pub transaction_retry_max_backoff_secs: Option<u64>,
     #[serde(default)]
     pub quic_proxy_addr: Option<String>,
     #[serde(default)]
@@ -70,6 +118,10 @@ pub struct Config {
     #[serde(default)]
     pub grpc_x_token4: Option<String>,
 
+    /// list of geyser grpc sources; takes precedence over grpc_addr/grpc_addr2..4 if not empty
+    #[serde(default)]
+    pub grpc_sources: Vec<GrpcSource>,
+
     #[serde(default)]
     pub enable_grpc_stream_inspection: bool,
 
@@ -98,12 +150,14 @@ pub struct Config {
 
 impl Config {
     pub async fn load() -> anyhow::Result<Self> {
-        dotenv().ok();
+        Self::load_with_args(&Args::parse()).await
+    }
 
-        let args = Args::parse();
+    pub async fn load_with_args(args: &Args) -> anyhow::Result<Self> {
+        dotenv().ok();
 
         let config_path = if args.config.is_some() {
-            args.config
+            args.config.clone()
         } else {
             let default_config_path = "config.json";
 
@@ -115,19 +169,30 @@ impl Config {
             }
         };
 
-        let config = if let Some(config_path) = config_path {
-            tokio::fs::read_to_string(config_path)
+        let mut config = if let Some(config_path) = config_path {
+            let content = tokio::fs::read_to_string(&config_path)
                 .await
-                .context("Error reading config file")?
+                .context("Error reading config file")?;
+            Config::parse(ConfigFormat::from_path(&config_path), &content)
+                .context("Error parsing config file")?
         } else {
-            "{}".to_string()
+            Config::parse(ConfigFormat::Json, "{}")?
         };
 
-        let mut config: Config =
-            serde_json::from_str(&config).context("Error parsing config file")?;
-
         config.rpc_addr = env::var("RPC_ADDR").unwrap_or(config.rpc_addr);
 
+        if let Ok(rpc_fallback_addrs) = env::var("RPC_FALLBACK_ADDRS") {
+            config.rpc_fallback_addrs = rpc_fallback_addrs
+                .split(',')
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        config.rpc_failover_max_latency_ms = env_var_parsed("RPC_FAILOVER_MAX_LATENCY_MS")?
+            .unwrap_or(config.rpc_failover_max_latency_ms);
+
         config.ws_addr = env::var("WS_ADDR").unwrap_or(config.ws_addr);
 
         config.lite_rpc_http_addr =
@@ -135,31 +200,39 @@ impl Config {
 
         config.lite_rpc_ws_addr = env::var("LITE_RPC_WS_ADDR").unwrap_or(config.lite_rpc_ws_addr);
 
-        SocketAddr::from_str(&config.lite_rpc_http_addr).expect("invalid LITE_RPC_HTTP_ADDR");
-        SocketAddr::from_str(&config.lite_rpc_ws_addr).expect("invalid LITE_RPC_WS_ADDR");
+        config.ws_ping_interval_secs =
+            env_var_parsed("WS_PING_INTERVAL_SECS")?.unwrap_or(config.ws_ping_interval_secs);
 
-        config.fanout_size = env::var("FANOUT_SIZE")
-            .map(|size| size.parse().unwrap())
-            .unwrap_or(config.fanout_size);
+        config.fanout_size = env_var_parsed("FANOUT_SIZE")?.unwrap_or(config.fanout_size);
 
-        // note: identity config is handled in load_identity_keypair
-        // the behavior is different from the other config values as it does either take a file path or the keypair as json array
+        // note: identity config is handled in resolve_identity
+        // the behavior is different from the other config values as IDENTITY may be a file path, base58 secret or json array
 
         config.prometheus_addr = env::var("PROMETHEUS_ADDR").unwrap_or(config.prometheus_addr);
 
-        config.maximum_retries_per_tx = env::var("MAX_RETRIES")
-            .map(|max| max.parse().unwrap())
-            .unwrap_or(config.maximum_retries_per_tx);
+        config.health_addr = env::var("HEALTH_ADDR").unwrap_or(config.health_addr);
+
+        config.health_max_slot_age_ms =
+            env_var_parsed("HEALTH_MAX_SLOT_AGE_MS")?.unwrap_or(config.health_max_slot_age_ms);
+
+        config.maximum_retries_per_tx =
+            env_var_parsed("MAX_RETRIES")?.unwrap_or(config.maximum_retries_per_tx);
 
-        config.transaction_retry_after_secs = env::var("RETRY_TIMEOUT")
-            .map(|secs| secs.parse().unwrap())
-            .unwrap_or(config.transaction_retry_after_secs);
+        config.transaction_retry_after_secs =
+            env_var_parsed("RETRY_TIMEOUT")?.unwrap_or(config.transaction_retry_after_secs);
+
+        config.transaction_retry_backoff_multiplier = env_var_parsed("RETRY_BACKOFF_MULTIPLIER")?
+            .or(config.transaction_retry_backoff_multiplier);
+
+        config.transaction_retry_max_backoff_secs =
+            env_var_parsed("RETRY_MAX_BACKOFF_SECS")?.or(config.transaction_retry_max_backoff_secs);
 
         config.quic_proxy_addr = env::var("QUIC_PROXY_ADDR").ok();
 
-        config.use_grpc = env::var("USE_GRPC")
-            .map(|value| value.parse::<bool>().unwrap())
-            .unwrap_or(config.use_grpc);
+        config.use_grpc = env_var_parsed("USE_GRPC")?.unwrap_or(config.use_grpc);
+
+        // GRPC_ADDR* env variables are sugar for a grpc_sources list
+        let grpc_sources_from_env = env::var("GRPC_ADDR").is_ok();
 
         // source 1
         config.grpc_addr = env::var("GRPC_ADDR").unwrap_or(config.grpc_addr);
@@ -200,18 +273,18 @@ impl Config {
             .map(Some)
             .unwrap_or(config.grpc_x_token4);
 
-        config.enable_grpc_stream_inspection = env::var("ENABLE_GRPC_STREAM_INSPECTION")
-            .map(|value| value.parse::<bool>().expect("bool value"))
+        if config.grpc_sources.is_empty() || grpc_sources_from_env {
+            config.grpc_sources = config.legacy_grpc_sources();
+        }
+
+        config.enable_grpc_stream_inspection = env_var_parsed("ENABLE_GRPC_STREAM_INSPECTION")?
             .unwrap_or(config.enable_grpc_stream_inspection);
 
-        config.max_number_of_connection = env::var("MAX_NB_OF_CONNECTIONS_WITH_LEADERS")
-            .map(|x| x.parse().ok())
-            .unwrap_or(config.max_number_of_connection);
+        config.max_number_of_connection = env_var_parsed("MAX_NB_OF_CONNECTIONS_WITH_LEADERS")?
+            .or(config.max_number_of_connection);
 
-        config.enable_address_lookup_tables = env::var("ENABLE_ADDRESS_LOOKUP_TABLES")
-            .map(|value| value.parse::<bool>().unwrap())
-            .ok()
-            .or(config.enable_address_lookup_tables);
+        config.enable_address_lookup_tables =
+            env_var_parsed("ENABLE_ADDRESS_LOOKUP_TABLES")?.or(config.enable_address_lookup_tables);
 
         config.address_lookup_tables_binary = env::var("ADDRESS_LOOKUP_TABLES_BINARY")
             .ok()
@@ -219,18 +292,168 @@ impl Config {
 
         config.account_filters = env::var("ACCOUNT_FILTERS").ok().or(config.account_filters);
 
-        config.enable_accounts_on_demand_accounts_service = env::var("ENABLE_ACCOUNT_ON_DEMAND")
-            .map(|value| value.parse::<bool>().unwrap())
-            .ok()
-            .or(config.enable_accounts_on_demand_accounts_service);
+        config.enable_accounts_on_demand_accounts_service =
+            env_var_parsed("ENABLE_ACCOUNT_ON_DEMAND")?
+                .or(config.enable_accounts_on_demand_accounts_service);
 
         config.postgres = PostgresSessionConfig::new_from_env()?.or(config.postgres);
-        config.quic_connection_parameters = config
-            .quic_connection_parameters
-            .or(quic_params_from_environment());
+        config.quic_connection_parameters = match config.quic_connection_parameters {
+            Some(quic_connection_parameters) => Some(quic_connection_parameters),
+            None => quic_params_from_environment()?,
+        };
+
+        config.validate()?;
+
         Ok(config)
     }
 
+    /// deserialize the config file content without applying env overrides
+    pub fn parse(format: ConfigFormat, content: &str) -> anyhow::Result<Self> {
+        let config = match format {
+            ConfigFormat::Json => serde_json::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+            ConfigFormat::Toml => toml::from_str(content)?,
+        };
+        Ok(config)
+    }
+
+    /// copy of the config with secrets (tokens, passwords, keys) masked for printing
+    pub fn redacted(&self) -> Config {
+        let mut config = self.clone();
+        config.rpc_addr = url_obfuscate_api_token(&self.rpc_addr).to_string();
+        config.rpc_fallback_addrs = self
+            .rpc_fallback_addrs
+            .iter()
+            .map(|addr| url_obfuscate_api_token(addr).to_string())
+            .collect();
+        config.ws_addr = url_obfuscate_api_token(&self.ws_addr).to_string();
+        config.identity_keypair = self.identity_keypair.as_ref().map(|_| REDACTED.to_string());
+        config.identity_keypairs = vec![REDACTED.to_string(); self.identity_keypairs.len()];
+
+        config.grpc_addr = url_obfuscate_api_token(&self.grpc_addr).to_string();
+        config.grpc_x_token = redact_token(&self.grpc_x_token);
+        for (addr, x_token) in [
+            (&mut config.grpc_addr2, &mut config.grpc_x_token2),
+            (&mut config.grpc_addr3, &mut config.grpc_x_token3),
+            (&mut config.grpc_addr4, &mut config.grpc_x_token4),
+        ] {
+            *addr = addr
+                .as_ref()
+                .map(|addr| url_obfuscate_api_token(addr).to_string());
+            *x_token = redact_token(x_token);
+        }
+        for grpc_source in &mut config.grpc_sources {
+            grpc_source.addr = url_obfuscate_api_token(&grpc_source.addr).to_string();
+            grpc_source.x_token = redact_token(&grpc_source.x_token);
+        }
+
+        if let Some(postgres) = &mut config.postgres {
+            postgres.pg_config = redact_pg_config(&postgres.pg_config);
+            if let Some(ssl) = &mut postgres.ssl {
+                ssl.ca_pem_b64 = REDACTED.to_string();
+                ssl.client_pks_b64 = REDACTED.to_string();
+                ssl.client_pks_pass = REDACTED.to_string();
+            }
+        }
+
+        config
+    }
+
+    /// resolve the identity keypair from IDENTITY env variable or identity_keypair config
+    pub fn resolve_identity(&self) -> anyhow::Result<Option<Keypair>> {
+        resolve_identity_keypair(self.identity_keypair.as_deref())
+    }
+
+    /// the identity from [Self::resolve_identity] followed by the identity_keypairs; fails on duplicates
+    pub fn resolve_identities(&self) -> anyhow::Result<Vec<Keypair>> {
+        let mut identities = self.resolve_identity()?.into_iter().collect_vec();
+        for (index, identity_keypair) in self.identity_keypairs.iter().enumerate() {
+            let identity = parse_identity_keypair(identity_keypair)
+                .with_context(|| format!("Invalid identity_keypairs[{index}]"))?;
+            if identities
+                .iter()
+                .any(|other| other.pubkey() == identity.pubkey())
+            {
+                bail!(
+                    "identity {} is configured more than once",
+                    identity.pubkey()
+                );
+            }
+            identities.push(identity);
+        }
+        Ok(identities)
+    }
+
+    /// check the effective configuration (after env overrides) for values which would fail at runtime
+    pub fn validate(&self) -> anyhow::Result<()> {
+        validate_socket_addr("lite_rpc_http_addr", &self.lite_rpc_http_addr)?;
+        validate_socket_addr("lite_rpc_ws_addr", &self.lite_rpc_ws_addr)?;
+        validate_socket_addr("prometheus_addr", &self.prometheus_addr)?;
+        validate_socket_addr("health_addr", &self.health_addr)?;
+
+        if self.health_max_slot_age_ms == 0 {
+            bail!("health_max_slot_age_ms must be greater than 0");
+        }
+
+        validate_url("rpc_addr", &self.rpc_addr)?;
+        for rpc_fallback_addr in &self.rpc_fallback_addrs {
+            validate_url("rpc_fallback_addrs", rpc_fallback_addr)?;
+        }
+        if !self.rpc_fallback_addrs.is_empty() && self.rpc_failover_max_latency_ms == 0 {
+            bail!("rpc_failover_max_latency_ms must be greater than 0");
+        }
+        validate_url("ws_addr", &self.ws_addr)?;
+
+        if let Some(quic_proxy_addr) = &self.quic_proxy_addr {
+            quic_proxy_addr.to_socket_addrs().with_context(|| {
+                format!("invalid quic_proxy_addr '{quic_proxy_addr}' - expected host:port")
+            })?;
+        }
+
+        if self.fanout_size == 0 {
+            bail!("fanout_size must be greater than 0");
+        }
+
+        if self.maximum_retries_per_tx > MAXIMUM_RETRIES_PER_TX_LIMIT {
+            bail!(
+                "maximum_retries_per_tx is {} but must not exceed {}",
+                self.maximum_retries_per_tx,
+                MAXIMUM_RETRIES_PER_TX_LIMIT
+            );
+        }
+
+        if let Some(multiplier) = self.transaction_retry_backoff_multiplier {
+            if !multiplier.is_finite() || multiplier < 1.0 {
+                bail!(
+                    "transaction_retry_backoff_multiplier is {} but must be at least 1.0",
+                    multiplier
+                );
+            }
+        }
+
+        if let Some(max_backoff_secs) = self.transaction_retry_max_backoff_secs {
+            if max_backoff_secs < self.transaction_retry_after_secs {
+                bail!(
+                    "transaction_retry_max_backoff_secs is {} but must not be less than transaction_retry_after_secs {}",
+                    max_backoff_secs,
+                    self.transaction_retry_after_secs
+                );
+            }
+        }
+
+        if self.use_grpc {
+            let grpc_sources = self.get_grpc_sources();
+            if grpc_sources.iter().all(|s| s.addr.trim().is_empty()) {
+                bail!("use_grpc is enabled but grpc_addr is empty");
+            }
+            for grpc_source in grpc_sources {
+                validate_url("grpc_addr", &grpc_source.addr)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn lite_rpc_ws_addr() -> String {
         "[::]:8891".to_string()
     }
@@ -243,6 +466,10 @@ impl Config {
         DEFAULT_RPC_ADDR.to_string()
     }
 
+    pub const fn default_rpc_failover_max_latency_ms() -> u64 {
+        DEFAULT_RPC_FAILOVER_MAX_LATENCY_MS
+    }
+
     pub fn default_ws_addr() -> String {
         DEFAULT_WS_ADDR.to_string()
     }
@@ -251,6 +478,10 @@ impl Config {
         "[::]:8891".to_string()
     }
 
+    pub const fn default_ws_ping_interval_secs() -> u64 {
+        DEFAULT_WS_PING_INTERVAL_SECS
+    }
+
     pub const fn default_fanout_size() -> u64 {
         DEFAULT_FANOUT_SIZE
     }
@@ -259,6 +490,14 @@ impl Config {
         "[::]:9091".to_string()
     }
 
+    pub fn default_health_addr() -> String {
+        "[::]:8892".to_string()
+    }
+
+    pub const fn default_health_max_slot_age_ms() -> u64 {
+        DEFAULT_HEALTH_MAX_SLOT_AGE_MS
+    }
+
     pub const fn default_maximum_retries_per_tx() -> usize {
         MAX_RETRIES
     }
@@ -267,11 +506,42 @@ impl Config {
         DEFAULT_RETRY_TIMEOUT
     }
 
+    /// resend policy for transactions which are not confirmed
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retry_after: Duration::from_secs(self.transaction_retry_after_secs),
+            max_retries: self.maximum_retries_per_tx,
+            backoff_multiplier: self.transaction_retry_backoff_multiplier,
+            max_backoff: self
+                .transaction_retry_max_backoff_secs
+                .map(Duration::from_secs),
+        }
+    }
+
     pub fn default_grpc_addr() -> String {
         DEFAULT_GRPC_ADDR.to_string()
     }
 
     pub fn get_grpc_sources(&self) -> Vec<GrpcSource> {
+        if self.grpc_sources.is_empty() {
+            self.legacy_grpc_sources()
+        } else {
+            self.grpc_sources.clone()
+        }
+    }
+
+    pub fn get_grpc_source_configs(
+        &self,
+        timeouts: GrpcConnectionTimeouts,
+    ) -> Vec<GrpcSourceConfig> {
+        self.get_grpc_sources()
+            .into_iter()
+            .map(|s| GrpcSourceConfig::new(s.addr, s.x_token, None, timeouts.clone()))
+            .collect()
+    }
+
+    /// sources configured with the single grpc_addr/grpc_x_token pairs
+    fn legacy_grpc_sources(&self) -> Vec<GrpcSource> {
         let mut sources: Vec<GrpcSource> = vec![];
 
         sources.push(GrpcSource {
@@ -304,9 +574,67 @@ impl Config {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// detect format from file extension; defaults to json
+    pub fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+// anything above is most likely a misconfiguration
+const MAXIMUM_RETRIES_PER_TX_LIMIT: usize = 1000;
+
+/// parse an optional environment variable; the error names the variable if the value is invalid
+fn env_var_parsed<T>(name: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .parse::<T>()
+            .map(Some)
+            .with_context(|| format!("invalid value '{value}' for environment variable {name}")),
+        Err(_) => Ok(None),
+    }
+}
+
+fn validate_socket_addr(field: &str, addr: &str) -> anyhow::Result<()> {
+    SocketAddr::from_str(addr).with_context(|| {
+        format!("invalid {field} '{addr}' - expected socket address like [::]:8890")
+    })?;
+    Ok(())
+}
+
+fn validate_url(field: &str, url: &str) -> anyhow::Result<()> {
+    Url::parse(url).with_context(|| {
+        format!(
+            "invalid {field} '{}' - expected url like http://127.0.0.1:8899",
+            url_obfuscate_api_token(url)
+        )
+    })?;
+    Ok(())
+}
+
+#[derive(Clone, serde::Deserialize)]
 pub struct GrpcSource {
     pub addr: String,
+    #[serde(default)]
     pub x_token: Option<String>,
 }
 
@@ -353,45 +681,275 @@ fn obfuscate_token(token: &Option<String>) -> String {
     }
 }
 
-fn quic_params_from_environment() -> Option<QuicConnectionParameters> {
+const REDACTED: &str = "<redacted>";
+
+fn redact_token(token: &Option<String>) -> Option<String> {
+    token.as_ref().map(|_| REDACTED.to_string())
+}
+
+fn quic_params_from_environment() -> anyhow::Result<Option<QuicConnectionParameters>> {
     let mut quic_connection_parameters = QuicConnectionParameters::default();
 
-    quic_connection_parameters.connection_timeout = env::var("QUIC_CONNECTION_TIMEOUT_MILLIS")
-        .map(|millis| Duration::from_millis(millis.parse().unwrap()))
-        .unwrap_or(quic_connection_parameters.connection_timeout);
+    quic_connection_parameters.connection_timeout =
+        env_var_parsed("QUIC_CONNECTION_TIMEOUT_MILLIS")?
+            .map(Duration::from_millis)
+            .unwrap_or(quic_connection_parameters.connection_timeout);
 
-    quic_connection_parameters.unistream_timeout = env::var("QUIC_UNISTREAM_TIMEOUT_MILLIS")
-        .map(|millis| Duration::from_millis(millis.parse().unwrap()))
+    quic_connection_parameters.unistream_timeout = env_var_parsed("QUIC_UNISTREAM_TIMEOUT_MILLIS")?
+        .map(Duration::from_millis)
         .unwrap_or(quic_connection_parameters.unistream_timeout);
 
-    quic_connection_parameters.write_timeout = env::var("QUIC_WRITE_TIMEOUT_MILLIS")
-        .map(|millis| Duration::from_millis(millis.parse().unwrap()))
+    quic_connection_parameters.write_timeout = env_var_parsed("QUIC_WRITE_TIMEOUT_MILLIS")?
+        .map(Duration::from_millis)
         .unwrap_or(quic_connection_parameters.write_timeout);
 
-    quic_connection_parameters.finalize_timeout = env::var("QUIC_FINALIZE_TIMEOUT_MILLIS")
-        .map(|millis| Duration::from_millis(millis.parse().unwrap()))
+    quic_connection_parameters.finalize_timeout = env_var_parsed("QUIC_FINALIZE_TIMEOUT_MILLIS")?
+        .map(Duration::from_millis)
         .unwrap_or(quic_connection_parameters.finalize_timeout);
 
-    quic_connection_parameters.connection_retry_count = env::var("QUIC_CONNECTION_RETRY_COUNT")
-        .map(|millis| millis.parse().unwrap())
-        .unwrap_or(quic_connection_parameters.connection_retry_count);
+    quic_connection_parameters.connection_retry_count =
+        env_var_parsed("QUIC_CONNECTION_RETRY_COUNT")?
+            .unwrap_or(quic_connection_parameters.connection_retry_count);
 
     quic_connection_parameters.max_number_of_connections =
-        env::var("QUIC_MAX_NUMBER_OF_CONNECTIONS")
-            .map(|millis| millis.parse().unwrap())
+        env_var_parsed("QUIC_MAX_NUMBER_OF_CONNECTIONS")?
             .unwrap_or(quic_connection_parameters.max_number_of_connections);
 
     quic_connection_parameters.number_of_transactions_per_unistream =
-        env::var("QUIC_NUMBER_OF_TRANSACTIONS_PER_TASK")
-            .map(|millis| millis.parse().unwrap())
+        env_var_parsed("QUIC_NUMBER_OF_TRANSACTIONS_PER_TASK")?
             .unwrap_or(quic_connection_parameters.number_of_transactions_per_unistream);
 
-    quic_connection_parameters.unistreams_to_create_new_connection_in_percentage =
-        env::var("QUIC_PERCENTAGE_TO_CREATE_NEW_CONNECTION")
-            .map(|millis| millis.parse().unwrap())
-            .unwrap_or(
-                quic_connection_parameters.unistreams_to_create_new_connection_in_percentage,
+    quic_connection_parameters.unistreams_to_create_new_connection_in_percentage = env_var_parsed(
+        "QUIC_PERCENTAGE_TO_CREATE_NEW_CONNECTION",
+    )?
+    .unwrap_or(quic_connection_parameters.unistreams_to_create_new_connection_in_percentage);
+
+    Ok(Some(quic_connection_parameters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_config(json: &str) -> Config {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let config = parse_config("{}");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_socket_addr() {
+        let config = parse_config(r#"{ "lite_rpc_http_addr": "localhost" }"#);
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("lite_rpc_http_addr"));
+    }
+
+    #[test]
+    fn test_zero_fanout_size() {
+        let config = parse_config(r#"{ "fanout_size": 0 }"#);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_too_many_retries() {
+        let config = parse_config(r#"{ "maximum_retries_per_tx": 1000000 }"#);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_retry_policy_resend_schedule() {
+        let config = parse_config(
+            r#"{
+                "maximum_retries_per_tx": 5,
+                "transaction_retry_after_secs": 2,
+                "transaction_retry_backoff_multiplier": 2.0,
+                "transaction_retry_max_backoff_secs": 20
+            }"#,
+        );
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.retry_policy().resend_schedule(),
+            [2, 4, 8, 16, 20, 20].map(Duration::from_secs)
+        );
+
+        // linear without multiplier
+        let config =
+            parse_config(r#"{ "maximum_retries_per_tx": 3, "transaction_retry_after_secs": 3 }"#);
+        assert_eq!(
+            config.retry_policy().resend_schedule(),
+            [3, 3, 6, 9].map(Duration::from_secs)
+        );
+    }
+
+    #[test]
+    fn test_invalid_retry_backoff() {
+        let config = parse_config(r#"{ "transaction_retry_backoff_multiplier": 0.5 }"#);
+        assert!(config.validate().is_err());
+
+        let config = parse_config(
+            r#"{ "transaction_retry_after_secs": 10, "transaction_retry_max_backoff_secs": 5 }"#,
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_multiple_identities() {
+        let identity_a = Keypair::new();
+        let identity_b = Keypair::new();
+        let config = parse_config(&format!(
+            r#"{{
+                "identity_keypairs": ["{}", "{}"],
+                "identity_selection": "stake_weighted"
+            }}"#,
+            identity_a.to_base58_string(),
+            identity_b.to_base58_string()
+        ));
+        assert_eq!(
+            config.identity_selection,
+            IdentitySelectionStrategy::StakeWeighted
+        );
+        // IDENTITY env variable would take precedence over identity_keypair
+        if env::var("IDENTITY").is_err() {
+            let identities = config.resolve_identities().unwrap();
+            assert_eq!(
+                identities.iter().map(|k| k.pubkey()).collect_vec(),
+                vec![identity_a.pubkey(), identity_b.pubkey()]
             );
+        }
+        assert!(config
+            .redacted()
+            .identity_keypairs
+            .iter()
+            .all(|identity| identity == REDACTED));
+
+        let config = parse_config(&format!(
+            r#"{{ "identity_keypairs": ["{0}", "{0}"] }}"#,
+            identity_a.to_base58_string()
+        ));
+        assert!(config.resolve_identities().is_err());
+
+        let config = parse_config("{}");
+        assert_eq!(
+            config.identity_selection,
+            IdentitySelectionStrategy::RoundRobin
+        );
+    }
+
+    #[test]
+    fn test_use_grpc_requires_grpc_addr() {
+        let config = parse_config(r#"{ "use_grpc": true, "grpc_addr": "" }"#);
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("grpc_addr"));
+    }
+
+    #[test]
+    fn test_parse_multiple_grpc_sources() {
+        let config = parse_config(
+            r#"{
+                "use_grpc": true,
+                "grpc_sources": [
+                    { "addr": "http://127.0.0.1:10000", "x_token": "token1" },
+                    { "addr": "http://127.0.0.1:10001" }
+                ]
+            }"#,
+        );
+        let sources = config.get_grpc_sources();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].addr, "http://127.0.0.1:10000");
+        assert_eq!(sources[0].x_token.as_deref(), Some("token1"));
+        assert_eq!(sources[1].addr, "http://127.0.0.1:10001");
+        assert_eq!(sources[1].x_token, None);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_single_grpc_addr_is_one_element_list() {
+        let config = parse_config(r#"{ "grpc_addr": "http://127.0.0.1:10000" }"#);
+        let sources = config.get_grpc_sources();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].addr, "http://127.0.0.1:10000");
+    }
 
-    Some(quic_connection_parameters)
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(ConfigFormat::from_path("config.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("config.yaml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("config.YML"), ConfigFormat::Yaml);
+        assert_eq!(
+            ConfigFormat::from_path("/etc/config.toml"),
+            ConfigFormat::Toml
+        );
+        assert_eq!(ConfigFormat::from_path("config"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("config.txt"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_json_yaml_toml_equal() {
+        let json = r#"{
+            "rpc_addr": "http://127.0.0.1:8899",
+            "fanout_size": 12,
+            "use_grpc": true,
+            "grpc_sources": [
+                { "addr": "http://127.0.0.1:10000", "x_token": "token1" },
+                { "addr": "http://127.0.0.1:10001" }
+            ]
+        }"#;
+        let yaml = r#"
+rpc_addr: "http://127.0.0.1:8899"
+fanout_size: 12
+use_grpc: true
+grpc_sources:
+  - addr: "http://127.0.0.1:10000"
+    x_token: "token1"
+  - addr: "http://127.0.0.1:10001"
+"#;
+        let toml = r#"
+rpc_addr = "http://127.0.0.1:8899"
+fanout_size = 12
+use_grpc = true
+
+[[grpc_sources]]
+addr = "http://127.0.0.1:10000"
+x_token = "token1"
+
+[[grpc_sources]]
+addr = "http://127.0.0.1:10001"
+"#;
+        let from_json = Config::parse(ConfigFormat::Json, json).unwrap();
+        let from_yaml = Config::parse(ConfigFormat::Yaml, yaml).unwrap();
+        let from_toml = Config::parse(ConfigFormat::Toml, toml).unwrap();
+
+        assert_eq!(from_json.fanout_size, 12);
+        assert_eq!(from_json.get_grpc_sources().len(), 2);
+        assert_eq!(format!("{from_json:?}"), format!("{from_yaml:?}"));
+        assert_eq!(format!("{from_json:?}"), format!("{from_toml:?}"));
+    }
+
+    #[test]
+    fn test_redacted_config() {
+        let config = parse_config(
+            r#"{
+                "grpc_x_token": "supersecrettoken",
+                "grpc_sources": [{ "addr": "http://127.0.0.1:10000", "x_token": "othersecret" }],
+                "postgres": { "pg_config": "host=localhost user=literpc password=pgsecret", "ssl": null }
+            }"#,
+        );
+        let printed = format!("{:?}", config.redacted());
+        assert!(!printed.contains("supersecrettoken"));
+        assert!(!printed.contains("othersecret"));
+        assert!(!printed.contains("pgsecret"));
+        assert!(printed.contains("user=literpc"));
+    }
+
+    #[test]
+    fn test_invalid_env_var_names_variable() {
+        env::set_var("LITE_RPC_TEST_INVALID_NUMBER", "abc");
+        let err = env_var_parsed::<u64>("LITE_RPC_TEST_INVALID_NUMBER").unwrap_err();
+        assert!(err.to_string().contains("LITE_RPC_TEST_INVALID_NUMBER"));
+        env::remove_var("LITE_RPC_TEST_INVALID_NUMBER");
+    }
 }
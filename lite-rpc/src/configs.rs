@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use solana_lite_rpc_core::encoding::BinaryEncoding;
+use solana_rpc_client_api::config::RpcSendTransactionConfig;
 use solana_sdk::commitment_config::CommitmentLevel;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -15,6 +16,16 @@ pub struct SendTransactionConfig {
     //    pub min_context_slot: Option<Slot>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendTransactionWithConfirmationConfig {
+    /// encoding defaults to base64
+    #[serde(flatten)]
+    pub send_transaction_config: RpcSendTransactionConfig,
+    /// how long to wait for the transaction to be observed in a block; capped at 60s, defaults to 15s
+    pub timeout_ms: Option<u64>,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IsBlockHashValidConfig {
@@ -0,0 +1,238 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{debug, error, warn};
+use solana_lite_rpc_core::types::SlotStream;
+use solana_lite_rpc_core::AnyhowJoinHandle;
+use solana_sdk::slot_history::Slot;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::broadcast::error::RecvError;
+
+/// tracks when the last slot notification was received to tell if the upstream source is flowing
+#[derive(Clone, Default)]
+pub struct SlotHealth {
+    last_slot: Arc<Mutex<Option<(Slot, Instant)>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    pub last_slot: Option<Slot>,
+    pub last_slot_age: Option<Duration>,
+}
+
+impl HealthStatus {
+    fn to_json(&self) -> String {
+        serde_json::json!({
+            "status": if self.healthy { "ok" } else { "stale" },
+            "last_slot": self.last_slot,
+            "last_slot_age_ms": self.last_slot_age.map(|age| age.as_millis() as u64),
+        })
+        .to_string()
+    }
+}
+
+impl SlotHealth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_slot(&self, slot: Slot) {
+        *self.last_slot.lock().unwrap() = Some((slot, Instant::now()));
+    }
+
+    /// healthy if the last slot notification is younger than `max_slot_age`
+    pub fn status(&self, max_slot_age: Duration) -> HealthStatus {
+        match *self.last_slot.lock().unwrap() {
+            Some((slot, received_at)) => {
+                let age = received_at.elapsed();
+                HealthStatus {
+                    healthy: age < max_slot_age,
+                    last_slot: Some(slot),
+                    last_slot_age: Some(age),
+                }
+            }
+            None => HealthStatus {
+                healthy: false,
+                last_slot: None,
+                last_slot_age: None,
+            },
+        }
+    }
+
+    pub fn start_slot_tracker(&self, mut slot_stream: SlotStream) -> AnyhowJoinHandle {
+        let slot_health = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match slot_stream.recv().await {
+                    Ok(slot_notification) => {
+                        slot_health.record_slot(slot_notification.processed_slot)
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("health check lagged behind slot stream by {skipped} messages");
+                    }
+                    Err(RecvError::Closed) => {
+                        anyhow::bail!("slot stream closed - stopping health check slot tracker");
+                    }
+                }
+            }
+        })
+    }
+}
+
+// a client which does not send its request or read the response in time gets disconnected
+const HEALTH_CHECK_IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct HealthCheck;
+
+impl HealthCheck {
+    fn create_response(status: &HealthStatus) -> String {
+        let payload = status.to_json();
+        let status_line = if status.healthy {
+            "200 OK"
+        } else {
+            "503 Service Unavailable"
+        };
+        format!(
+            "HTTP/1.1 {status_line}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            payload.len(),
+            payload
+        )
+    }
+
+    fn create_not_found_response() -> String {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string()
+    }
+
+    async fn handle_stream(
+        stream: &mut TcpStream,
+        slot_health: &SlotHealth,
+        max_slot_age: Duration,
+    ) -> anyhow::Result<()> {
+        let mut request_buffer = [0u8; 1024];
+        let nb_read =
+            tokio::time::timeout(HEALTH_CHECK_IO_TIMEOUT, stream.read(&mut request_buffer))
+                .await??;
+        let request = String::from_utf8_lossy(&request_buffer[..nb_read]);
+
+        // e.g. GET /health HTTP/1.1
+        let path = request.split_whitespace().nth(1).unwrap_or_default();
+        let response = if path == "/health" {
+            Self::create_response(&slot_health.status(max_slot_age))
+        } else {
+            Self::create_not_found_response()
+        };
+
+        tokio::time::timeout(HEALTH_CHECK_IO_TIMEOUT, async {
+            stream.writable().await?;
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub fn serve(
+        addr: impl ToSocketAddrs + Send + 'static,
+        slot_health: SlotHealth,
+        max_slot_age: Duration,
+    ) -> AnyhowJoinHandle {
+        tokio::spawn(async move {
+            let listener = TcpListener::bind(addr).await?;
+            Self::accept_connections(listener, slot_health, max_slot_age).await
+        })
+    }
+
+    async fn accept_connections(
+        listener: TcpListener,
+        slot_health: SlotHealth,
+        max_slot_age: Duration,
+    ) -> anyhow::Result<()> {
+        loop {
+            let Ok((mut stream, _addr)) = listener.accept().await else {
+                error!("Error accepting health check stream");
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                continue;
+            };
+
+            // one task per connection so a slow client does not block the others
+            let slot_health = slot_health.clone();
+            tokio::spawn(async move {
+                if let Err(err) = Self::handle_stream(&mut stream, &slot_health, max_slot_age).await
+                {
+                    debug!("Error handling health check request: {err:?}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_lite_rpc_core::structures::slot_notification::SlotNotification;
+
+    #[tokio::test]
+    async fn test_health_flips_at_staleness_threshold() {
+        let max_slot_age = Duration::from_millis(200);
+        let slot_health = SlotHealth::new();
+        assert!(!slot_health.status(max_slot_age).healthy);
+
+        let (slot_sender, slot_stream) = tokio::sync::broadcast::channel(16);
+        let _jh_tracker = slot_health.start_slot_tracker(slot_stream);
+
+        slot_sender
+            .send(SlotNotification {
+                processed_slot: 42,
+                estimated_processed_slot: 42,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let status = slot_health.status(max_slot_age);
+        assert!(status.healthy);
+        assert_eq!(status.last_slot, Some(42));
+        assert!(HealthCheck::create_response(&status).starts_with("HTTP/1.1 200 OK"));
+
+        tokio::time::sleep(max_slot_age).await;
+
+        let status = slot_health.status(max_slot_age);
+        assert!(!status.healthy);
+        assert_eq!(status.last_slot, Some(42));
+        assert!(status.last_slot_age.unwrap() >= max_slot_age);
+        let response = HealthCheck::create_response(&status);
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+        assert!(response.contains("\"last_slot_age_ms\""));
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_does_not_block_health_check() {
+        // any free port
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let slot_health = SlotHealth::new();
+        slot_health.record_slot(42);
+        let _jh_server = tokio::spawn(HealthCheck::accept_connections(
+            listener,
+            slot_health,
+            Duration::from_secs(60),
+        ));
+
+        // connected but never sends a request
+        let _idle_stream = TcpStream::connect(addr).await.unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        tokio::time::timeout(Duration::from_secs(1), stream.read_to_string(&mut response))
+            .await
+            .expect("health check must not wait for the idle connection")
+            .unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -1,14 +1,24 @@
 use async_trait::async_trait;
 use jsonrpsee::{SubscriptionMessage, SubscriptionSink};
 use solana_rpc_client_api::response::{Response as RpcResponse, RpcResponseContext};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ws_keepalive::{send_to_subscriber, SubscriberSendError, WsKeepalive};
 
 pub struct JsonRpseeSubscriptionHandlerSink {
     jsonrpsee_sink: SubscriptionSink,
+    keepalive: Option<WsKeepalive>,
+    // set once a send exceeded the keepalive timeout; the sink is reported closed from then on
+    stale: AtomicBool,
 }
 
 impl JsonRpseeSubscriptionHandlerSink {
-    pub fn new(jsonrpsee_sink: SubscriptionSink) -> Self {
-        Self { jsonrpsee_sink }
+    pub fn new(jsonrpsee_sink: SubscriptionSink, keepalive: Option<WsKeepalive>) -> Self {
+        Self {
+            jsonrpsee_sink,
+            keepalive,
+            stale: AtomicBool::new(false),
+        }
     }
 }
 
@@ -17,22 +27,26 @@ impl solana_lite_rpc_core::traits::subscription_sink::SubscriptionSink
     for JsonRpseeSubscriptionHandlerSink
 {
     async fn send(&self, slot: solana_sdk::slot_history::Slot, message: serde_json::Value) {
-        let _ = self
-            .jsonrpsee_sink
-            .send(
-                SubscriptionMessage::from_json(&RpcResponse {
-                    context: RpcResponseContext {
-                        slot,
-                        api_version: None,
-                    },
-                    value: message,
-                })
-                .unwrap(),
-            )
-            .await;
+        if self.stale.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let send = self.jsonrpsee_sink.send(
+            SubscriptionMessage::from_json(&RpcResponse {
+                context: RpcResponseContext {
+                    slot,
+                    api_version: None,
+                },
+                value: message,
+            })
+            .unwrap(),
+        );
+        if let Err(SubscriberSendError::Stale(_)) = send_to_subscriber(send, self.keepalive).await {
+            self.stale.store(true, Ordering::Relaxed);
+        }
     }
 
     fn is_closed(&self) -> bool {
-        self.jsonrpsee_sink.is_closed()
+        self.stale.load(Ordering::Relaxed) || self.jsonrpsee_sink.is_closed()
     }
 }
@@ -1,24 +1,33 @@
 use const_env::from_env;
 use solana_transaction_status::TransactionConfirmationStatus;
 
+pub mod block_subscription;
 pub mod bridge;
 pub mod bridge_pubsub;
 pub mod cli;
 pub mod configs;
 pub mod errors;
+pub mod health_check;
 pub mod jsonrpsee_subscrption_handler_sink;
 pub mod postgres_logger;
 pub mod rpc;
 pub mod rpc_errors;
+pub mod rpc_failover;
 pub mod rpc_pubsub;
 pub mod service_spawner;
 pub mod start_server;
+pub mod transaction_confirmation;
+pub mod ws_keepalive;
 
 #[from_env]
 pub const DEFAULT_RPC_ADDR: &str = "http://0.0.0.0:8899";
 #[from_env]
 pub const DEFAULT_WS_ADDR: &str = "ws://0.0.0.0:8900";
 
+// getHealth roundtrip above which an RPC endpoint is failed over
+#[from_env]
+pub const DEFAULT_RPC_FAILOVER_MAX_LATENCY_MS: u64 = 1_000;
+
 #[from_env]
 pub const DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE: usize = 200_000;
 
@@ -31,6 +40,14 @@ pub const MAX_RETRIES: usize = 40;
 
 pub const DEFAULT_RETRY_TIMEOUT: u64 = 3;
 
+// slots are produced every ~400ms
+#[from_env]
+pub const DEFAULT_HEALTH_MAX_SLOT_AGE_MS: u64 = 10_000;
+
+// below the common idle timeout of 60s of load balancers and proxies
+#[from_env]
+pub const DEFAULT_WS_PING_INTERVAL_SECS: u64 = 30;
+
 #[from_env]
 pub const DEFAULT_CLEAN_INTERVAL_MS: u64 = 5 * 60 * 1000; // five minute
 pub const DEFAULT_TRANSACTION_CONFIRMATION_STATUS: TransactionConfirmationStatus =
@@ -2,14 +2,19 @@ pub mod rpc_tester;
 
 use crate::rpc_tester::RpcTester;
 use anyhow::bail;
+use clap::Parser;
 use dashmap::DashMap;
-use itertools::Itertools;
 use lite_rpc::bridge::LiteBridge;
 use lite_rpc::bridge_pubsub::LitePubSubBridge;
-use lite_rpc::cli::Config;
+use lite_rpc::cli::{Args, Command, Config};
+use lite_rpc::health_check::{HealthCheck, SlotHealth};
 use lite_rpc::postgres_logger::PostgresLogger;
+use lite_rpc::rpc_failover::{
+    create_failover_rpc_client, run_rpc_health_checks, RpcEndpointSelector,
+};
 use lite_rpc::service_spawner::ServiceSpawner;
-use lite_rpc::start_server::start_servers;
+use lite_rpc::start_server::{start_servers, ServerConfiguration};
+use lite_rpc::ws_keepalive::WsKeepalive;
 use lite_rpc::DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE;
 use log::info;
 use solana_lite_rpc_accounts::account_service::AccountService;
@@ -20,9 +25,7 @@ use solana_lite_rpc_address_lookup_tables::address_lookup_table_store::AddressLo
 use solana_lite_rpc_blockstore::history::History;
 use solana_lite_rpc_cluster_endpoints::endpoint_stremers::EndpointStreaming;
 
-use solana_lite_rpc_cluster_endpoints::geyser_grpc_connector::{
-    GrpcConnectionTimeouts, GrpcSourceConfig,
-};
+use solana_lite_rpc_cluster_endpoints::geyser_grpc_connector::GrpcConnectionTimeouts;
 use solana_lite_rpc_cluster_endpoints::grpc_inspect::{
     debugtask_blockstream_confirmation_sequence, debugtask_blockstream_slot_progression,
 };
@@ -30,7 +33,6 @@ use solana_lite_rpc_cluster_endpoints::grpc_subscription::create_grpc_subscripti
 use solana_lite_rpc_cluster_endpoints::json_rpc_leaders_getter::JsonRpcLeaderGetter;
 use solana_lite_rpc_cluster_endpoints::json_rpc_subscription::create_json_rpc_polling_subscription;
 use solana_lite_rpc_cluster_endpoints::rpc_polling::poll_blocks::NUM_PARALLEL_TASKS_DEFAULT;
-use solana_lite_rpc_core::keypair_loader::load_identity_keypair;
 use solana_lite_rpc_core::stores::{
     block_information_store::{BlockInformation, BlockInformationStore},
     cluster_info_store::ClusterInfo,
@@ -41,7 +43,9 @@ use solana_lite_rpc_core::stores::{
 use solana_lite_rpc_core::structures::account_filter::AccountFilters;
 use solana_lite_rpc_core::structures::leaderschedule::CalculatedSchedule;
 use solana_lite_rpc_core::structures::{
-    epoch::EpochCache, identity_stakes::IdentityStakes, notifications::NotificationSender,
+    epoch::EpochCache,
+    identity_stakes::{IdentityStakes, StakeCache},
+    notifications::NotificationSender,
 };
 use solana_lite_rpc_core::traits::address_lookup_table_interface::AddressLookupTableInterface;
 use solana_lite_rpc_core::types::BlockStream;
@@ -49,6 +53,7 @@ use solana_lite_rpc_core::utils::wait_till_block_of_commitment_is_recieved;
 use solana_lite_rpc_core::AnyhowJoinHandle;
 use solana_lite_rpc_prioritization_fees::account_prio_service::AccountPrioService;
 use solana_lite_rpc_services::data_caching_service::DataCachingService;
+use solana_lite_rpc_services::tpu_utils::identity_selector::IdentitySelector;
 use solana_lite_rpc_services::tpu_utils::tpu_connection_path::TpuConnectionPath;
 use solana_lite_rpc_services::tpu_utils::tpu_service::{TpuService, TpuServiceConfig};
 use solana_lite_rpc_services::transaction_replayer::TransactionReplayer;
@@ -99,15 +104,36 @@ pub async fn start_postgres(
 pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow::Result<()> {
     let grpc_sources = args.get_grpc_sources();
     log::info!("grpc_sources:{grpc_sources:?}");
+    let timeouts = GrpcConnectionTimeouts {
+        connect_timeout: Duration::from_secs(15),
+        request_timeout: Duration::from_secs(15),
+        subscribe_timeout: Duration::from_secs(15),
+        receive_timeout: Duration::from_secs(15),
+    };
+
+    let gprc_sources = args.get_grpc_source_configs(timeouts);
+    let mut identities: Vec<Arc<Keypair>> = args
+        .resolve_identities()?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+    if identities.is_empty() {
+        identities.push(Arc::new(Keypair::new()));
+    }
+    let validator_identity = identities[0].pubkey();
+    let identity_selection = args.identity_selection;
+    let retry_policy = args.retry_policy();
+    info!("Transaction retry policy: {:?}", retry_policy);
+
     let Config {
         lite_rpc_ws_addr,
+        ws_ping_interval_secs,
         lite_rpc_http_addr,
         fanout_size,
         postgres,
         prometheus_addr,
-        identity_keypair,
-        maximum_retries_per_tx,
-        transaction_retry_after_secs,
+        health_addr,
+        health_max_slot_age_ms,
         quic_proxy_addr,
         use_grpc,
         enable_grpc_stream_inspection,
@@ -119,14 +145,6 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         ..
     } = args;
 
-    let validator_identity = Arc::new(
-        load_identity_keypair(identity_keypair)
-            .await?
-            .unwrap_or_else(Keypair::new),
-    );
-
-    let retry_after = Duration::from_secs(transaction_retry_after_secs);
-
     let tpu_connection_path = configure_tpu_connection_path(quic_proxy_addr);
 
     let account_filters = if let Some(account_filters) = account_filters {
@@ -144,28 +162,11 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         log::info!("Accounts on demand service is disabled");
     }
 
-    let timeouts = GrpcConnectionTimeouts {
-        connect_timeout: Duration::from_secs(15),
-        request_timeout: Duration::from_secs(15),
-        subscribe_timeout: Duration::from_secs(15),
-        receive_timeout: Duration::from_secs(15),
-    };
-
-    let gprc_sources = grpc_sources
-        .iter()
-        .map(|s| GrpcSourceConfig::new(s.addr.clone(), s.x_token.clone(), None, timeouts.clone()))
-        .collect_vec();
-
     let (subscriptions, cluster_endpoint_tasks) = if use_grpc {
         info!("Creating geyser subscription...");
         create_grpc_subscription(
             rpc_client.clone(),
-            grpc_sources
-                .iter()
-                .map(|s| {
-                    GrpcSourceConfig::new(s.addr.clone(), s.x_token.clone(), None, timeouts.clone())
-                })
-                .collect(),
+            gprc_sources.clone(),
             account_filters.clone(),
         )?
     } else {
@@ -243,7 +244,7 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
     let data_cache = DataCache {
         block_information_store,
         cluster_info: ClusterInfo::default(),
-        identity_stakes: IdentityStakes::new(validator_identity.pubkey()),
+        identity_stakes: IdentityStakes::new(validator_identity),
         slot_cache: SlotCache::new(finalized_block_info.slot),
         tx_subs: SubscriptionStore::default(),
         txs: TxStore {
@@ -258,14 +259,19 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         clean_duration: Duration::from_secs(120),
     };
 
+    // stakes of all configured identities for the identity selection
+    let stake_cache = StakeCache::new();
+    let stake_cache_updater = stake_cache.start_updater(vote_account_notifier.resubscribe());
+
     // to avoid laggin we resubscribe to block notification
-    let data_caching_service = data_cache_service.listen(
+    let mut data_caching_service = data_cache_service.listen(
         blocks_notifier.resubscribe(),
         blockinfo_notifier.resubscribe(),
         slot_notifier.resubscribe(),
         cluster_info_notifier,
         vote_account_notifier,
     );
+    data_caching_service.push(stake_cache_updater);
 
     let (block_priofees_task, block_priofees_service) =
         start_block_priofees_task(blocks_notifier.resubscribe(), 100);
@@ -316,23 +322,31 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
     };
     //init grpc leader schedule and vote account is configured.
     let leader_schedule = Arc::new(JsonRpcLeaderGetter::new(rpc_client.clone(), 1024, 128));
-    let tpu_service: TpuService = TpuService::new(
+    if identities.len() > 1 {
+        info!(
+            "Sending transactions with {} identities selected {:?}",
+            identities.len(),
+            identity_selection
+        );
+    }
+    let identity_selector = IdentitySelector::new(identities, identity_selection, stake_cache);
+    let tpu_service: TpuService = TpuService::new_with_identities(
         tpu_config,
-        validator_identity,
+        identity_selector,
         leader_schedule,
         data_cache.clone(),
     )
     .await?;
     let tx_sender = TxSender::new(data_cache.clone(), tpu_service.clone());
     let tx_replayer =
-        TransactionReplayer::new(tpu_service.clone(), data_cache.clone(), retry_after);
+        TransactionReplayer::new(tpu_service.clone(), data_cache.clone(), retry_policy);
     let (transaction_service, tx_service_jh) = spawner.spawn_tx_service(
         tx_sender,
         tx_replayer,
         tpu_service,
         DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE,
         notification_channel.clone(),
-        maximum_retries_per_tx,
+        retry_policy.max_retries,
         slot_notifier.resubscribe(),
     );
 
@@ -351,6 +365,7 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         accounts_service.clone(),
     );
 
+    let ws_keepalive = WsKeepalive::from_ping_interval_secs(ws_ping_interval_secs);
     let pubsub_service = LitePubSubBridge::new(
         data_cache.clone(),
         block_priofees_service,
@@ -358,6 +373,15 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         blocks_notifier,
         blockinfo_notifier,
         accounts_service.clone(),
+        ws_keepalive,
+    );
+
+    let slot_health = SlotHealth::new();
+    let slot_tracker = slot_health.start_slot_tracker(slot_notifier.resubscribe());
+    let health_check = HealthCheck::serve(
+        health_addr,
+        slot_health,
+        Duration::from_millis(health_max_slot_age_ms),
     );
 
     let bridge_service = tokio::spawn(start_servers(
@@ -365,7 +389,10 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         pubsub_service,
         lite_rpc_ws_addr,
         lite_rpc_http_addr,
-        None,
+        Some(ServerConfiguration {
+            ws_keepalive,
+            ..ServerConfiguration::default()
+        }),
     ));
     drop(slot_notifier);
 
@@ -379,6 +406,12 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         res = bridge_service => {
             anyhow::bail!("Server {res:?}")
         }
+        res = health_check => {
+            anyhow::bail!("Health check server {res:?}")
+        }
+        res = slot_tracker => {
+            anyhow::bail!("Health check slot tracker {res:?}")
+        }
         // allow it to fail
         // res = block_priofees_task => {
         //     anyhow::bail!("Prio Fees Service {res:?}")
@@ -419,16 +452,51 @@ fn setup_grpc_stream_debugging(blocks_notifier: &BlockStream) {
 pub async fn main() -> anyhow::Result<()> {
     setup_tracing_subscriber();
 
-    let config = Config::load().await?;
+    let args = Args::parse();
+    let config = Config::load_with_args(&args).await?;
+
+    if let Some(Command::Validate) = args.command {
+        // fail if the identities cannot be resolved
+        config.resolve_identities()?;
+        println!(
+            "Config is valid - effective config:\n{:#?}",
+            config.redacted()
+        );
+        return Ok(());
+    }
 
     let ctrl_c_signal = tokio::signal::ctrl_c();
-    let Config { rpc_addr, .. } = &config;
+    let Config {
+        rpc_addr,
+        rpc_fallback_addrs,
+        ..
+    } = &config;
     // rpc client
-    let rpc_client = Arc::new(RpcClient::new(rpc_addr.clone()));
+    let mut rpc_health_checks_jh = None;
+    let rpc_client = if rpc_fallback_addrs.is_empty() {
+        Arc::new(RpcClient::new(rpc_addr.clone()))
+    } else {
+        let selector = RpcEndpointSelector::new(
+            rpc_addr.clone(),
+            rpc_fallback_addrs.clone(),
+            Duration::from_millis(config.rpc_failover_max_latency_ms),
+        );
+        info!("Use {} RPC fallback addresses", rpc_fallback_addrs.len());
+        rpc_health_checks_jh = Some(tokio::spawn(run_rpc_health_checks(selector.clone())));
+        Arc::new(create_failover_rpc_client(selector))
+    };
     let rpc_tester = tokio::spawn(RpcTester::new(rpc_client.clone()).start(config.use_grpc));
 
     info!("Use RPC address: {}", obfuscate_rpcurl(rpc_addr));
 
+    let rpc_health_checks = async move {
+        match rpc_health_checks_jh {
+            Some(jh) => jh.await,
+            // no fallback endpoints to fail over to
+            None => futures::future::pending().await,
+        }
+    };
+
     let main = start_lite_rpc(config, rpc_client);
 
     tokio::select! {
@@ -441,6 +509,11 @@ pub async fn main() -> anyhow::Result<()> {
             log::error!("Services quit unexpectedly {res:?}");
             bail!("Service quit unexpectedly {res:?}");
         }
+        res = rpc_health_checks => {
+            // endpoint selection would be stuck on the current endpoint
+            log::error!("RPC health checks quit unexpectedly {res:?}");
+            bail!("RPC health checks quit unexpectedly {res:?}");
+        }
         _ = ctrl_c_signal => {
             log::info!("Received ctrl+c signal");
             Ok(())
@@ -1,9 +1,12 @@
-use crate::configs::IsBlockHashValidConfig;
+use crate::configs::{IsBlockHashValidConfig, SendTransactionWithConfirmationConfig};
+use crate::transaction_confirmation::SendTransactionConfirmation;
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 use solana_account_decoder::UiAccount;
 use solana_lite_rpc_prioritization_fees::prioritization_fee_calculation_method::PrioritizationFeeCalculationMethod;
-use solana_lite_rpc_prioritization_fees::rpc_data::{AccountPrioFeesStats, PrioFeesStats};
+use solana_lite_rpc_prioritization_fees::rpc_data::{
+    AccountPrioFeesStats, PrioFeesStats, VersionedPrioFeesStats,
+};
 use solana_rpc_client_api::config::{
     RpcAccountInfoConfig, RpcBlocksConfigWrapper, RpcContextConfig, RpcGetVoteAccountsConfig,
     RpcLeaderScheduleConfig, RpcProgramAccountsConfig, RpcRequestAirdropConfig,
@@ -119,6 +122,8 @@ pub trait LiteRpc {
         config: Option<RpcSignatureStatusConfig>,
     ) -> RpcResult<RpcResponse<Vec<Option<TransactionStatus>>>>;
 
+    /// without accounts: median (by CU) prioritization fee per recent slot
+    /// with accounts: max p75 of global and account prioritization fees per account
     #[method(name = "getRecentPrioritizationFees")]
     async fn get_recent_prioritization_fees(
         &self,
@@ -136,6 +141,14 @@ pub trait LiteRpc {
         send_transaction_config: Option<RpcSendTransactionConfig>,
     ) -> RpcResult<String>;
 
+    /// same as sendTransaction but waits until the transaction was observed in a block or the timeout elapsed
+    #[method(name = "sendTransactionWithConfirmation")]
+    async fn send_transaction_with_confirmation(
+        &self,
+        tx: String,
+        config: Option<SendTransactionWithConfirmationConfig>,
+    ) -> RpcResult<SendTransactionConfirmation>;
+
     // ***********************
     // Deprecated
     // ***********************
@@ -183,6 +196,14 @@ pub trait LiteRpc {
         method: Option<PrioritizationFeeCalculationMethod>,
     ) -> RpcResult<RpcResponse<PrioFeesStats>>;
 
+    /// prio fees distribution aggregated over the recent blocks (default: all retained blocks);
+    /// the response carries a schema version clients can use to detect changes of the JSON shape
+    #[method(name = "getLiteRpcPrioFeesStats")]
+    async fn get_lite_rpc_priofees_stats(
+        &self,
+        nb_blocks: Option<usize>,
+    ) -> RpcResult<RpcResponse<VersionedPrioFeesStats>>;
+
     #[method(name = "getLatestAccountPrioFees")]
     async fn get_latest_account_priofees(
         &self,
@@ -0,0 +1,241 @@
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use prometheus::{opts, register_int_gauge_vec, IntGaugeVec};
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_rpc_client::http_sender::HttpSender;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client::rpc_client::RpcClientConfig;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::Result as ClientResult;
+use solana_rpc_client_api::request::RpcRequest;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+lazy_static::lazy_static! {
+    static ref SELECTED_RPC_ENDPOINT: IntGaugeVec =
+    register_int_gauge_vec!(opts!("literpc_rpc_selected_endpoint", "1 for the RPC endpoint requests are sent to, 0 for the other configured endpoints"), &["endpoint"]).unwrap();
+}
+
+const RPC_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// picks the RPC endpoint for the requests: the primary if healthy, otherwise the first healthy fallback
+#[derive(Clone)]
+pub struct RpcEndpointSelector {
+    // primary first
+    endpoints: Vec<String>,
+    max_latency: Duration,
+    selected: Arc<AtomicUsize>,
+}
+
+impl RpcEndpointSelector {
+    pub fn new(primary: String, fallbacks: Vec<String>, max_latency: Duration) -> Self {
+        let endpoints = std::iter::once(primary)
+            .chain(fallbacks)
+            .collect::<Vec<_>>();
+        for endpoint in &endpoints {
+            SELECTED_RPC_ENDPOINT
+                .with_label_values(&[&obfuscate_rpcurl(endpoint)])
+                .set(0);
+        }
+        SELECTED_RPC_ENDPOINT
+            .with_label_values(&[&obfuscate_rpcurl(&endpoints[0])])
+            .set(1);
+        Self {
+            endpoints,
+            max_latency,
+            selected: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected.load(Ordering::Relaxed)
+    }
+
+    pub fn selected_endpoint(&self) -> &str {
+        &self.endpoints[self.selected_index()]
+    }
+
+    /// checks the endpoints in order (primary first) with `roundtrip_duration` and selects the first one
+    /// responding within the latency threshold; keeps the current endpoint if none is healthy
+    pub async fn select<F, Fut>(&self, mut roundtrip_duration: F) -> usize
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = anyhow::Result<Duration>>,
+    {
+        let current = self.selected_index();
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            match roundtrip_duration(index).await {
+                Ok(latency) if latency <= self.max_latency => {
+                    self.set_selected(current, index);
+                    return index;
+                }
+                Ok(latency) => debug!(
+                    "RPC endpoint {} too slow: getHealth took {:?}",
+                    obfuscate_rpcurl(endpoint),
+                    latency
+                ),
+                Err(err) => debug!(
+                    "RPC endpoint {} unhealthy: {:?}",
+                    obfuscate_rpcurl(endpoint),
+                    err
+                ),
+            }
+        }
+        warn!(
+            "No healthy RPC endpoint - keep using {}",
+            obfuscate_rpcurl(&self.endpoints[current])
+        );
+        current
+    }
+
+    fn set_selected(&self, previous: usize, index: usize) {
+        if previous == index {
+            return;
+        }
+        self.selected.store(index, Ordering::Relaxed);
+        SELECTED_RPC_ENDPOINT
+            .with_label_values(&[&obfuscate_rpcurl(&self.endpoints[previous])])
+            .set(0);
+        SELECTED_RPC_ENDPOINT
+            .with_label_values(&[&obfuscate_rpcurl(&self.endpoints[index])])
+            .set(1);
+        info!(
+            "Switched RPC endpoint from {} to {}",
+            obfuscate_rpcurl(&self.endpoints[previous]),
+            obfuscate_rpcurl(&self.endpoints[index])
+        );
+    }
+}
+
+pub async fn rpc_roundtrip_duration(rpc: &RpcClient) -> anyhow::Result<Duration> {
+    let started_at = Instant::now();
+    rpc.get_health().await?;
+    Ok(started_at.elapsed())
+}
+
+/// sends each request to the endpoint currently selected by the [RpcEndpointSelector]
+struct FailoverRpcSender {
+    senders: Vec<HttpSender>,
+    selector: RpcEndpointSelector,
+}
+
+#[async_trait]
+impl RpcSender for FailoverRpcSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        self.senders[self.selector.selected_index()]
+            .send(request, params)
+            .await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.senders[self.selector.selected_index()].get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.selector.selected_endpoint().to_string()
+    }
+}
+
+/// rpc client sending to the selected endpoint of `selector`
+pub fn create_failover_rpc_client(selector: RpcEndpointSelector) -> RpcClient {
+    let senders = selector
+        .endpoints()
+        .iter()
+        .map(HttpSender::new)
+        .collect::<Vec<_>>();
+    RpcClient::new_sender(
+        FailoverRpcSender { senders, selector },
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    )
+}
+
+/// re-selects the endpoint every few seconds, switching back to the primary once it is healthy again
+pub async fn run_rpc_health_checks(selector: RpcEndpointSelector) {
+    let rpc_clients = selector
+        .endpoints()
+        .iter()
+        .map(|endpoint| RpcClient::new_with_timeout(endpoint.clone(), RPC_HEALTH_CHECK_INTERVAL))
+        .collect::<Vec<_>>();
+    let mut interval = tokio::time::interval(RPC_HEALTH_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        selector
+            .select(|index| rpc_roundtrip_duration(&rpc_clients[index]))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn selector() -> RpcEndpointSelector {
+        RpcEndpointSelector::new(
+            "http://primary:8899".to_string(),
+            vec![
+                "http://fallback-1:8899".to_string(),
+                "http://fallback-2:8899".to_string(),
+            ],
+            Duration::from_millis(500),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failover_when_primary_unhealthy() {
+        let selector = selector();
+        let primary_healthy = AtomicBool::new(true);
+        let primary_healthy = &primary_healthy;
+        // fallback-1 is too slow, fallback-2 is healthy
+        let roundtrip_duration = |index: usize| async move {
+            match index {
+                0 if primary_healthy.load(Ordering::Relaxed) => Ok(Duration::from_millis(20)),
+                0 => Err(anyhow::anyhow!("Node is unhealthy")),
+                1 => Ok(Duration::from_secs(2)),
+                _ => Ok(Duration::from_millis(50)),
+            }
+        };
+
+        assert_eq!(selector.select(roundtrip_duration).await, 0);
+        assert_eq!(selector.selected_endpoint(), "http://primary:8899");
+
+        primary_healthy.store(false, Ordering::Relaxed);
+        assert_eq!(selector.select(roundtrip_duration).await, 2);
+        assert_eq!(selector.selected_endpoint(), "http://fallback-2:8899");
+
+        // back to the primary once it recovered
+        primary_healthy.store(true, Ordering::Relaxed);
+        assert_eq!(selector.select(roundtrip_duration).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_endpoint_if_none_healthy() {
+        let selector = selector();
+        selector
+            .select(|index| async move {
+                match index {
+                    1 => Ok(Duration::from_millis(10)),
+                    _ => Err(anyhow::anyhow!("connection refused")),
+                }
+            })
+            .await;
+        assert_eq!(selector.selected_index(), 1);
+
+        let selected = selector
+            .select(|_| async { Err(anyhow::anyhow!("connection refused")) })
+            .await;
+        assert_eq!(selected, 1);
+        assert_eq!(selector.selected_endpoint(), "http://fallback-1:8899");
+    }
+}
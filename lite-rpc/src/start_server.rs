@@ -1,6 +1,6 @@
 use crate::{
     bridge::LiteBridge, bridge_pubsub::LitePubSubBridge, rpc::LiteRpcServer,
-    rpc_pubsub::LiteRpcPubSubServer,
+    rpc_pubsub::LiteRpcPubSubServer, ws_keepalive::WsKeepalive,
 };
 
 use hyper::Method;
@@ -18,6 +18,9 @@ pub struct ServerConfiguration {
     pub max_connection: u32,
 
     pub max_subscriptions_per_connection: u32,
+
+    /// websocket pings keep idle subscriber connections alive behind proxies; None disables
+    pub ws_keepalive: Option<WsKeepalive>,
 }
 
 impl Default for ServerConfiguration {
@@ -27,6 +30,9 @@ impl Default for ServerConfiguration {
             max_response_body_size: 500_000 * (1 << 10), // 500MB response size
             max_connection: 1000000,
             max_subscriptions_per_connection: 1000,
+            ws_keepalive: WsKeepalive::from_ping_interval_secs(
+                crate::DEFAULT_WS_PING_INTERVAL_SECS,
+            ),
         }
     }
 }
@@ -42,10 +48,14 @@ pub async fn start_servers(
     let pubsub = pubsub.into_rpc();
     let server_configuration = server_configuration.unwrap_or_default();
 
-    let ws_server_handle = ServerBuilder::default()
+    let mut ws_server_builder = ServerBuilder::default()
         .ws_only()
         .max_connections(server_configuration.max_connection)
-        .max_subscriptions_per_connection(server_configuration.max_subscriptions_per_connection)
+        .max_subscriptions_per_connection(server_configuration.max_subscriptions_per_connection);
+    if let Some(ws_keepalive) = server_configuration.ws_keepalive {
+        ws_server_builder = ws_server_builder.ping_interval(ws_keepalive.ping_interval);
+    }
+    let ws_server_handle = ws_server_builder
         .build(ws_addr.clone())
         .await?
         .start(pubsub);
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+use solana_sdk::slot_history::Slot;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// upper limit for the requested timeout; the client connection is kept open while waiting
+pub const MAX_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+// slots are produced every ~400ms
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendTransactionConfirmation {
+    pub signature: String,
+    /// slot of the first block the transaction was observed in; None if not observed before the timeout
+    pub confirmation_slot: Option<Slot>,
+    /// time from forwarding the transaction until it was observed or the timeout elapsed
+    pub elapsed_ms: u64,
+}
+
+/// forwards the transaction via `send` and polls `confirmation_slot` until the transaction was observed
+/// in a block or `timeout` elapsed; a timeout is not an error as the transaction may still land later
+pub async fn send_and_await_confirmation<S, Fut, C>(
+    send: S,
+    confirmation_slot: C,
+    timeout: Duration,
+) -> anyhow::Result<SendTransactionConfirmation>
+where
+    S: FnOnce() -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+    C: Fn(&Signature) -> Option<Slot>,
+{
+    let signature_str = send().await?;
+    let signature = Signature::from_str(&signature_str)?;
+    let started = Instant::now();
+
+    let confirmation_slot = tokio::time::timeout(timeout, async {
+        loop {
+            if let Some(slot) = confirmation_slot(&signature) {
+                return slot;
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .ok();
+
+    Ok(SendTransactionConfirmation {
+        signature: signature_str,
+        confirmation_slot,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_first_observed_confirmation_slot() {
+        let signature = Signature::new_unique();
+        let nb_forwarded = AtomicUsize::new(0);
+        let nb_polls = AtomicUsize::new(0);
+
+        let forwarded = &nb_forwarded;
+        let confirmation = send_and_await_confirmation(
+            || async move {
+                forwarded.fetch_add(1, Ordering::SeqCst);
+                Ok(signature.to_string())
+            },
+            |polled| {
+                assert_eq!(*polled, signature);
+                // observed in a block on the third poll
+                (nb_polls.fetch_add(1, Ordering::SeqCst) >= 2).then_some(4242)
+            },
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(nb_forwarded.load(Ordering::SeqCst), 1);
+        assert_eq!(confirmation.signature, signature.to_string());
+        assert_eq!(confirmation.confirmation_slot, Some(4242));
+        assert_eq!(nb_polls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_confirmation_timeout() {
+        let signature = Signature::new_unique();
+        let confirmation = send_and_await_confirmation(
+            || async move { Ok(signature.to_string()) },
+            |_| None,
+            Duration::from_millis(120),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(confirmation.confirmation_slot, None);
+        assert!(confirmation.elapsed_ms >= 120);
+    }
+
+    #[tokio::test]
+    async fn test_forwarding_error() {
+        let result = send_and_await_confirmation(
+            || async { Err(anyhow::anyhow!("Blockhash not found in block store")) },
+            |_| panic!("must not wait for confirmation"),
+            Duration::from_secs(1),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use prometheus::{opts, register_int_counter, IntCounter};
+use std::future::Future;
+use std::time::Duration;
+
+lazy_static::lazy_static! {
+    static ref WS_STALE_SUBSCRIBERS_REAPED: IntCounter =
+    register_int_counter!(opts!("literpc_ws_stale_subscribers_reaped", "Websocket subscriptions dropped because the subscriber did not take messages within the keepalive timeout")).unwrap();
+}
+
+/// a subscriber which does not take a message for this many ping intervals is treated as dead
+pub const WS_MISSED_PINGS_LIMIT: u32 = 3;
+
+/// websocket ping settings of the subscription server
+///
+/// jsonrpsee sends the pings but does not report missing pongs, so a dead connection is detected by
+/// the subscription messages piling up: a send which does not complete within [Self::stale_timeout]
+/// means the connection neither took the pings nor the notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WsKeepalive {
+    pub ping_interval: Duration,
+}
+
+impl WsKeepalive {
+    /// None if pings are disabled (interval 0)
+    pub fn from_ping_interval_secs(ping_interval_secs: u64) -> Option<Self> {
+        (ping_interval_secs > 0).then(|| Self {
+            ping_interval: Duration::from_secs(ping_interval_secs),
+        })
+    }
+
+    pub fn stale_timeout(&self) -> Duration {
+        self.ping_interval * WS_MISSED_PINGS_LIMIT
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SubscriberSendError {
+    #[error("subscriber disconnected")]
+    Disconnected,
+    /// connection is open but did not take the message within the keepalive timeout
+    #[error("subscriber did not take messages within {0:?}")]
+    Stale(Duration),
+}
+
+/// awaits a send to a subscriber, bounded by the stale timeout of the keepalive if pings are enabled;
+/// on error the subscription task must stop so that its sink and stream receiver are dropped
+pub async fn send_to_subscriber<F, E>(
+    send: F,
+    keepalive: Option<WsKeepalive>,
+) -> Result<(), SubscriberSendError>
+where
+    F: Future<Output = Result<(), E>>,
+{
+    let Some(keepalive) = keepalive else {
+        return send.await.map_err(|_| SubscriberSendError::Disconnected);
+    };
+
+    let stale_timeout = keepalive.stale_timeout();
+    match tokio::time::timeout(stale_timeout, send).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) => Err(SubscriberSendError::Disconnected),
+        Err(_elapsed) => {
+            WS_STALE_SUBSCRIBERS_REAPED.inc();
+            log::debug!("Dropping websocket subscriber which did not take messages within {stale_timeout:?}");
+            Err(SubscriberSendError::Stale(stale_timeout))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_from_config() {
+        assert_eq!(WsKeepalive::from_ping_interval_secs(0), None);
+        let keepalive = WsKeepalive::from_ping_interval_secs(10).unwrap();
+        assert_eq!(keepalive.ping_interval, Duration::from_secs(10));
+        assert_eq!(keepalive.stale_timeout(), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_stale_subscriber_is_reaped() {
+        let keepalive = Some(WsKeepalive {
+            ping_interval: Duration::from_millis(50),
+        });
+
+        // dead connection: the send never completes
+        let result = send_to_subscriber(std::future::pending::<Result<(), ()>>(), keepalive).await;
+        assert_eq!(
+            result,
+            Err(SubscriberSendError::Stale(Duration::from_millis(150)))
+        );
+
+        // slow but alive subscriber takes the message before the timeout
+        let slow_send = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<(), ()>(())
+        };
+        assert_eq!(send_to_subscriber(slow_send, keepalive).await, Ok(()));
+
+        assert_eq!(
+            send_to_subscriber(async { Err::<(), ()>(()) }, keepalive).await,
+            Err(SubscriberSendError::Disconnected)
+        );
+    }
+}
@@ -83,6 +83,7 @@ impl AccountPrioStore {
             let value = PrioFeesData {
                 priority: transaction.prioritization_fees.unwrap_or_default(),
                 cu_consumed: transaction.cu_consumed.unwrap_or_default(),
+                failed: transaction.err.is_some(),
             };
             let mut writable_accounts = transaction.writable_accounts.clone();
             let mut readable_accounts = transaction.readable_accounts.clone();
@@ -31,6 +31,19 @@ impl PrioFeesService {
             .map(|(slot, value)| (*slot, value.calculate_stats()))
     }
 
+    /// stats of the last `nb` blocks ordered by slot ascending
+    pub async fn get_recent_priofees(&self, nb: usize) -> Vec<(Slot, PrioFeesStats)> {
+        let lock = self.block_fees_store.recent.read().await;
+        let mut recent = lock
+            .iter()
+            .rev()
+            .take(nb)
+            .map(|(slot, value)| (*slot, value.calculate_stats()))
+            .collect::<Vec<_>>();
+        recent.reverse();
+        recent
+    }
+
     pub async fn get_last_n_priofees_aggregate(&self, nb: usize) -> Option<(Slot, PrioFeesStats)> {
         let lock = self.block_fees_store.recent.read().await;
         let last_slot = match lock.last_key_value().map(|x| *x.0) {
@@ -80,6 +93,7 @@ pub fn start_block_priofees_task(
                         .map(|tx| PrioFeesData {
                             priority: tx.prioritization_fees.unwrap_or_default(),
                             cu_consumed: tx.cu_consumed.unwrap_or_default(),
+                            failed: tx.err.is_some(),
                         })
                         .collect::<Vec<PrioFeesData>>();
 
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rpc_data::PrioFeesStats;
+
+/// CU-weighted percentile each fee tier is taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeTierPercentiles {
+    pub low: u8,
+    pub medium: u8,
+    pub high: u8,
+    pub max: u8,
+}
+
+impl Default for FeeTierPercentiles {
+    fn default() -> Self {
+        Self {
+            low: 50,
+            medium: 75,
+            high: 90,
+            max: 100,
+        }
+    }
+}
+
+/// recommended prioritization fees in micro lamports per CU; always low <= medium <= high <= max
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct FeeTiers {
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+    pub max: u64,
+}
+
+/// fee tiers from the CU-weighted distribution; a percentile which was not calculated is
+/// rounded up to the next calculated one - None if the stats have no data
+pub fn calculate_fee_tiers(
+    stats: &PrioFeesStats,
+    tier_percentiles: &FeeTierPercentiles,
+) -> Option<FeeTiers> {
+    if !stats.has_data() {
+        return None;
+    }
+
+    let low = get_fees_by_cu_rounded_up(stats, tier_percentiles.low)?;
+    // clamp - the mapping is not required to be ordered
+    let medium = get_fees_by_cu_rounded_up(stats, tier_percentiles.medium)?.max(low);
+    let high = get_fees_by_cu_rounded_up(stats, tier_percentiles.high)?.max(medium);
+    let max = get_fees_by_cu_rounded_up(stats, tier_percentiles.max)?.max(high);
+    Some(FeeTiers {
+        low,
+        medium,
+        high,
+        max,
+    })
+}
+
+fn get_fees_by_cu_rounded_up(stats: &PrioFeesStats, percentile: u8) -> Option<u64> {
+    (percentile..=100)
+        .find_map(|p| stats.fee_at_cu_percentile(p))
+        .or_else(|| stats.by_cu.last().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prioritization_fee_data::{BlockPrioData, PrioFeesData};
+
+    fn block_stats(fees_and_cu: &[(u64, u64)]) -> PrioFeesStats {
+        BlockPrioData {
+            transaction_data: fees_and_cu
+                .iter()
+                .map(|fees_and_cu| PrioFeesData::from(*fees_and_cu))
+                .collect(),
+            ..Default::default()
+        }
+        .calculate_stats()
+    }
+
+    #[test]
+    fn test_default_mapping() {
+        // 10 transactions with equal CU and fees 100, 200, .., 1000
+        let stats = block_stats(&(1..=10).map(|i| (i * 100, 1000)).collect::<Vec<_>>());
+
+        let fee_tiers = calculate_fee_tiers(&stats, &FeeTierPercentiles::default()).unwrap();
+
+        let (_, p50) = stats.get_percentile(0.5).unwrap();
+        let (_, p75) = stats.get_percentile(0.75).unwrap();
+        let (_, p90) = stats.get_percentile(0.9).unwrap();
+        assert_eq!(
+            fee_tiers,
+            FeeTiers {
+                low: p50,
+                medium: p75,
+                high: p90,
+                max: 1000,
+            }
+        );
+        assert!(calculate_fee_tiers(&block_stats(&[]), &FeeTierPercentiles::default()).is_none());
+    }
+
+    #[test]
+    fn test_tiers_are_monotonic() {
+        let stats = block_stats(&[(0, 200000), (50, 1000), (7000, 300), (100000, 50)]);
+        let reversed_mapping = FeeTierPercentiles {
+            low: 100,
+            medium: 90,
+            high: 75,
+            max: 50,
+        };
+
+        for tier_percentiles in [FeeTierPercentiles::default(), reversed_mapping] {
+            let FeeTiers {
+                low,
+                medium,
+                high,
+                max,
+            } = calculate_fee_tiers(&stats, &tier_percentiles).unwrap();
+            assert!(low <= medium && medium <= high && high <= max);
+        }
+
+        let fee_tiers = calculate_fee_tiers(&stats, &reversed_mapping).unwrap();
+        assert_eq!(fee_tiers.low, fee_tiers.max);
+    }
+}
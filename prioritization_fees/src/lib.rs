@@ -2,8 +2,12 @@ mod block_priofees;
 
 pub mod account_prio_service;
 mod account_priofees;
+pub mod fee_tiers;
+pub mod priofees_time_series;
+pub mod priofees_window;
 pub mod prioritization_fee_calculation_method;
 pub mod prioritization_fee_data;
+pub mod recommended_fee;
 pub mod rpc_data;
 mod stats_calculation;
 
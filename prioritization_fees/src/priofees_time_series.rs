@@ -0,0 +1,107 @@
+use crate::rpc_data::PrioFeesStats;
+use solana_sdk::clock::Slot;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+/// per block prio fees stats of the last N blocks (ring buffer) ordered by slot, e.g. to chart fee trends
+pub struct PrioFeesTimeSeries {
+    max_blocks: usize,
+    blocks: VecDeque<(Slot, PrioFeesStats)>,
+}
+
+impl PrioFeesTimeSeries {
+    pub fn new(max_blocks: usize) -> Self {
+        assert!(max_blocks > 0, "time series must retain at least one block");
+        Self {
+            max_blocks,
+            blocks: VecDeque::with_capacity(max_blocks),
+        }
+    }
+
+    /// add the stats of a block; replaces the stats of a slot seen before and evicts the oldest slot if full;
+    /// a block older than all retained blocks of a full series is dropped
+    pub fn push_block(&mut self, slot: Slot, stats: PrioFeesStats) {
+        match self.blocks.binary_search_by_key(&slot, |(slot, _)| *slot) {
+            Ok(index) => self.blocks[index].1 = stats,
+            Err(0) if self.blocks.len() == self.max_blocks => {}
+            Err(index) => {
+                self.blocks.insert(index, (slot, stats));
+                if self.blocks.len() > self.max_blocks {
+                    self.blocks.pop_front();
+                }
+            }
+        }
+    }
+
+    /// fee by transaction at percentile `p` (0..=100) per slot, oldest first;
+    /// blocks without that percentile (e.g. no transactions) are skipped
+    pub fn series(&self, p: u8) -> Vec<(Slot, u64)> {
+        self.blocks
+            .iter()
+            .filter_map(|(slot, stats)| stats.fee_at_percentile(p).map(|fee| (*slot, fee)))
+            .collect()
+    }
+
+    /// slots covered by the retained blocks
+    pub fn slot_range(&self) -> Option<RangeInclusive<Slot>> {
+        let (first_slot, _) = self.blocks.front()?;
+        let (last_slot, _) = self.blocks.back()?;
+        Some(*first_slot..=*last_slot)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prioritization_fee_data::{BlockPrioData, PrioFeesData};
+
+    fn stats(fees: &[u64]) -> PrioFeesStats {
+        let transaction_data = fees
+            .iter()
+            .map(|fee| PrioFeesData::from((*fee, 1000)))
+            .collect::<Vec<_>>();
+        let nb_tx = transaction_data.len() as u64;
+        BlockPrioData {
+            transaction_data,
+            nb_non_vote_tx: nb_tx,
+            nb_total_tx: nb_tx,
+            non_vote_cu_consumed: nb_tx * 1000,
+            total_cu_consumed: nb_tx * 1000,
+        }
+        .calculate_stats()
+    }
+
+    #[test]
+    fn test_series_is_bounded_and_ordered_by_slot() {
+        let mut time_series = PrioFeesTimeSeries::new(3);
+        time_series.push_block(100, stats(&[10]));
+        time_series.push_block(102, stats(&[30]));
+        // late block is inserted in slot order
+        time_series.push_block(101, stats(&[20]));
+        time_series.push_block(103, stats(&[40]));
+
+        assert_eq!(time_series.len(), 3);
+        assert_eq!(time_series.slot_range(), Some(101..=103));
+        assert_eq!(
+            time_series.series(50),
+            vec![(101, 20), (102, 30), (103, 40)]
+        );
+
+        // older than all retained blocks
+        time_series.push_block(99, stats(&[1]));
+        assert_eq!(time_series.slot_range(), Some(101..=103));
+
+        // block without transactions has no percentiles
+        time_series.push_block(104, stats(&[]));
+        assert_eq!(time_series.len(), 3);
+        assert_eq!(time_series.series(50), vec![(102, 30), (103, 40)]);
+    }
+}
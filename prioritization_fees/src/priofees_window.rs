@@ -0,0 +1,113 @@
+use crate::prioritization_fee_data::{BlockPrioData, PrioFeesData};
+use crate::rpc_data::PrioFeesStats;
+use solana_sdk::clock::Slot;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+/// prio fees of the last N blocks (ring buffer) to calculate stats over a recent window
+pub struct PrioFeesWindow {
+    max_blocks: usize,
+    // (slot, non-vote transactions of the block)
+    blocks: VecDeque<(Slot, Vec<PrioFeesData>)>,
+}
+
+impl PrioFeesWindow {
+    pub fn new(max_blocks: usize) -> Self {
+        assert!(max_blocks > 0, "window must retain at least one block");
+        Self {
+            max_blocks,
+            blocks: VecDeque::with_capacity(max_blocks),
+        }
+    }
+
+    /// add block; the oldest block gets evicted if the window is full
+    pub fn push_block(&mut self, slot: Slot, fees: Vec<PrioFeesData>) {
+        if self.blocks.len() == self.max_blocks {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((slot, fees));
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// slots covered by the retained blocks
+    pub fn slot_range(&self) -> Option<RangeInclusive<Slot>> {
+        let min_slot = self.blocks.iter().map(|(slot, _)| *slot).min()?;
+        let max_slot = self.blocks.iter().map(|(slot, _)| *slot).max()?;
+        Some(min_slot..=max_slot)
+    }
+
+    /// stats over all retained transactions; only non-vote transactions are tracked so total equals nonvote
+    pub fn aggregate(&self) -> PrioFeesStats {
+        let transaction_data = self
+            .blocks
+            .iter()
+            .flat_map(|(_, fees)| fees.iter().cloned())
+            .collect::<Vec<_>>();
+        let nb_tx = transaction_data.len() as u64;
+        let cu_consumed = transaction_data.iter().map(|x| x.cu_consumed).sum();
+
+        BlockPrioData {
+            transaction_data,
+            nb_non_vote_tx: nb_tx,
+            nb_total_tx: nb_tx,
+            non_vote_cu_consumed: cu_consumed,
+            total_cu_consumed: cu_consumed,
+        }
+        .calculate_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(data: &[(u64, u64)]) -> Vec<PrioFeesData> {
+        data.iter().map(|x| PrioFeesData::from(*x)).collect()
+    }
+
+    #[test]
+    fn test_aggregate_three_blocks() {
+        let mut window = PrioFeesWindow::new(3);
+        window.push_block(100, fees(&[(10, 1000), (20, 1000)]));
+        window.push_block(101, fees(&[(30, 1000)]));
+        window.push_block(102, fees(&[(40, 1000), (50, 1000)]));
+
+        assert_eq!(window.slot_range(), Some(100..=102));
+
+        let stats = window.aggregate();
+        assert_eq!(stats.tx_count.total, 5);
+        assert_eq!(stats.cu_consumed.total, 5000);
+        assert_eq!(stats.get_percentile(0.0), Some((10, 10)));
+        assert_eq!(stats.get_percentile(0.5).map(|x| x.0), Some(30));
+        assert_eq!(stats.get_percentile(1.0), Some((50, 50)));
+    }
+
+    #[test]
+    fn test_evicts_oldest_block() {
+        let mut window = PrioFeesWindow::new(2);
+        window.push_block(100, fees(&[(1000, 1000)]));
+        window.push_block(101, fees(&[(10, 1000)]));
+        window.push_block(102, fees(&[(20, 1000)]));
+
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.slot_range(), Some(101..=102));
+        assert_eq!(
+            window.aggregate().get_percentile(1.0).map(|x| x.0),
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let window = PrioFeesWindow::new(2);
+        assert!(window.slot_range().is_none());
+        assert!(!window.aggregate().has_data());
+    }
+}
@@ -1,23 +1,84 @@
 use crate::{
     rpc_data::{PrioFeesStats, TxAggregateStats},
-    stats_calculation::calculate_supp_percentiles,
+    stats_calculation::{
+        calculate_cu_share, calculate_supp_percentiles, calculate_supp_percentiles_by_outcome,
+        calculate_supp_percentiles_from_histogram, calculate_supp_percentiles_with,
+        calculate_top_fees, Percentiles, DEFAULT_PERCENTILES, DEFAULT_TOP_FEES_COUNT,
+    },
 };
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct PrioFeesData {
     pub priority: u64,
     pub cu_consumed: u64,
+    // transaction failed (fees are charged anyway)
+    pub failed: bool,
 }
 
+/// successful transaction
 impl From<(u64, u64)> for PrioFeesData {
     fn from(value: (u64, u64)) -> Self {
         Self {
             priority: value.0,
             cu_consumed: value.1,
+            failed: false,
         }
     }
 }
 
+/// drops transactions with absurd prioritization fees (e.g. u64::MAX from a buggy client)
+/// before the stats get calculated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutlierRejection {
+    /// reject fees above this multiple of the median fee by transaction; not applied if the median is 0
+    pub max_multiple_of_median: Option<u64>,
+    /// reject fees above this ceiling
+    pub max_fee: Option<u64>,
+}
+
+impl OutlierRejection {
+    /// (kept transactions, number of rejected transactions)
+    pub fn apply(&self, transaction_data: &[PrioFeesData]) -> (Vec<PrioFeesData>, usize) {
+        let median_ceiling = self
+            .max_multiple_of_median
+            .and_then(|multiple| {
+                let median = median_fee(transaction_data)?;
+                (median > 0).then(|| median.saturating_mul(multiple))
+            })
+            .unwrap_or(u64::MAX);
+        let ceiling = median_ceiling.min(self.max_fee.unwrap_or(u64::MAX));
+
+        let kept = transaction_data
+            .iter()
+            .filter(|data| data.priority <= ceiling)
+            .cloned()
+            .collect::<Vec<_>>();
+        let rejected = transaction_data.len() - kept.len();
+        (kept, rejected)
+    }
+}
+
+// upper median; selection instead of a full sort
+fn median_fee(transaction_data: &[PrioFeesData]) -> Option<u64> {
+    if transaction_data.is_empty() {
+        return None;
+    }
+    let mut fees = transaction_data
+        .iter()
+        .map(|data| data.priority)
+        .collect::<Vec<_>>();
+    let middle = fees.len() / 2;
+    Some(*fees.select_nth_unstable(middle).1)
+}
+
+/// stats of the successful and the failed transactions of the same blocks;
+/// tx_count and cu_consumed are the totals of the blocks for both
+#[derive(Clone, Debug, Default)]
+pub struct PrioFeesStatsByOutcome {
+    pub succeeded: PrioFeesStats,
+    pub failed: PrioFeesStats,
+}
+
 #[derive(Default, Clone)]
 pub struct BlockPrioData {
     pub transaction_data: Vec<PrioFeesData>,
@@ -28,9 +89,93 @@ pub struct BlockPrioData {
 }
 
 impl BlockPrioData {
+    /// percentile arrays are empty if there is no fee data
     pub fn calculate_stats(&self) -> PrioFeesStats {
-        let priofees_percentiles = calculate_supp_percentiles(&self.transaction_data);
+        self.build_stats(
+            &self.transaction_data,
+            calculate_supp_percentiles(&self.transaction_data),
+            DEFAULT_TOP_FEES_COUNT,
+        )
+    }
+
+    /// fees of failed transactions are a poor guide for new submitters - see [PrioFeesStatsByOutcome]
+    pub fn calculate_stats_by_outcome(&self) -> PrioFeesStatsByOutcome {
+        let (succeeded, failed) = calculate_supp_percentiles_by_outcome(&self.transaction_data);
+        let (succeeded_data, failed_data): (Vec<PrioFeesData>, Vec<PrioFeesData>) =
+            self.transaction_data.iter().partition(|data| !data.failed);
+        PrioFeesStatsByOutcome {
+            succeeded: self.build_stats(&succeeded_data, succeeded, DEFAULT_TOP_FEES_COUNT),
+            failed: self.build_stats(&failed_data, failed, DEFAULT_TOP_FEES_COUNT),
+        }
+    }
+
+    /// same as [Self::calculate_stats] but reports the `top_fees_count` highest-fee transactions
+    pub fn calculate_stats_with_top_fees(&self, top_fees_count: usize) -> PrioFeesStats {
+        self.build_stats(
+            &self.transaction_data,
+            calculate_supp_percentiles(&self.transaction_data),
+            top_fees_count,
+        )
+    }
+
+    /// percentile arrays are empty if there is no (non-zero) fee data
+    pub fn calculate_stats_with(&self, exclude_zero_fee: bool) -> PrioFeesStats {
+        let priofees_percentiles = calculate_supp_percentiles_with(
+            &self.transaction_data,
+            &DEFAULT_PERCENTILES,
+            exclude_zero_fee,
+        )
+        .expect("default percentiles must be valid");
+        self.build_stats(
+            &self.transaction_data,
+            priofees_percentiles,
+            DEFAULT_TOP_FEES_COUNT,
+        )
+    }
+
+    /// same as [Self::calculate_stats] but with percentiles approximated from a fee histogram instead of sorting
+    /// all transactions; faster for large blocks, fees by transaction are off by at most 1/64 (~1.6%)
+    pub fn calculate_stats_from_histogram(&self) -> PrioFeesStats {
+        let priofees_percentiles = calculate_supp_percentiles_from_histogram(
+            &self.transaction_data,
+            &DEFAULT_PERCENTILES,
+            false,
+        )
+        .expect("default percentiles must be valid");
+        self.build_stats(
+            &self.transaction_data,
+            priofees_percentiles,
+            DEFAULT_TOP_FEES_COUNT,
+        )
+    }
+
+    /// same as [Self::calculate_stats] but without the transactions rejected by `outlier_rejection`;
+    /// the highest percentile (p100) is the highest fee that was kept
+    pub fn calculate_stats_rejecting_outliers(
+        &self,
+        outlier_rejection: &OutlierRejection,
+    ) -> PrioFeesStats {
+        let (kept, rejected) = outlier_rejection.apply(&self.transaction_data);
+        PrioFeesStats {
+            rejected_outliers: rejected as u64,
+            ..self.build_stats(
+                &kept,
+                calculate_supp_percentiles(&kept),
+                DEFAULT_TOP_FEES_COUNT,
+            )
+        }
+    }
 
+    // transaction_data is the data the percentiles were calculated from
+    fn build_stats(
+        &self,
+        transaction_data: &[PrioFeesData],
+        priofees_percentiles: Option<Percentiles>,
+        top_fees_count: usize,
+    ) -> PrioFeesStats {
+        let priofees_percentiles = priofees_percentiles.unwrap_or_default();
+        let top_fees = calculate_top_fees(transaction_data, top_fees_count);
+        let top_fees_cu_share = calculate_cu_share(&top_fees, self.total_cu_consumed);
         PrioFeesStats {
             by_tx: priofees_percentiles.by_tx,
             by_tx_percentiles: priofees_percentiles.by_tx_percentiles,
@@ -44,6 +189,9 @@ impl BlockPrioData {
                 total: self.total_cu_consumed,
                 nonvote: self.non_vote_cu_consumed,
             },
+            top_fees,
+            top_fees_cu_share,
+            rejected_outliers: 0,
         }
     }
 
@@ -58,3 +206,63 @@ impl BlockPrioData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_prio_data(fees: &[u64]) -> BlockPrioData {
+        BlockPrioData {
+            transaction_data: fees
+                .iter()
+                .map(|fee| PrioFeesData::from((*fee, 1000)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_extreme_outlier_is_rejected() {
+        let mut fees = (1..=10).map(|i| i * 100).collect::<Vec<_>>();
+        fees.push(u64::MAX - 1);
+        let block_prio_data = block_prio_data(&fees);
+
+        let unfiltered = block_prio_data.calculate_stats();
+        assert_eq!(unfiltered.fee_at_percentile(100), Some(u64::MAX - 1));
+        assert_eq!(unfiltered.rejected_outliers, 0);
+
+        let stats = block_prio_data.calculate_stats_rejecting_outliers(&OutlierRejection {
+            max_multiple_of_median: Some(10),
+            max_fee: None,
+        });
+        assert_eq!(stats.rejected_outliers, 1);
+        assert_eq!(stats.fee_at_percentile(100), Some(1000));
+        assert_eq!(stats.fee_at_percentile(50), Some(550));
+        assert_eq!(stats.fee_at_cu_percentile(100), Some(1000));
+        assert!(stats.top_fees.iter().all(|(fee, _cu)| *fee <= 1000));
+    }
+
+    #[test]
+    fn test_outlier_rejection_ceiling_and_zero_median() {
+        let block_prio_data = block_prio_data(&[0, 0, 0, 500, 2_000_000]);
+
+        // median is 0 - the multiple of the median is not applied
+        let (kept, rejected) = OutlierRejection {
+            max_multiple_of_median: Some(10),
+            max_fee: None,
+        }
+        .apply(&block_prio_data.transaction_data);
+        assert_eq!((kept.len(), rejected), (5, 0));
+
+        let stats = block_prio_data.calculate_stats_rejecting_outliers(&OutlierRejection {
+            max_multiple_of_median: Some(10),
+            max_fee: Some(1_000_000),
+        });
+        assert_eq!(stats.rejected_outliers, 1);
+        assert_eq!(stats.fee_at_percentile(100), Some(500));
+
+        let (kept, rejected) = OutlierRejection::default().apply(&[]);
+        assert!(kept.is_empty());
+        assert_eq!(rejected, 0);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::rpc_data::PrioFeesStats;
+
+/// CU-weighted percentiles smoothed by default
+pub const DEFAULT_EMA_PERCENTILES: [u8; 2] = [75, 90];
+
+/// exponential moving average of CU-weighted prio fee percentiles across blocks
+/// to smooth out the block-to-block noise
+#[derive(Clone, Debug)]
+pub struct PrioFeesEma {
+    // weight of the newest block in 0.0..=1.0
+    alpha: f64,
+    // (percentile, ema in micro lamports per CU)
+    ema_by_cu: Vec<(u8, Option<f64>)>,
+}
+
+impl PrioFeesEma {
+    pub fn new(alpha: f64) -> Self {
+        Self::new_with_percentiles(alpha, &DEFAULT_EMA_PERCENTILES)
+    }
+
+    pub fn new_with_percentiles(alpha: f64, percentiles: &[u8]) -> Self {
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "alpha must be in range (0.0, 1.0]"
+        );
+        Self {
+            alpha,
+            ema_by_cu: percentiles.iter().map(|p| (*p, None)).collect(),
+        }
+    }
+
+    /// feed the stats of a new block; blocks without data are ignored
+    pub fn update(&mut self, stats: &PrioFeesStats) {
+        for (percentile, ema) in self.ema_by_cu.iter_mut() {
+            let Some(fees) = stats.fee_at_cu_percentile(*percentile) else {
+                continue;
+            };
+            let fees = fees as f64;
+            *ema = Some(match ema {
+                Some(previous) => self.alpha * fees + (1.0 - self.alpha) * *previous,
+                None => fees,
+            });
+        }
+    }
+
+    /// smoothed fee for one of the tracked percentiles; None if not tracked or no block seen yet
+    pub fn recommended_micro_lamports(&self, target_percentile: u8) -> Option<u64> {
+        self.ema_by_cu
+            .iter()
+            .find(|(percentile, _)| *percentile == target_percentile)
+            .and_then(|(_, ema)| *ema)
+            .map(|ema| ema.round() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prioritization_fee_data::{BlockPrioData, PrioFeesData};
+
+    fn block_stats(fees: u64) -> PrioFeesStats {
+        BlockPrioData {
+            transaction_data: vec![PrioFeesData::from((fees, 1000))],
+            ..Default::default()
+        }
+        .calculate_stats()
+    }
+
+    #[test]
+    fn test_ema_converges_after_step_change() {
+        let mut ema = PrioFeesEma::new(0.3);
+        for _ in 0..10 {
+            ema.update(&block_stats(1000));
+        }
+        assert_eq!(ema.recommended_micro_lamports(75), Some(1000));
+
+        ema.update(&block_stats(2000));
+        let after_one_block = ema.recommended_micro_lamports(75).unwrap();
+        assert_eq!(after_one_block, 1300);
+
+        let mut previous = after_one_block;
+        for _ in 0..20 {
+            ema.update(&block_stats(2000));
+            let current = ema.recommended_micro_lamports(90).unwrap();
+            assert!(current >= previous);
+            previous = current;
+        }
+        assert!(previous > 1990 && previous <= 2000);
+    }
+
+    #[test]
+    fn test_untracked_percentile_and_no_data() {
+        let mut ema = PrioFeesEma::new(0.5);
+        assert_eq!(ema.recommended_micro_lamports(75), None);
+
+        ema.update(&BlockPrioData::default().calculate_stats());
+        assert_eq!(ema.recommended_micro_lamports(75), None);
+
+        ema.update(&block_stats(500));
+        assert_eq!(ema.recommended_micro_lamports(75), Some(500));
+        assert_eq!(ema.recommended_micro_lamports(50), None);
+    }
+}
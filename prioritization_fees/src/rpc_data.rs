@@ -1,14 +1,18 @@
-use jsonrpsee::core::Serialize;
+use crate::fee_tiers::{calculate_fee_tiers, FeeTierPercentiles, FeeTiers};
+use serde::{Deserialize, Serialize};
 use solana_sdk::{clock::Slot, pubkey::Pubkey};
 use std::{collections::HashMap, fmt::Display, sync::Arc};
 
-#[derive(Clone, Serialize, Debug, Default)]
+/// bump on every change of the JSON shape of [VersionedPrioFeesStats]
+pub const PRIO_FEES_STATS_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
 pub struct TxAggregateStats {
     pub total: u64,
     pub nonvote: u64,
 }
 
-#[derive(Clone, Serialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct PrioFeesStats {
     // the arrays are same size and ordered monotonically
     pub by_tx: Vec<u64>,
@@ -21,9 +25,62 @@ pub struct PrioFeesStats {
     // per block stats
     pub tx_count: TxAggregateStats,
     pub cu_consumed: TxAggregateStats,
+
+    // (fee, cu_consumed) of the transactions with the highest fees, highest first
+    #[serde(default)]
+    pub top_fees: Vec<(u64, u64)>,
+    // fraction of the total CU consumed by the top_fees transactions
+    #[serde(default)]
+    pub top_fees_cu_share: f32,
+    // transactions excluded from the percentiles and top_fees by outlier rejection
+    #[serde(default)]
+    pub rejected_outliers: u64,
 }
 
 impl PrioFeesStats {
+    /// false if there were no transactions to calculate the percentiles from
+    pub fn has_data(&self) -> bool {
+        !self.by_tx.is_empty()
+    }
+
+    /// fee by transaction at percentile `p` (0..=100); None if the percentile was not calculated
+    pub fn fee_at_percentile(&self, p: u8) -> Option<u64> {
+        fee_at_percentile(&self.by_tx_percentiles, &self.by_tx, p)
+    }
+
+    /// fee by compute units at percentile `p` (0..=100); None if the percentile was not calculated
+    pub fn fee_at_cu_percentile(&self, p: u8) -> Option<u64> {
+        fee_at_percentile(&self.by_cu_percentiles, &self.by_cu, p)
+    }
+
+    /// inverse of [Self::fee_at_cu_percentile]: percentile (0..=100) of the fee by compute units, linearly
+    /// interpolated between the calculated percentiles; 0 below p0 (or without data), 100 from p100 on
+    pub fn percentile_for_fee(&self, fee: u64) -> f64 {
+        let points = self
+            .by_cu_percentiles
+            .iter()
+            .zip(&self.by_cu)
+            .map(|(percentile, point_fee)| (to_percentile_points(*percentile) as f64, *point_fee))
+            .collect::<Vec<_>>();
+        let (Some(&(_, lowest_fee)), Some(&(_, highest_fee))) = (points.first(), points.last())
+        else {
+            return 0.0;
+        };
+        if fee < lowest_fee {
+            return 0.0;
+        }
+        if fee >= highest_fee {
+            return 100.0;
+        }
+        // highest percentile paying at most `fee`; a fee shared by several percentiles ranks at the highest
+        let index = points.partition_point(|(_, point_fee)| *point_fee <= fee) - 1;
+        let (percentile_low, fee_low) = points[index];
+        let (percentile_high, fee_high) = points[index + 1];
+        percentile_low
+            + (fee - fee_low) as f64 / (fee_high - fee_low) as f64
+                * (percentile_high - percentile_low)
+    }
+
     pub fn get_percentile(&self, percentile: f32) -> Option<(u64, u64)> {
         let index_tx = self.by_tx_percentiles.iter().position(|x| *x == percentile);
         let index_cu = self.by_cu_percentiles.iter().position(|x| *x == percentile);
@@ -35,27 +92,126 @@ impl PrioFeesStats {
     }
 }
 
-#[derive(Clone, Serialize, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
 pub struct FeePoint {
     // percentile
     pub percentile: u32,
     // value of fees in lamports
+    #[serde(rename = "fee")]
     pub fees: u64,
 }
 
+/// lookup by percentile name (p50 -> 50) instead of the position in the distribution
+pub(crate) fn fee_at_percentile(percentiles: &[f32], fees: &[u64], p: u8) -> Option<u64> {
+    percentiles
+        .iter()
+        .zip(fees)
+        .find(|(percentile, _fee)| to_percentile_points(**percentile) == p as u32)
+        .map(|(_percentile, fee)| *fee)
+}
+
+fn to_percentile_points(percentile: f32) -> u32 {
+    (percentile * 100.0).round() as u32
+}
+
 impl Display for FeePoint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "(p{}, {})", self.percentile, self.fees)
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TopFee {
+    pub fee: u64,
+    pub cu_consumed: u64,
+}
+
+/// stable JSON representation of [PrioFeesStats]; field names must not change without bumping
+/// [PRIO_FEES_STATS_SCHEMA_VERSION]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrioFeesStatsV2 {
+    // ordered by percentile ascending
+    pub dist_fee_by_index: Vec<FeePoint>,
+    pub dist_fee_by_cu: Vec<FeePoint>,
+    pub tx_count: TxAggregateStats,
+    pub cu_consumed: TxAggregateStats,
+    // highest fee first
+    pub top_fees: Vec<TopFee>,
+    pub top_fees_cu_share: f32,
+}
+
+impl PrioFeesStatsV2 {
+    /// fee of the `"p{p}"` entry of [Self::dist_fee_by_index]
+    pub fn fee_at_percentile(&self, p: u8) -> Option<u64> {
+        fee_point_at_percentile(&self.dist_fee_by_index, p)
+    }
+
+    /// fee of the `"p{p}"` entry of [Self::dist_fee_by_cu]
+    pub fn fee_at_cu_percentile(&self, p: u8) -> Option<u64> {
+        fee_point_at_percentile(&self.dist_fee_by_cu, p)
+    }
+}
+
+fn fee_point_at_percentile(fee_points: &[FeePoint], p: u8) -> Option<u64> {
+    fee_points
+        .iter()
+        .find(|fee_point| fee_point.percentile == p as u32)
+        .map(|fee_point| fee_point.fees)
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionedPrioFeesStats {
+    pub version: u32,
+    pub stats: PrioFeesStatsV2,
+    // default tier mapping; None if there were no transactions (since version 2)
+    pub fee_tiers: Option<FeeTiers>,
+}
+
+fn to_fee_points(fees: &[u64], percentiles: &[f32]) -> Vec<FeePoint> {
+    percentiles
+        .iter()
+        .zip(fees)
+        .map(|(percentile, fees)| FeePoint {
+            percentile: to_percentile_points(*percentile),
+            fees: *fees,
+        })
+        .collect()
+}
+
+impl From<&PrioFeesStats> for VersionedPrioFeesStats {
+    fn from(stats: &PrioFeesStats) -> Self {
+        Self {
+            version: PRIO_FEES_STATS_SCHEMA_VERSION,
+            stats: PrioFeesStatsV2 {
+                dist_fee_by_index: to_fee_points(&stats.by_tx, &stats.by_tx_percentiles),
+                dist_fee_by_cu: to_fee_points(&stats.by_cu, &stats.by_cu_percentiles),
+                tx_count: stats.tx_count.clone(),
+                cu_consumed: stats.cu_consumed.clone(),
+                top_fees: stats
+                    .top_fees
+                    .iter()
+                    .map(|(fee, cu_consumed)| TopFee {
+                        fee: *fee,
+                        cu_consumed: *cu_consumed,
+                    })
+                    .collect(),
+                top_fees_cu_share: stats.top_fees_cu_share,
+            },
+            fee_tiers: calculate_fee_tiers(stats, &FeeTierPercentiles::default()),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PrioFeesUpdateMessage {
     pub slot: Slot,
     pub priofees_stats: PrioFeesStats,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct AccountPrioFeesStats {
     pub write_stats: PrioFeesStats,
     pub all_stats: PrioFeesStats,
@@ -66,3 +222,93 @@ pub struct AccountPrioFeesUpdateMessage {
     pub slot: Slot,
     pub accounts_data: Arc<HashMap<Pubkey, AccountPrioFeesStats>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_stats() -> PrioFeesStats {
+        PrioFeesStats {
+            by_tx: vec![0, 150, 5000],
+            by_tx_percentiles: vec![0.0, 0.5, 1.0],
+            by_cu: vec![10, 100, 5000],
+            by_cu_percentiles: vec![0.0, 0.5, 1.0],
+            tx_count: TxAggregateStats {
+                total: 1200,
+                nonvote: 400,
+            },
+            cu_consumed: TxAggregateStats {
+                total: 48000000,
+                nonvote: 30000000,
+            },
+            top_fees: vec![(5000, 3000), (4000, 1000)],
+            top_fees_cu_share: 0.5,
+            rejected_outliers: 0,
+        }
+    }
+
+    #[test]
+    fn test_percentile_for_fee() {
+        // p0=10, p50=100, p100=5000
+        let stats = sample_stats();
+        assert_eq!(stats.percentile_for_fee(5), 0.0);
+        assert_eq!(stats.percentile_for_fee(10), 0.0);
+        assert_eq!(stats.percentile_for_fee(55), 25.0);
+        assert_eq!(stats.percentile_for_fee(100), 50.0);
+        assert_eq!(stats.percentile_for_fee(2550), 75.0);
+        assert_eq!(stats.percentile_for_fee(5000), 100.0);
+        assert_eq!(stats.percentile_for_fee(9999), 100.0);
+
+        let plateau = PrioFeesStats {
+            by_cu: vec![10, 10, 100],
+            ..sample_stats()
+        };
+        assert_eq!(plateau.percentile_for_fee(10), 50.0);
+        assert_eq!(PrioFeesStats::default().percentile_for_fee(100), 0.0);
+    }
+
+    #[test]
+    fn test_versioned_stats_json_snapshot() {
+        let versioned = VersionedPrioFeesStats::from(&sample_stats());
+        let json = serde_json::to_string(&versioned).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"version":2,"stats":{"#,
+                r#""distFeeByIndex":[{"percentile":0,"fee":0},{"percentile":50,"fee":150},{"percentile":100,"fee":5000}],"#,
+                r#""distFeeByCu":[{"percentile":0,"fee":10},{"percentile":50,"fee":100},{"percentile":100,"fee":5000}],"#,
+                r#""txCount":{"total":1200,"nonvote":400},"#,
+                r#""cuConsumed":{"total":48000000,"nonvote":30000000},"#,
+                r#""topFees":[{"fee":5000,"cuConsumed":3000},{"fee":4000,"cuConsumed":1000}],"#,
+                r#""topFeesCuShare":0.5},"#,
+                r#""feeTiers":{"low":100,"medium":5000,"high":5000,"max":5000}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_fee_at_percentile() {
+        let stats = sample_stats();
+        assert_eq!(stats.fee_at_percentile(50), Some(150));
+        assert_eq!(stats.fee_at_cu_percentile(0), Some(10));
+        assert_eq!(stats.fee_at_cu_percentile(100), Some(5000));
+        // not calculated
+        assert_eq!(stats.fee_at_percentile(75), None);
+        assert_eq!(stats.fee_at_cu_percentile(25), None);
+
+        let versioned = VersionedPrioFeesStats::from(&stats);
+        assert_eq!(versioned.stats.fee_at_percentile(50), Some(150));
+        assert_eq!(versioned.stats.fee_at_cu_percentile(50), Some(100));
+        assert_eq!(versioned.stats.fee_at_percentile(99), None);
+
+        assert_eq!(PrioFeesStats::default().fee_at_percentile(50), None);
+    }
+
+    #[test]
+    fn test_versioned_stats_roundtrip() {
+        let versioned = VersionedPrioFeesStats::from(&sample_stats());
+        let json = serde_json::to_string(&versioned).unwrap();
+        let parsed: VersionedPrioFeesStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, versioned);
+    }
+}
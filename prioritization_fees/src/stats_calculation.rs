@@ -1,34 +1,75 @@
-use crate::{prioritization_fee_data::PrioFeesData, rpc_data::FeePoint};
+use crate::{
+    prioritization_fee_data::PrioFeesData,
+    rpc_data::{fee_at_percentile, FeePoint},
+};
 use itertools::Itertools;
-use std::iter::zip;
 
-/// `quantile` function is the same as the median if q=50, the same as the minimum if q=0 and the same as the maximum if q=100.
+/// percentiles p0, p5, .., p100
+pub const DEFAULT_PERCENTILES: [u8; 21] = [
+    0, 5, 10, 15, 20, 25, 30, 35, 40, 45, 50, 55, 60, 65, 70, 75, 80, 85, 90, 95, 100,
+];
+
+/// CU-weighted percentile returned by [Percentiles::recommended_fee]
+pub const RECOMMENDED_FEE_PERCENTILE: u8 = 75;
+
+// fee histogram: fees below 2^HISTOGRAM_SUB_BUCKET_BITS get their own bucket, every higher power of two
+// is split into 2^HISTOGRAM_SUB_BUCKET_BITS equally wide buckets
+const HISTOGRAM_SUB_BUCKET_BITS: u32 = 5;
+const HISTOGRAM_SUB_BUCKETS: u64 = 1 << HISTOGRAM_SUB_BUCKET_BITS;
+const HISTOGRAM_BUCKETS: usize =
+    ((64 - HISTOGRAM_SUB_BUCKET_BITS + 1) as u64 * HISTOGRAM_SUB_BUCKETS) as usize;
 
+/// number of highest-fee transactions reported in [PrioFeesStats::top_fees](crate::rpc_data::PrioFeesStats)
+pub const DEFAULT_TOP_FEES_COUNT: usize = 5;
+
+/// `quantile` function is the same as the median if q=50, the same as the minimum if q=0 and the same as the maximum if q=100.
+///
+/// returns None if there is no data (percentile for empty array is undefined)
 pub fn calculate_supp_percentiles(
     // Vec(prioritization_fees, cu_consumed)
     prio_fees_in_block: &[PrioFeesData],
-) -> Percentiles {
-    let prio_fees_in_block = if prio_fees_in_block.is_empty() {
-        // note: percentile for empty array is undefined
-        vec![PrioFeesData::default()]
-    } else {
-        // sort by prioritization fees
-        prio_fees_in_block
-            .iter()
-            .sorted_by_key(|data| data.priority)
-            .cloned()
-            .collect_vec()
-    };
+) -> Option<Percentiles> {
+    calculate_supp_percentiles_with(prio_fees_in_block, &DEFAULT_PERCENTILES, false)
+        .expect("default percentiles must be valid")
+}
+
+/// same as [calculate_supp_percentiles] but for the requested percentiles (0..=100) only;
+/// the result is ordered by percentile ascending
+///
+/// with `exclude_zero_fee` transactions without prioritization fees are ignored for both distributions
+pub fn calculate_supp_percentiles_with(
+    // Vec(prioritization_fees, cu_consumed)
+    prio_fees_in_block: &[PrioFeesData],
+    percentiles: &[u8],
+    exclude_zero_fee: bool,
+) -> anyhow::Result<Option<Percentiles>> {
+    if let Some(invalid) = percentiles.iter().find(|p| **p > 100) {
+        anyhow::bail!("percentile {} is out of range 0..=100", invalid);
+    }
+    let percentiles = percentiles
+        .iter()
+        .sorted()
+        .dedup()
+        .map(|p| *p as u64)
+        .collect_vec();
+
+    // sort by prioritization fees
+    let prio_fees_in_block = prio_fees_in_block
+        .iter()
+        .filter(|data| !exclude_zero_fee || data.priority > 0)
+        .sorted_by_key(|data| data.priority)
+        .cloned()
+        .collect_vec();
+
+    if prio_fees_in_block.is_empty() {
+        return Ok(None);
+    }
 
     // get stats by transaction
-    let dist_fee_by_index: Vec<FeePoint> = (0..=100)
-        .step_by(5)
-        .map(|p| {
-            let prio_fee = {
-                let index = prio_fees_in_block.len() * p / 100;
-                let cap_index = index.min(prio_fees_in_block.len().saturating_sub(1));
-                prio_fees_in_block[cap_index].priority
-            };
+    let dist_fee_by_index: Vec<FeePoint> = percentiles
+        .iter()
+        .map(|&p| {
+            let prio_fee = interpolate_percentile(&prio_fees_in_block, p);
             FeePoint {
                 percentile: p as u32,
                 fees: prio_fee,
@@ -37,65 +78,338 @@ pub fn calculate_supp_percentiles(
         .collect_vec();
 
     // get stats by CU
-    let cu_sum: u64 = prio_fees_in_block.iter().map(|x| x.cu_consumed).sum();
-    let mut agg: u64 = prio_fees_in_block[0].cu_consumed;
+    let dist_fee_by_cu = interpolate_percentiles_by_cu(&prio_fees_in_block, &percentiles);
+
+    // always calculated, independent of the requested percentiles
+    let median_by_tx = interpolate_percentile(&prio_fees_in_block, 50);
+    let cu_weighted = interpolate_percentiles_by_cu(
+        &prio_fees_in_block,
+        &[50, RECOMMENDED_FEE_PERCENTILE as u64],
+    );
+
+    Ok(Some(Percentiles::from_fee_points(
+        &dist_fee_by_index,
+        &dist_fee_by_cu,
+        median_by_tx,
+        cu_weighted[0].fees,
+        cu_weighted[1].fees,
+    )))
+}
+
+/// same as [calculate_supp_percentiles_with] but without sorting the transactions: fees are counted into
+/// a histogram with log-spaced buckets in O(n) and each percentile is read from the cumulative bucket counts
+/// in O(buckets); meant for large blocks where the full sort is too expensive
+///
+/// precision: a fee is replaced by the middle of its bucket, buckets are at most 1/32 of their lower bound wide;
+/// fees below 64 are exact
+/// - `by_tx` is off by at most 1/64 of the exact fee (+1 for rounding)
+/// - `by_cu` is off by at most 1/64 of the fee of the transaction covering the percentile; unlike the exact method
+///   it is not interpolated towards the fee of the previous transaction, which makes a difference for sparse fees only
+pub fn calculate_supp_percentiles_from_histogram(
+    // Vec(prioritization_fees, cu_consumed)
+    prio_fees_in_block: &[PrioFeesData],
+    percentiles: &[u8],
+    exclude_zero_fee: bool,
+) -> anyhow::Result<Option<Percentiles>> {
+    if let Some(invalid) = percentiles.iter().find(|p| **p > 100) {
+        anyhow::bail!("percentile {} is out of range 0..=100", invalid);
+    }
+    let percentiles = percentiles
+        .iter()
+        .sorted()
+        .dedup()
+        .map(|p| *p as u64)
+        .collect_vec();
+
+    let histogram = FeeHistogram::from_prio_fees(
+        prio_fees_in_block
+            .iter()
+            .filter(|data| !exclude_zero_fee || data.priority > 0),
+    );
+    if histogram.nb_transactions == 0 {
+        return Ok(None);
+    }
+
+    let dist_fee_by_index = percentiles
+        .iter()
+        .map(|&p| FeePoint {
+            percentile: p as u32,
+            fees: histogram.percentile(p),
+        })
+        .collect_vec();
+    let dist_fee_by_cu = percentiles
+        .iter()
+        .map(|&p| FeePoint {
+            percentile: p as u32,
+            fees: histogram.percentile_by_cu(p),
+        })
+        .collect_vec();
+
+    Ok(Some(Percentiles::from_fee_points(
+        &dist_fee_by_index,
+        &dist_fee_by_cu,
+        histogram.percentile(50),
+        histogram.percentile_by_cu(50),
+        histogram.percentile_by_cu(RECOMMENDED_FEE_PERCENTILE as u64),
+    )))
+}
+
+// transaction count and consumed CU per fee bucket
+struct FeeHistogram {
+    nb_transactions: u64,
+    cu_sum: u128,
+    buckets: Vec<(u64, u128)>,
+}
+
+impl FeeHistogram {
+    fn from_prio_fees<'a>(prio_fees: impl Iterator<Item = &'a PrioFeesData>) -> Self {
+        let mut histogram = FeeHistogram {
+            nb_transactions: 0,
+            cu_sum: 0,
+            buckets: vec![(0, 0); HISTOGRAM_BUCKETS],
+        };
+        for data in prio_fees {
+            let bucket = &mut histogram.buckets[histogram_bucket(data.priority)];
+            bucket.0 += 1;
+            bucket.1 += data.cu_consumed as u128;
+            histogram.nb_transactions += 1;
+            histogram.cu_sum += data.cu_consumed as u128;
+        }
+        histogram
+    }
+
+    // same interpolation as interpolate_percentile with the bucket values instead of the fees
+    fn percentile(&self, percentile: u64) -> u64 {
+        let rank_x100 = percentile * self.nb_transactions.saturating_sub(1);
+        let lower_rank = rank_x100 / 100;
+        let fraction_x100 = (rank_x100 % 100) as u128;
+        let upper_rank = (lower_rank + 1).min(self.nb_transactions - 1);
+
+        let lower = self.value_at_rank(lower_rank);
+        let upper = self.value_at_rank(upper_rank);
+        let delta = ((upper - lower) as u128 * fraction_x100 + 50) / 100;
+        lower + delta as u64
+    }
+
+    // value of the transaction at `rank` if all transactions were sorted by fee
+    fn value_at_rank(&self, rank: u64) -> u64 {
+        let mut cumulative = 0;
+        for (bucket, (count, _cu)) in self.buckets.iter().enumerate() {
+            cumulative += count;
+            if cumulative > rank {
+                return histogram_bucket_value(bucket);
+            }
+        }
+        unreachable!("rank must be less than the number of transactions")
+    }
+
+    // value of the first bucket with cumulative CU >= percentile; same weighting as interpolate_percentiles_by_cu
+    fn percentile_by_cu(&self, percentile: u64) -> u64 {
+        if self.cu_sum == 0 {
+            return self.percentile(percentile);
+        }
+        let target_x100 = self.cu_sum * percentile as u128;
+        let mut cumulative_x100 = 0;
+        for (bucket, (count, cu)) in self.buckets.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            cumulative_x100 += cu * 100;
+            if cumulative_x100 >= target_x100 {
+                return histogram_bucket_value(bucket);
+            }
+        }
+        unreachable!("cumulative CU must reach 100%")
+    }
+}
+
+fn histogram_bucket(fee: u64) -> usize {
+    if fee < HISTOGRAM_SUB_BUCKETS {
+        return fee as usize;
+    }
+    let exponent = 63 - fee.leading_zeros();
+    let shift = exponent - HISTOGRAM_SUB_BUCKET_BITS;
+    let sub_bucket = (fee >> shift) - HISTOGRAM_SUB_BUCKETS;
+    ((shift as u64 + 1) * HISTOGRAM_SUB_BUCKETS + sub_bucket) as usize
+}
+
+// middle of the fee range covered by the bucket
+fn histogram_bucket_value(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+    if bucket < HISTOGRAM_SUB_BUCKETS {
+        return bucket;
+    }
+    let shift = bucket / HISTOGRAM_SUB_BUCKETS - 1;
+    let sub_bucket = bucket % HISTOGRAM_SUB_BUCKETS;
+    let lower_bound = (HISTOGRAM_SUB_BUCKETS + sub_bucket) << shift;
+    let width = 1u64 << shift;
+    lower_bound + width / 2
+}
+
+/// percentile by linear interpolation between closest ranks (same as numpy default / Excel PERCENTILE.INC):
+/// rank = p/100 * (n-1); the fee is interpolated between the values at floor(rank) and ceil(rank)
+/// and rounded to the nearest integer; p50 of an even-length list is the average of the two middle values
+fn interpolate_percentile(sorted_prio_fees: &[PrioFeesData], percentile: u64) -> u64 {
+    let len = sorted_prio_fees.len() as u64;
+    // rank scaled by 100 to stay in integer arithmetic
+    let rank_x100 = percentile * len.saturating_sub(1);
+    let lower_index = (rank_x100 / 100) as usize;
+    let fraction_x100 = (rank_x100 % 100) as u128;
+    let upper_index = (lower_index + 1).min(sorted_prio_fees.len() - 1);
+
+    let lower = sorted_prio_fees[lower_index].priority;
+    let upper = sorted_prio_fees[upper_index].priority;
+    let delta = ((upper - lower) as u128 * fraction_x100 + 50) / 100;
+    lower + delta as u64
+}
+
+/// CU-weighted percentiles in one pass over the fee-sorted transactions:
+/// transaction i covers the cumulative CU fraction up to c_i/total; for percentile p the first
+/// transaction with c_i >= p * total is selected (percentiles exactly on a boundary belong to the lower fee),
+/// otherwise the fee is interpolated linearly between the boundaries of the previous and the selected transaction
+///
+/// if no CU were consumed at all, every transaction gets the same weight
+fn interpolate_percentiles_by_cu(
+    sorted_prio_fees: &[PrioFeesData],
+    percentiles: &[u64],
+) -> Vec<FeePoint> {
+    let cu_sum: u128 = sorted_prio_fees.iter().map(|x| x.cu_consumed as u128).sum();
+    if cu_sum == 0 {
+        return percentiles
+            .iter()
+            .map(|&percentile| FeePoint {
+                percentile: percentile as u32,
+                fees: interpolate_percentile(sorted_prio_fees, percentile),
+            })
+            .collect_vec();
+    }
+
     let mut index = 0;
-    let p_step = 5;
+    // cumulative CU (scaled by 100) up to and including transaction at index
+    let mut cumulative_x100 = sorted_prio_fees[0].cu_consumed as u128 * 100;
+    let mut previous_cumulative_x100 = 0;
 
-    let dist_fee_by_cu = (0..=100)
-        .step_by(p_step)
-        .map(|percentile| {
-            while agg < (cu_sum * percentile) / 100 {
+    percentiles
+        .iter()
+        .map(|&percentile| {
+            let target_x100 = cu_sum * percentile as u128;
+            while cumulative_x100 < target_x100 {
                 index += 1;
-                agg += prio_fees_in_block[index].cu_consumed;
+                previous_cumulative_x100 = cumulative_x100;
+                cumulative_x100 += sorted_prio_fees[index].cu_consumed as u128 * 100;
             }
-            let priority = prio_fees_in_block[index].priority;
+
+            let upper = sorted_prio_fees[index].priority;
+            let fees = if index == 0 || cumulative_x100 == target_x100 {
+                upper
+            } else {
+                let lower = sorted_prio_fees[index - 1].priority;
+                let span = cumulative_x100 - previous_cumulative_x100;
+                let fraction = target_x100 - previous_cumulative_x100;
+                lower + (((upper - lower) as u128 * fraction + span / 2) / span) as u64
+            };
+
             FeePoint {
                 percentile: percentile as u32,
-                fees: priority,
+                fees,
             }
         })
-        .collect_vec();
+        .collect_vec()
+}
 
-    Percentiles {
-        by_tx: dist_fee_by_index
-            .iter()
-            .map(|fee_point| fee_point.fees)
-            .collect_vec(),
-        by_tx_percentiles: dist_fee_by_index
-            .iter()
-            .map(|fee_point| fee_point.percentile as f32 / 100.0)
-            .collect_vec(),
-        by_cu: dist_fee_by_cu
-            .iter()
-            .map(|fee_point| fee_point.fees)
-            .collect_vec(),
-        by_cu_percentiles: dist_fee_by_cu
-            .iter()
-            .map(|fee_point| fee_point.percentile as f32 / 100.0)
-            .collect_vec(),
+/// same as [calculate_supp_percentiles] but separately for the (successful, failed) transactions
+pub fn calculate_supp_percentiles_by_outcome(
+    prio_fees_in_block: &[PrioFeesData],
+) -> (Option<Percentiles>, Option<Percentiles>) {
+    let (succeeded, failed): (Vec<PrioFeesData>, Vec<PrioFeesData>) =
+        prio_fees_in_block.iter().partition(|data| !data.failed);
+    (
+        calculate_supp_percentiles(&succeeded),
+        calculate_supp_percentiles(&failed),
+    )
+}
+
+/// the `top_n` transactions with the highest prioritization fees as (fee, cu_consumed), highest fee first;
+/// transactions with the same fee are ordered by cu_consumed descending
+pub fn calculate_top_fees(prio_fees_in_block: &[PrioFeesData], top_n: usize) -> Vec<(u64, u64)> {
+    prio_fees_in_block
+        .iter()
+        .map(|data| (data.priority, data.cu_consumed))
+        .sorted_by(|a, b| b.cmp(a))
+        .take(top_n)
+        .collect_vec()
+}
+
+/// fraction of `total_cu_consumed` used by the given transactions; 0.0 if no CU were consumed
+pub fn calculate_cu_share(fees: &[(u64, u64)], total_cu_consumed: u64) -> f32 {
+    if total_cu_consumed == 0 {
+        return 0.0;
     }
+    let cu_consumed: u64 = fees.iter().map(|(_fee, cu)| cu).sum();
+    cu_consumed as f32 / total_cu_consumed as f32
 }
 
+/// two distributions of the same fees: `by_tx` weights every transaction equally (index-based),
+/// `by_cu` weights every transaction by its consumed compute units
+#[derive(Default)]
 pub struct Percentiles {
     pub by_tx: Vec<u64>,
     pub by_tx_percentiles: Vec<f32>,
     pub by_cu: Vec<u64>,
     pub by_cu_percentiles: Vec<f32>,
+    /// index-based median: the fee of the middle transaction, no matter how many CU it consumed
+    pub median_by_tx: u64,
+    /// CU-weighted median: half of the consumed CU paid at most this fee; reflects the price of
+    /// block space better than [Self::median_by_tx] as a few large transactions can dominate a block
+    pub median_by_cu: u64,
+    // CU-weighted fee at RECOMMENDED_FEE_PERCENTILE
+    recommended_by_cu: u64,
 }
 
-#[allow(dead_code)]
 impl Percentiles {
-    fn get_fees_by_tx(&self, percentile: f32) -> Option<u64> {
-        zip(&self.by_tx_percentiles, &self.by_tx)
-            .find(|(&p, _cu)| p == percentile)
-            .map(|(_p, &cu)| cu)
+    fn from_fee_points(
+        dist_fee_by_index: &[FeePoint],
+        dist_fee_by_cu: &[FeePoint],
+        median_by_tx: u64,
+        median_by_cu: u64,
+        recommended_by_cu: u64,
+    ) -> Self {
+        Percentiles {
+            by_tx: dist_fee_by_index
+                .iter()
+                .map(|fee_point| fee_point.fees)
+                .collect_vec(),
+            by_tx_percentiles: dist_fee_by_index
+                .iter()
+                .map(|fee_point| fee_point.percentile as f32 / 100.0)
+                .collect_vec(),
+            by_cu: dist_fee_by_cu
+                .iter()
+                .map(|fee_point| fee_point.fees)
+                .collect_vec(),
+            by_cu_percentiles: dist_fee_by_cu
+                .iter()
+                .map(|fee_point| fee_point.percentile as f32 / 100.0)
+                .collect_vec(),
+            median_by_tx,
+            median_by_cu,
+            recommended_by_cu,
+        }
+    }
+
+    /// fee by transaction at percentile `p` (0..=100); None if the percentile was not calculated
+    pub fn fee_at_percentile(&self, p: u8) -> Option<u64> {
+        fee_at_percentile(&self.by_tx_percentiles, &self.by_tx, p)
     }
 
-    fn get_fees_cu(&self, percentile: f32) -> Option<u64> {
-        zip(&self.by_cu_percentiles, &self.by_cu)
-            .find(|(&p, _cu)| p == percentile)
-            .map(|(_p, &cu)| cu)
+    /// fee by compute units at percentile `p` (0..=100); None if the percentile was not calculated
+    pub fn fee_at_cu_percentile(&self, p: u8) -> Option<u64> {
+        fee_at_percentile(&self.by_cu_percentiles, &self.by_cu, p)
+    }
+
+    /// canonical fee recommendation: the CU-weighted p75, available whatever percentiles were requested
+    pub fn recommended_fee(&self) -> u64 {
+        self.recommended_by_cu
     }
 }
 
@@ -112,12 +426,42 @@ mod tests {
             PrioFeesData::from((3, 3)),
             PrioFeesData::from((1, 1)),
         ];
-        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).by_tx;
-        assert_eq!(supp_info[0], 1);
-        assert_eq!(supp_info[10], 3);
-        assert_eq!(supp_info[15], 4);
-        assert_eq!(supp_info[18], 5);
-        assert_eq!(supp_info[20], 5);
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        assert_eq!(supp_info.fee_at_percentile(0), Some(1));
+        assert_eq!(supp_info.fee_at_percentile(50), Some(3));
+        assert_eq!(supp_info.fee_at_percentile(75), Some(4));
+        assert_eq!(supp_info.fee_at_percentile(90), Some(5));
+        assert_eq!(supp_info.fee_at_percentile(100), Some(5));
+    }
+
+    #[test]
+    fn test_calculate_supp_info_by_outcome() {
+        let failed = |priority, cu_consumed| PrioFeesData {
+            priority,
+            cu_consumed,
+            failed: true,
+        };
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((10, 1000)),
+            failed(5000, 200),
+            PrioFeesData::from((30, 1000)),
+            failed(9000, 200),
+            PrioFeesData::from((20, 1000)),
+        ];
+
+        let (succeeded, failed) = calculate_supp_percentiles_by_outcome(&prio_fees_in_block);
+        let succeeded = succeeded.unwrap();
+        let failed = failed.unwrap();
+        assert_eq!(succeeded.fee_at_percentile(0), Some(10));
+        assert_eq!(succeeded.fee_at_percentile(50), Some(20));
+        assert_eq!(succeeded.fee_at_percentile(100), Some(30));
+        assert_eq!(failed.fee_at_percentile(0), Some(5000));
+        assert_eq!(failed.fee_at_percentile(100), Some(9000));
+
+        let (succeeded, failed) =
+            calculate_supp_percentiles_by_outcome(&[PrioFeesData::from((10, 1000))]);
+        assert!(succeeded.is_some());
+        assert!(failed.is_none());
     }
 
     #[test]
@@ -127,28 +471,80 @@ mod tests {
             PrioFeesData::from((100, 10000)),
             PrioFeesData::from((200, 10000)),
         ];
-        let Percentiles {
-            by_cu,
-            by_cu_percentiles,
-            ..
-        } = calculate_supp_percentiles(&prio_fees_in_block);
-        assert_eq!(by_cu_percentiles[10], 0.5);
-        assert_eq!(by_cu[10], 100); // need more than 100 to beat 50% of the CU
-        assert_eq!(by_cu[11], 200); // need more than 200 to beat 55% of the CU
-        assert_eq!(by_cu[20], 200); // need more than 200 to beat 100% of the CU
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        assert_eq!(supp_info.fee_at_cu_percentile(50), Some(100)); // 50% of the CU have a fee of 100 or less
+        assert_eq!(supp_info.fee_at_cu_percentile(55), Some(110)); // interpolated between 50% (100) and 100% (200)
+        assert_eq!(supp_info.fee_at_cu_percentile(100), Some(200)); // 100% of the CU have a fee of 200 or less
+    }
+
+    #[test]
+    fn test_by_cu_exact_boundary() {
+        // cumulative CU fractions: 0.25, 0.5, 1.0
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((20, 2500)),
+            PrioFeesData::from((10, 2500)),
+            PrioFeesData::from((30, 5000)),
+        ];
+        let supp_info =
+            calculate_supp_percentiles_with(&prio_fees_in_block, &[0, 25, 50, 75, 100], false)
+                .unwrap()
+                .unwrap();
+        assert_eq!(supp_info.by_cu, vec![10, 10, 20, 25, 30]);
+    }
+
+    #[test]
+    fn test_median_even_length() {
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((10, 1)),
+            PrioFeesData::from((20, 1)),
+            PrioFeesData::from((30, 1)),
+            PrioFeesData::from((40, 1)),
+        ];
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        assert_eq!(supp_info.fee_at_percentile(50), Some(25));
+        assert_eq!(supp_info.fee_at_percentile(0), Some(10));
+        assert_eq!(supp_info.fee_at_percentile(100), Some(40));
     }
 
     #[test]
     fn test_empty_array() {
         let prio_fees_in_block = vec![];
-        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).by_tx;
-        assert_eq!(supp_info[0], 0);
+        assert!(calculate_supp_percentiles(&prio_fees_in_block).is_none());
+    }
+
+    #[test]
+    fn test_exclude_zero_fee() {
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((0, 10000)),
+            PrioFeesData::from((0, 10000)),
+            PrioFeesData::from((0, 10000)),
+            PrioFeesData::from((100, 1000)),
+            PrioFeesData::from((200, 1000)),
+        ];
+        let with_zeros = calculate_supp_percentiles_with(&prio_fees_in_block, &[0, 50], false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(with_zeros.by_tx, vec![0, 0]);
+
+        let without_zeros = calculate_supp_percentiles_with(&prio_fees_in_block, &[0, 50], true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(without_zeros.by_tx, vec![100, 150]);
+        assert_eq!(without_zeros.fee_at_cu_percentile(0), Some(100));
+    }
+
+    #[test]
+    fn test_exclude_zero_fee_only_zeros() {
+        let prio_fees_in_block = vec![PrioFeesData::from((0, 10000))];
+        let supp_info = calculate_supp_percentiles_with(&prio_fees_in_block, &[50], true).unwrap();
+        assert!(supp_info.is_none());
     }
+
     #[test]
     fn test_zeros() {
         let prio_fees_in_block = vec![PrioFeesData::from((0, 0)), PrioFeesData::from((0, 0))];
-        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).by_cu;
-        assert_eq!(supp_info[0], 0);
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        assert_eq!(supp_info.fee_at_cu_percentile(0), Some(0));
     }
 
     #[test]
@@ -163,11 +559,10 @@ mod tests {
             PrioFeesData::from((68, 7)),
             PrioFeesData::from((72, 8)),
         ];
-        let supp_info = calculate_supp_percentiles(&prio_fees_in_block);
-        assert_eq!(supp_info.by_tx[5], 43);
-        assert_eq!(supp_info.by_tx_percentiles[5], 0.25);
-        assert_eq!(supp_info.by_cu_percentiles[20], 1.0);
-        assert_eq!(supp_info.by_cu[20], 72);
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        // rank 0.25 * 7 = 1.75 -> 33 + 0.75 * (43 - 33) = 40.5
+        assert_eq!(supp_info.fee_at_percentile(25), Some(41));
+        assert_eq!(supp_info.fee_at_cu_percentile(100), Some(72));
     }
 
     #[test]
@@ -186,21 +581,168 @@ mod tests {
             PrioFeesData::from((10, 9)),
         ];
 
-        let supp_info = calculate_supp_percentiles(&values);
+        let supp_info = calculate_supp_percentiles(&values).unwrap();
+
+        assert_eq!(supp_info.fee_at_percentile(25), Some(5));
+        assert_eq!(supp_info.fee_at_cu_percentile(95), Some(10));
+        assert_eq!(supp_info.fee_at_cu_percentile(100), Some(10));
+    }
+
+    #[test]
+    fn test_custom_percentiles() {
+        let prio_fees_in_block = (0..1000).map(|x| PrioFeesData::from((x, 1))).collect_vec();
+        let supp_info = calculate_supp_percentiles_with(&prio_fees_in_block, &[99, 25], false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(supp_info.by_tx_percentiles, vec![0.25, 0.99]);
+        assert_eq!(supp_info.by_tx, vec![250, 989]);
+        assert_eq!(supp_info.by_cu_percentiles, vec![0.25, 0.99]);
+        assert_eq!(supp_info.fee_at_percentile(25), Some(250));
+        assert_eq!(supp_info.fee_at_percentile(50), None);
+    }
+
+    #[test]
+    fn test_invalid_percentile() {
+        let prio_fees_in_block = vec![PrioFeesData::from((1, 1))];
+        assert!(calculate_supp_percentiles_with(&prio_fees_in_block, &[50, 101], false).is_err());
+    }
+
+    #[test]
+    fn test_top_fees() {
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((100, 20000)),
+            PrioFeesData::from((5000, 1000)),
+            PrioFeesData::from((0, 50000)),
+            PrioFeesData::from((250, 9000)),
+            PrioFeesData::from((5000, 3000)),
+            PrioFeesData::from((10, 17000)),
+        ];
+        let top_fees = calculate_top_fees(&prio_fees_in_block, 3);
+        assert_eq!(top_fees, vec![(5000, 3000), (5000, 1000), (250, 9000)]);
+        assert_eq!(calculate_cu_share(&top_fees, 100000), 0.13);
+
+        assert_eq!(calculate_top_fees(&prio_fees_in_block, 10).len(), 6);
+        assert!(calculate_top_fees(&[], 3).is_empty());
+        assert_eq!(calculate_cu_share(&top_fees, 0), 0.0);
+    }
+
+    #[test]
+    fn test_index_and_cu_median_diverge_for_skewed_block() {
+        // many small cheap transactions and one large expensive transaction
+        let prio_fees_in_block = vec![
+            PrioFeesData::from((1, 100)),
+            PrioFeesData::from((2, 100)),
+            PrioFeesData::from((3, 100)),
+            PrioFeesData::from((4, 100)),
+            PrioFeesData::from((100, 1_000_000)),
+        ];
+        let supp_info = calculate_supp_percentiles_with(&prio_fees_in_block, &[50], false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(supp_info.median_by_tx, 3);
+        assert_eq!(supp_info.median_by_cu, 52);
+        assert_eq!(
+            supp_info.fee_at_percentile(50),
+            Some(supp_info.median_by_tx)
+        );
+        assert_eq!(
+            supp_info.fee_at_cu_percentile(50),
+            Some(supp_info.median_by_cu)
+        );
+
+        // p75 was not requested but the recommendation is still the CU-weighted p75
+        assert_eq!(supp_info.fee_at_cu_percentile(75), None);
+        assert_eq!(supp_info.recommended_fee(), 76);
+        let all_percentiles = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        assert_eq!(
+            all_percentiles.fee_at_cu_percentile(RECOMMENDED_FEE_PERCENTILE),
+            Some(all_percentiles.recommended_fee())
+        );
+        assert_ne!(
+            all_percentiles.fee_at_percentile(RECOMMENDED_FEE_PERCENTILE),
+            Some(all_percentiles.recommended_fee())
+        );
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        for fee in [0, 1, 31, 32, 63] {
+            assert_eq!(histogram_bucket_value(histogram_bucket(fee)), fee);
+        }
+        // 64..=65 share a bucket
+        assert_eq!(histogram_bucket(64), histogram_bucket(65));
+        assert_eq!(histogram_bucket_value(histogram_bucket(64)), 65);
+        assert_eq!(histogram_bucket(u64::MAX), HISTOGRAM_BUCKETS - 1);
+        for fee in [64, 1000, 123_456, 98_765_432_101, u64::MAX] {
+            let value = histogram_bucket_value(histogram_bucket(fee));
+            assert!(value.abs_diff(fee) <= fee / 64, "{fee} -> {value}");
+        }
+    }
+
+    #[test]
+    fn test_histogram_percentiles_within_tolerance() {
+        // xorshift to get a reproducible spread of fees and CU
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let prio_fees_in_block = (0..20000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                PrioFeesData::from((state % 1_000_000, 1000 + (state >> 32) % 200_000))
+            })
+            .collect_vec();
+
+        let exact = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        let approx = calculate_supp_percentiles_from_histogram(
+            &prio_fees_in_block,
+            &DEFAULT_PERCENTILES,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(approx.by_tx_percentiles, exact.by_tx_percentiles);
+        for (approx_fee, exact_fee) in approx.by_tx.iter().zip(&exact.by_tx) {
+            assert!(
+                approx_fee.abs_diff(*exact_fee) <= exact_fee / 64 + 1,
+                "by_tx {approx_fee} vs {exact_fee}"
+            );
+        }
+        for (approx_fee, exact_fee) in approx.by_cu.iter().zip(&exact.by_cu) {
+            assert!(
+                approx_fee.abs_diff(*exact_fee) <= exact_fee / 32 + 1,
+                "by_cu {approx_fee} vs {exact_fee}"
+            );
+        }
+        assert!(approx.median_by_tx.abs_diff(exact.median_by_tx) <= exact.median_by_tx / 64 + 1);
+        assert!(
+            approx.recommended_fee().abs_diff(exact.recommended_fee())
+                <= exact.recommended_fee() / 32 + 1
+        );
 
-        assert_eq!(supp_info.by_tx_percentiles[4], 0.20);
-        assert_eq!(supp_info.by_tx[5], 5);
-        assert_eq!(supp_info.by_cu_percentiles[19], 0.95);
-        assert_eq!(supp_info.by_cu_percentiles[20], 1.0);
-        assert_eq!(supp_info.by_cu[19], 10);
-        assert_eq!(supp_info.by_cu[20], 10);
+        // small fees are exact
+        let small_fees = vec![
+            PrioFeesData::from((2, 2)),
+            PrioFeesData::from((4, 4)),
+            PrioFeesData::from((5, 5)),
+            PrioFeesData::from((3, 3)),
+            PrioFeesData::from((1, 1)),
+        ];
+        let approx =
+            calculate_supp_percentiles_from_histogram(&small_fees, &[0, 50, 75, 100], false)
+                .unwrap()
+                .unwrap();
+        assert_eq!(approx.by_tx, vec![1, 3, 4, 5]);
+        assert!(calculate_supp_percentiles_from_histogram(&[], &[50], false)
+            .unwrap()
+            .is_none());
     }
 
     #[test]
     fn test_large_list() {
         let prio_fees_in_block = (0..1000).map(|x| PrioFeesData::from((x, x))).collect_vec();
-        let supp_info = calculate_supp_percentiles(&prio_fees_in_block);
-        assert_eq!(supp_info.by_tx[19], 950);
-        assert_eq!(supp_info.by_tx_percentiles[19], 0.95);
+        let supp_info = calculate_supp_percentiles(&prio_fees_in_block).unwrap();
+        // rank 0.95 * 999 = 949.05
+        assert_eq!(supp_info.fee_at_percentile(95), Some(949));
     }
 }
@@ -512,6 +512,8 @@ async fn start_literpc_client_direct_mode(
         min_stakes: 0,
         max_stakes: 40,
         total_stakes: 100,
+        is_delinquent: false,
+        stake_percentile: 0.0,
     };
 
     // solana_streamer::nonblocking::quic: Peer type: Staked, stake 30, total stake 0, max streams 128 receive_window Ok(12320) from peer 127.0.0.1:8000
@@ -613,6 +615,8 @@ async fn start_literpc_client_proxy_mode(
         min_stakes: 0,
         max_stakes: 40,
         total_stakes: 100,
+        is_delinquent: false,
+        stake_percentile: 0.0,
     };
 
     // solana_streamer::nonblocking::quic: Peer type: Staked, stake 30, total stake 0, max streams 128 receive_window Ok(12320) from peer 127.0.0.1:8000
@@ -174,14 +174,17 @@ impl DataCachingService {
         let identity_stakes_jh = tokio::spawn(async move {
             let mut va_notification = va_notification;
             loop {
-                let vote_accounts = va_notification
+                let snapshot = va_notification
                     .recv()
                     .await
                     .context("Could not get vote accounts")?;
-                data_cache
+                if let Err(err) = data_cache
                     .identity_stakes
-                    .update_stakes_for_identity(vote_accounts)
-                    .await;
+                    .update_stakes_from_snapshot(&snapshot)
+                    .await
+                {
+                    warn!("{err:?}");
+                }
             }
         });
 
@@ -0,0 +1,237 @@
+use prometheus::{opts, register_int_gauge_vec, IntGaugeVec};
+use solana_lite_rpc_core::structures::identity_stakes::{IdentityStakesData, StakeCache};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+lazy_static::lazy_static! {
+    static ref ACTIVE_IDENTITY: IntGaugeVec =
+    register_int_gauge_vec!(opts!("literpc_active_identity", "1 for the identity used for the current send batch, 0 for the other configured identities"), &["identity"]).unwrap();
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentitySelectionStrategy {
+    /// next identity for every send batch
+    #[default]
+    RoundRobin,
+    /// identities are picked proportionally to their stake; unstaked identities only if no identity has stake
+    StakeWeighted,
+}
+
+/// picks one of the configured identities for each send batch
+#[derive(Clone)]
+pub struct IdentitySelector {
+    identities: Vec<Arc<Keypair>>,
+    strategy: IdentitySelectionStrategy,
+    stake_cache: StakeCache,
+    next_round_robin: Arc<AtomicUsize>,
+    // smooth weighted round robin state, one entry per identity
+    current_weights: Arc<Mutex<Vec<i128>>>,
+    active: Arc<AtomicUsize>,
+}
+
+impl IdentitySelector {
+    pub fn new(
+        identities: Vec<Arc<Keypair>>,
+        strategy: IdentitySelectionStrategy,
+        stake_cache: StakeCache,
+    ) -> Self {
+        assert!(!identities.is_empty(), "at least one identity is required");
+        for identity in &identities {
+            ACTIVE_IDENTITY
+                .with_label_values(&[&identity.pubkey().to_string()])
+                .set(0);
+        }
+        Self {
+            current_weights: Arc::new(Mutex::new(vec![0; identities.len()])),
+            identities,
+            strategy,
+            stake_cache,
+            next_round_robin: Arc::new(AtomicUsize::new(0)),
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// identity for the next send batch; becomes the active identity
+    pub fn next_identity(&self) -> Arc<Keypair> {
+        let index = match self.strategy {
+            IdentitySelectionStrategy::RoundRobin => self.next_round_robin_index(),
+            IdentitySelectionStrategy::StakeWeighted => self.next_stake_weighted_index(),
+        };
+
+        let previous = self.active.swap(index, Ordering::Relaxed);
+        ACTIVE_IDENTITY
+            .with_label_values(&[&self.identities[previous].pubkey().to_string()])
+            .set(0);
+        ACTIVE_IDENTITY
+            .with_label_values(&[&self.identities[index].pubkey().to_string()])
+            .set(1);
+
+        self.identities[index].clone()
+    }
+
+    /// identity returned by the last call to [Self::next_identity]; the first identity before that
+    pub fn active_identity(&self) -> Pubkey {
+        self.identities[self.active.load(Ordering::Relaxed)].pubkey()
+    }
+
+    /// stakes of one of the identities from the stake cache; fails if the stakes are stale
+    pub fn stakes(&self, identity: &Pubkey) -> anyhow::Result<IdentityStakesData> {
+        self.stake_cache.get_stakes(identity)
+    }
+
+    pub fn identities(&self) -> &[Arc<Keypair>] {
+        &self.identities
+    }
+
+    pub fn strategy(&self) -> IdentitySelectionStrategy {
+        self.strategy
+    }
+
+    pub fn len(&self) -> usize {
+        self.identities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+
+    fn next_round_robin_index(&self) -> usize {
+        self.next_round_robin.fetch_add(1, Ordering::Relaxed) % self.identities.len()
+    }
+
+    // smooth weighted round robin: interleaves the identities instead of picking one identity many times in a row
+    fn next_stake_weighted_index(&self) -> usize {
+        let stakes = self
+            .identities
+            .iter()
+            // identities with stale stakes are treated as unstaked
+            .map(|identity| {
+                self.stake_cache
+                    .get_stakes(&identity.pubkey())
+                    .map_or(0, |stakes| stakes.stakes as i128)
+            })
+            .collect::<Vec<_>>();
+        let total_stakes: i128 = stakes.iter().sum();
+        if total_stakes == 0 {
+            return self.next_round_robin_index();
+        }
+
+        let mut current_weights = self.current_weights.lock().unwrap();
+        for (current_weight, stake) in current_weights.iter_mut().zip(&stakes) {
+            *current_weight += stake;
+        }
+        let (index, _) = current_weights
+            .iter()
+            .enumerate()
+            .max_by(|(index_a, a), (index_b, b)| a.cmp(b).then(index_b.cmp(index_a)))
+            .expect("identities must not be empty");
+        current_weights[index] -= total_stakes;
+        index
+    }
+}
+
+/// identity of the current send batch: selected for the first transaction of a batch and kept until the batch ends
+#[derive(Debug, Default)]
+pub struct SendBatchIdentity {
+    identity: Option<Pubkey>,
+}
+
+impl SendBatchIdentity {
+    pub fn get_or_select(&mut self, next_identity: impl FnOnce() -> Pubkey) -> Pubkey {
+        *self.identity.get_or_insert_with(next_identity)
+    }
+
+    pub fn end_batch(&mut self) {
+        self.identity = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_rpc_client_api::response::{RpcVoteAccountInfo, RpcVoteAccountStatus};
+
+    fn identities(count: usize) -> Vec<Arc<Keypair>> {
+        (0..count).map(|_| Arc::new(Keypair::new())).collect()
+    }
+
+    fn vote_account(node_pubkey: &Pubkey, activated_stake: u64) -> RpcVoteAccountInfo {
+        RpcVoteAccountInfo {
+            vote_pubkey: Pubkey::new_unique().to_string(),
+            node_pubkey: node_pubkey.to_string(),
+            activated_stake,
+            commission: 0,
+            epoch_vote_account: true,
+            epoch_credits: vec![],
+            last_vote: 0,
+            root_slot: 0,
+        }
+    }
+
+    #[test]
+    fn test_round_robin_cycles_through_identities() {
+        let identities = identities(3);
+        let selector = IdentitySelector::new(
+            identities.clone(),
+            IdentitySelectionStrategy::RoundRobin,
+            StakeCache::new(),
+        );
+        assert_eq!(selector.active_identity(), identities[0].pubkey());
+
+        // 4 send batches of 2 transactions each
+        let mut batch_identity = SendBatchIdentity::default();
+        let mut selected = vec![];
+        for _batch in 0..4 {
+            for _transaction in 0..2 {
+                let identity = batch_identity.get_or_select(|| selector.next_identity().pubkey());
+                assert_eq!(selector.active_identity(), identity);
+                selected.push(identity);
+            }
+            batch_identity.end_batch();
+        }
+        let expected = [0, 0, 1, 1, 2, 2, 0, 0]
+            .iter()
+            .map(|index| identities[*index].pubkey())
+            .collect::<Vec<_>>();
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn test_stake_weighted_selection() {
+        let identities = identities(3);
+        let stake_cache = StakeCache::new();
+        stake_cache.update(&RpcVoteAccountStatus {
+            current: vec![
+                vote_account(&identities[0].pubkey(), 300),
+                vote_account(&identities[1].pubkey(), 100),
+            ],
+            delinquent: vec![],
+        });
+        let selector = IdentitySelector::new(
+            identities.clone(),
+            IdentitySelectionStrategy::StakeWeighted,
+            stake_cache,
+        );
+
+        let selected = (0..8)
+            .map(|_| selector.next_identity().pubkey())
+            .collect::<Vec<_>>();
+        let count = |index: usize| {
+            selected
+                .iter()
+                .filter(|pubkey| **pubkey == identities[index].pubkey())
+                .count()
+        };
+        assert_eq!(count(0), 6);
+        assert_eq!(count(1), 2);
+        // unstaked identity is never selected while others have stake
+        assert_eq!(count(2), 0);
+        // interleaved instead of all picks of identity 0 first
+        assert_eq!(selected[1], identities[0].pubkey());
+        assert_eq!(selected[2], identities[1].pubkey());
+    }
+}
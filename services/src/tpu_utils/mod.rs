@@ -1,5 +1,6 @@
 pub mod tpu_service;
 
+pub mod identity_selector;
 pub mod quic_proxy_connection_manager;
 pub mod quinn_auto_reconnect;
 pub mod tpu_connection_manager;
@@ -1,6 +1,7 @@
 use anyhow::Context;
 use prometheus::{core::GenericGauge, opts, register_int_gauge};
 
+use super::identity_selector::{IdentitySelectionStrategy, IdentitySelector};
 use super::tpu_connection_manager::TpuConnectionManager;
 use crate::quic_connection_utils::QuicConnectionParameters;
 use crate::tpu_utils::quic_proxy_connection_manager::QuicProxyConnectionManager;
@@ -9,17 +10,21 @@ use crate::tpu_utils::tpu_service::ConnectionManager::{DirectTpu, QuicProxy};
 
 use solana_lite_rpc_core::network_utils::log_gso_workaround;
 use solana_lite_rpc_core::stores::data_cache::DataCache;
+use solana_lite_rpc_core::structures::identity_stakes::{IdentityStakesData, StakeCache};
 use solana_lite_rpc_core::structures::transaction_sent_info::SentTransactionInfo;
 use solana_lite_rpc_core::traits::leaders_fetcher_interface::LeaderFetcherInterface;
 use solana_lite_rpc_core::types::SlotStream;
 use solana_lite_rpc_core::AnyhowJoinHandle;
-use solana_sdk::{quic::QUIC_PORT_OFFSET, signature::Keypair, slot_history::Slot};
+use solana_sdk::{
+    pubkey::Pubkey, quic::QUIC_PORT_OFFSET, signature::Keypair, signer::Signer, slot_history::Slot,
+};
 use solana_streamer::tls_certificates::new_self_signed_tls_certificate;
 use std::collections::HashMap;
 use std::{
     net::{IpAddr, Ipv4Addr},
     sync::Arc,
 };
+use tokio::sync::broadcast::Sender;
 
 lazy_static::lazy_static! {
     static ref NB_CLUSTER_NODES: GenericGauge<prometheus::core::AtomicI64> =
@@ -42,23 +47,33 @@ pub struct TpuServiceConfig {
 
 #[derive(Clone)]
 pub struct TpuService {
-    broadcast_sender: Arc<tokio::sync::broadcast::Sender<SentTransactionInfo>>,
     connection_manager: ConnectionManager,
     leader_schedule: Arc<dyn LeaderFetcherInterface>,
     config: TpuServiceConfig,
     data_cache: DataCache,
+    identity_selector: IdentitySelector,
 }
 
 #[derive(Clone)]
 enum ConnectionManager {
     DirectTpu {
-        tpu_connection_manager: Arc<TpuConnectionManager>,
+        // one connection manager per identity, all connected to the next leaders
+        identity_connections: Vec<IdentityConnections>,
     },
     QuicProxy {
+        broadcast_sender: Arc<Sender<SentTransactionInfo>>,
         quic_proxy_connection_manager: Arc<QuicProxyConnectionManager>,
     },
 }
 
+#[derive(Clone)]
+struct IdentityConnections {
+    identity: Pubkey,
+    tpu_connection_manager: Arc<TpuConnectionManager>,
+    // only the transactions sent with this identity
+    broadcast_sender: Arc<Sender<SentTransactionInfo>>,
+}
+
 impl TpuService {
     pub async fn new(
         config: TpuServiceConfig,
@@ -66,30 +81,67 @@ impl TpuService {
         leader_schedule: Arc<dyn LeaderFetcherInterface>,
         data_cache: DataCache,
     ) -> anyhow::Result<Self> {
-        let (sender, _) = tokio::sync::broadcast::channel(config.maximum_transaction_in_queue);
-        let (certificate, key) = new_self_signed_tls_certificate(
-            identity.as_ref(),
-            IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
-        )
-        .expect("Failed to initialize QUIC client certificates");
+        let identity_selector = IdentitySelector::new(
+            vec![identity],
+            IdentitySelectionStrategy::RoundRobin,
+            StakeCache::new(),
+        );
+        Self::new_with_identities(config, identity_selector, leader_schedule, data_cache).await
+    }
+
+    /// keeps connections to the next leaders for every identity and sends each batch of transactions
+    /// with the identity picked by `identity_selector`; the quic proxy path always uses the first identity
+    pub async fn new_with_identities(
+        config: TpuServiceConfig,
+        identity_selector: IdentitySelector,
+        leader_schedule: Arc<dyn LeaderFetcherInterface>,
+        data_cache: DataCache,
+    ) -> anyhow::Result<Self> {
+        let new_broadcast_sender = || {
+            let (sender, _) = tokio::sync::broadcast::channel(config.maximum_transaction_in_queue);
+            Arc::new(sender)
+        };
+        let new_certificate = |identity: &Keypair| {
+            new_self_signed_tls_certificate(identity, IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)))
+                .expect("Failed to initialize QUIC client certificates")
+        };
 
         log_gso_workaround();
 
         let connection_manager = match config.tpu_connection_path {
             TpuConnectionPath::QuicDirectPath => {
-                let tpu_connection_manager =
-                    TpuConnectionManager::new(certificate, key, config.fanout_slots as usize).await;
+                let mut identity_connections = vec![];
+                for identity in identity_selector.identities() {
+                    let (certificate, key) = new_certificate(identity.as_ref());
+                    let tpu_connection_manager =
+                        TpuConnectionManager::new(certificate, key, config.fanout_slots as usize)
+                            .await;
+                    identity_connections.push(IdentityConnections {
+                        identity: identity.pubkey(),
+                        tpu_connection_manager: Arc::new(tpu_connection_manager),
+                        broadcast_sender: new_broadcast_sender(),
+                    });
+                }
                 DirectTpu {
-                    tpu_connection_manager: Arc::new(tpu_connection_manager),
+                    identity_connections,
                 }
             }
             TpuConnectionPath::QuicForwardProxyPath {
                 forward_proxy_address,
             } => {
+                if identity_selector.len() > 1 {
+                    log::warn!(
+                        "Quic proxy connections use the first of {} identities only",
+                        identity_selector.len()
+                    );
+                }
+                let (certificate, key) =
+                    new_certificate(identity_selector.identities()[0].as_ref());
                 let quic_proxy_connection_manager =
                     QuicProxyConnectionManager::new(certificate, key, forward_proxy_address).await;
 
                 QuicProxy {
+                    broadcast_sender: new_broadcast_sender(),
                     quic_proxy_connection_manager: Arc::new(quic_proxy_connection_manager),
                 }
             }
@@ -97,18 +149,61 @@ impl TpuService {
 
         Ok(Self {
             leader_schedule,
-            broadcast_sender: Arc::new(sender),
             connection_manager,
             config,
             data_cache,
+            identity_selector,
         })
     }
 
-    pub fn send_transaction(&self, transaction: &SentTransactionInfo) -> anyhow::Result<()> {
-        self.broadcast_sender.send(transaction.clone())?;
+    /// identity of the current send batch
+    pub fn active_identity(&self) -> Pubkey {
+        self.identity_selector.active_identity()
+    }
+
+    /// select the identity for the next send batch
+    pub fn next_identity(&self) -> Pubkey {
+        self.identity_selector.next_identity().pubkey()
+    }
+
+    /// send with the connections of `identity` (see [Self::next_identity])
+    pub fn send_transaction(
+        &self,
+        transaction: &SentTransactionInfo,
+        identity: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let broadcast_sender = match &self.connection_manager {
+            DirectTpu {
+                identity_connections,
+            } => {
+                // connections of all identities are kept open so switching the identity is free
+                &identity_connections
+                    .iter()
+                    .find(|connections| connections.identity == *identity)
+                    .with_context(|| format!("No connections for identity {identity}"))?
+                    .broadcast_sender
+            }
+            QuicProxy {
+                broadcast_sender, ..
+            } => broadcast_sender,
+        };
+        broadcast_sender.send(transaction.clone())?;
         Ok(())
     }
 
+    // do not classify peers with outdated stakes - connect as unstaked instead
+    async fn identity_stakes(&self, identity: &Pubkey) -> IdentityStakesData {
+        let identity_stakes = if self.identity_selector.len() == 1 {
+            self.data_cache.identity_stakes.get_stakes().await
+        } else {
+            self.identity_selector.stakes(identity)
+        };
+        identity_stakes.unwrap_or_else(|err| {
+            log::warn!("{err:?} - connecting as unstaked");
+            IdentityStakesData::default()
+        })
+    }
+
     // update/reconfigure connections on slot change
     async fn update_quic_connections(
         &self,
@@ -147,22 +242,27 @@ impl TpuService {
 
         match &self.connection_manager {
             DirectTpu {
-                tpu_connection_manager,
+                identity_connections,
             } => {
-                tpu_connection_manager
-                    .update_connections(
-                        self.broadcast_sender.clone(),
-                        connections_to_keep,
-                        self.data_cache.identity_stakes.get_stakes().await,
-                        self.data_cache.clone(),
-                        self.config.quic_connection_params,
-                    )
-                    .await;
+                for connections in identity_connections {
+                    let identity_stakes = self.identity_stakes(&connections.identity).await;
+                    connections
+                        .tpu_connection_manager
+                        .update_connections(
+                            connections.broadcast_sender.clone(),
+                            connections_to_keep.clone(),
+                            identity_stakes,
+                            self.data_cache.clone(),
+                            self.config.quic_connection_params,
+                        )
+                        .await;
+                }
             }
             QuicProxy {
+                broadcast_sender,
                 quic_proxy_connection_manager,
             } => {
-                let transaction_receiver = self.broadcast_sender.subscribe();
+                let transaction_receiver = broadcast_sender.subscribe();
                 quic_proxy_connection_manager
                     .update_connection(
                         transaction_receiver,
@@ -25,25 +25,71 @@ pub struct TransactionReplay {
     pub replay_at: Instant,
 }
 
+/// delays between the resends of a transaction which is not confirmed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// delay before the first resend
+    pub retry_after: Duration,
+    pub max_retries: usize,
+    /// the delay is multiplied by this factor after every resend;
+    /// if None the delay grows linearly: retry_after, retry_after, retry_after*2, retry_after*3 ...
+    pub backoff_multiplier: Option<f64>,
+    /// upper bound for the delay between two resends
+    pub max_backoff: Option<Duration>,
+}
+
+impl RetryPolicy {
+    pub fn linear(retry_after: Duration, max_retries: usize) -> Self {
+        Self {
+            retry_after,
+            max_retries,
+            backoff_multiplier: None,
+            max_backoff: None,
+        }
+    }
+
+    /// delay before the next resend of a transaction which was already resent `replay_count` times
+    pub fn delay_before_replay(&self, replay_count: usize) -> Duration {
+        let factor = match self.backoff_multiplier {
+            Some(multiplier) => multiplier.powi(replay_count.min(i32::MAX as usize) as i32),
+            None => replay_count.max(1) as f64,
+        };
+        let mut delay_secs = self.retry_after.as_secs_f64() * factor;
+        if let Some(max_backoff) = self.max_backoff {
+            delay_secs = delay_secs.min(max_backoff.as_secs_f64());
+        }
+        Duration::try_from_secs_f64(delay_secs).unwrap_or(Duration::MAX)
+    }
+
+    /// delays before each resend of a transaction which never gets confirmed;
+    /// the replayer resends max_retries + 1 times as the first resend is not counted as a retry
+    pub fn resend_schedule(&self) -> Vec<Duration> {
+        (0..=self.max_retries)
+            .map(|replay_count| self.delay_before_replay(replay_count))
+            .collect()
+    }
+}
+
 /// Transaction Replayer
 /// It will replay transaction sent to the cluster if they are not confirmed
 /// They will be replayed max_replay times
-/// The replay time will be linearly increasing by after count * replay after
-/// So the transasctions will be replayed like retry_after, retry_after*2, retry_after*3 ...
+/// The delay between two replays is given by the [RetryPolicy]
 
 #[derive(Clone)]
 pub struct TransactionReplayer {
     pub tpu_service: TpuService,
     pub data_cache: DataCache,
     pub retry_offset: Duration,
+    pub retry_policy: RetryPolicy,
 }
 
 impl TransactionReplayer {
-    pub fn new(tpu_service: TpuService, data_cache: DataCache, retry_offset: Duration) -> Self {
+    pub fn new(tpu_service: TpuService, data_cache: DataCache, retry_policy: RetryPolicy) -> Self {
         Self {
             tpu_service,
             data_cache,
-            retry_offset,
+            retry_offset: retry_policy.retry_after,
+            retry_policy,
         }
     }
 
@@ -55,6 +101,7 @@ impl TransactionReplayer {
         let tpu_service = self.tpu_service.clone();
         let data_cache = self.data_cache.clone();
         let retry_offset = self.retry_offset;
+        let retry_policy = self.retry_policy;
 
         tokio::spawn(async move {
             while let Some(mut tx_replay) = reciever.recv().await {
@@ -74,12 +121,13 @@ impl TransactionReplayer {
                     continue;
                 }
                 // ignore reset error
-                let _ = tpu_service.send_transaction(&tx_replay.transaction);
+                let _ = tpu_service
+                    .send_transaction(&tx_replay.transaction, &tpu_service.active_identity());
 
                 if tx_replay.replay_count < tx_replay.max_replay {
                     tx_replay.replay_count += 1;
                     tx_replay.replay_at =
-                        Instant::now() + retry_offset.mul_f32(tx_replay.replay_count as f32);
+                        Instant::now() + retry_policy.delay_before_replay(tx_replay.replay_count);
                     sender.send(tx_replay).context("replay channel closed")?;
                     MESSAGES_IN_REPLAY_QUEUE.inc();
                 }
@@ -6,8 +6,10 @@ use itertools::Itertools;
 use log::{trace, warn};
 
 use prometheus::{core::GenericGauge, opts, register_int_counter, register_int_gauge, IntCounter};
+use solana_sdk::pubkey::Pubkey;
 use tokio::sync::mpsc::Receiver;
 
+use crate::tpu_utils::identity_selector::SendBatchIdentity;
 use crate::tpu_utils::tpu_service::TpuService;
 use solana_lite_rpc_core::{
     stores::{data_cache::DataCache, tx_store::TxProps},
@@ -47,7 +49,7 @@ impl TxSender {
     }
 
     /// retry enqued_tx(s)
-    async fn forward_txs(&self, transaction_info: &SentTransactionInfo) {
+    async fn forward_txs(&self, transaction_info: &SentTransactionInfo, identity: &Pubkey) {
         trace!("sending transaction {}", transaction_info.signature);
         self.data_cache.txs.insert(
             transaction_info.signature,
@@ -58,7 +60,10 @@ impl TxSender {
             },
         );
 
-        match self.tpu_service.send_transaction(transaction_info) {
+        match self
+            .tpu_service
+            .send_transaction(transaction_info, identity)
+        {
             Ok(_) => {
                 TXS_SENT.inc_by(1);
                 1
@@ -79,6 +84,7 @@ impl TxSender {
     ) -> AnyhowJoinHandle {
         tokio::spawn(async move {
             let mut notifications = vec![];
+            let mut batch_identity = SendBatchIdentity::default();
             let mut interval = tokio::time::interval(INTERVAL_PER_BATCH_IN_MS);
             let notify_transaction_messages = |notifications: &mut Vec<TransactionNotification>| {
                 if notifications.is_empty() {
@@ -97,7 +103,9 @@ impl TxSender {
                 tokio::select! {
                     transaction_info = recv.recv() => {
                         if let Some(transaction_info) = transaction_info {
-                            self.forward_txs(&transaction_info).await;
+                            let identity =
+                                batch_identity.get_or_select(|| self.tpu_service.next_identity());
+                            self.forward_txs(&transaction_info, &identity).await;
 
                             if notifier.is_some() {
                                 let forwarded_slot = self.data_cache.slot_cache.get_current_slot();
@@ -123,6 +131,7 @@ impl TxSender {
                     },
                     _ = interval.tick() => {
                         notify_transaction_messages(&mut notifications);
+                        batch_identity.end_batch();
                     }
                 }
             }
@@ -125,5 +125,28 @@ test('send and confirm transaction', async () => {
 //     }
 // });
 
+test('get recent prioritization fees per slot', async () => {
+    const fees = await connection.getRecentPrioritizationFees();
+    expect(fees.length).toBeGreaterThan(0);
+    for (const fee of fees) {
+        expect(fee.slot).toBeGreaterThan(0);
+        expect(fee.prioritizationFee).toBeGreaterThanOrEqual(0);
+    }
+});
 
-
+test('get lite rpc prio fees stats', async () => {
+    const response = await (connection as any)._rpcRequest('getLiteRpcPrioFeesStats', [10]);
+    expect(response.error).toBeUndefined();
+    const { version, stats, feeTiers } = response.result.value;
+    expect(response.result.context.slot).toBeGreaterThan(0);
+    expect(version).toEqual(2);
+    expect(stats.distFeeByIndex.length).toEqual(stats.distFeeByCu.length);
+    for (const point of stats.distFeeByIndex) {
+        expect(point.fee).toBeGreaterThanOrEqual(0);
+    }
+    if (feeTiers !== null) {
+        expect(feeTiers.low).toBeLessThanOrEqual(feeTiers.medium);
+        expect(feeTiers.medium).toBeLessThanOrEqual(feeTiers.high);
+        expect(feeTiers.high).toBeLessThanOrEqual(feeTiers.max);
+    }
+});